@@ -1,3 +1,7 @@
+use crate::difficulty::DifficultyCurve;
+use crate::level::ldtk::LdtkProject;
+use crate::level::meta::LevelMetadata;
+use crate::stats::Achievements;
 use bevy::asset::*;
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::Aseprite;
@@ -14,6 +18,22 @@ pub struct GameAssets {
     #[asset(path = "image/level.aseprite")]
     pub level: Handle<Aseprite>,
 
+    /// level.aseprite のスライスを上書きする、LDtk で編集されたレベルです
+    #[asset(path = "level.ldtk")]
+    pub ldtk: Handle<LdtkProject>,
+
+    /// レベルごとの名前、BGM、明るさなどの設定です
+    #[asset(path = "level.meta.json")]
+    pub level_meta: Handle<LevelMetadata>,
+
+    /// エンドレスモードでの難易度の上昇率です
+    #[asset(path = "difficulty.curve.json")]
+    pub difficulty_curve: Handle<DifficultyCurve>,
+
+    /// 実績の一覧です
+    #[asset(path = "game.achievements.json")]
+    pub achievements: Handle<Achievements>,
+
     #[asset(path = "image/title.aseprite")]
     pub title: Handle<Aseprite>,
 
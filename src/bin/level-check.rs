@@ -0,0 +1,129 @@
+// レベルのデータを検査するツールです
+// level.aseprite の levelN と multiplay_arena のスライス、および level.ldtk のすべてのレベルを読み込み、
+// 地図を表示したうえで問題があれば報告します
+// 問題がひとつでもあれば終了コード 1 で終了するので、レベルを編集したあとのチェックに使えます
+//
+// cargo run --bin level-check
+
+use aseprite_loader::loader::AsepriteFile;
+use magiaforge::level::check::{check_chunk, render_ascii};
+use magiaforge::level::ldtk::{ldtk_to_tilemap, LdtkProject};
+use magiaforge::level::map::image_to_tilemap;
+use std::path::Path;
+use std::process::ExitCode;
+
+const LEVEL_ASEPRITE: &str = "assets/image/level.aseprite";
+
+const LEVEL_LDTK: &str = "assets/level.ldtk";
+
+/// 検査の対象になるスライスかどうかを返します
+fn is_level_slice(name: &str) -> bool {
+    name == "multiplay_arena"
+        || name
+            .strip_prefix("level")
+            .map_or(false, |n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// 問題を表示し、問題の数を返します
+fn report(name: &str, ascii: &str, issues: &[String]) -> usize {
+    println!("== {} ==", name);
+    print!("{}", ascii);
+    if issues.is_empty() {
+        println!("ok");
+    }
+    for issue in issues.iter() {
+        println!("error: {}: {}", name, issue);
+    }
+    println!();
+    issues.len()
+}
+
+fn check_aseprite(path: &Path) -> Result<usize, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let file = AsepriteFile::load(&bytes).map_err(|e| format!("{}: {:?}", path.display(), e))?;
+
+    // レベルの画像は1フレームだけで構成されています
+    let (width, height) = file.size();
+    let mut data = vec![0; width as usize * height as usize * 4];
+    file.combined_frame_image(0, &mut data)
+        .map_err(|e| format!("{}: {:?}", path.display(), e))?;
+
+    let mut slices: Vec<_> = file
+        .slices()
+        .iter()
+        .filter(|s| is_level_slice(&s.name))
+        .collect();
+    slices.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut errors = 0;
+    for slice in slices {
+        let Some(key) = slice.slice_keys.first() else {
+            continue;
+        };
+        let (chunk, issues) = image_to_tilemap(
+            &data,
+            width as u32,
+            key.x,
+            key.x + key.width as i32,
+            key.y,
+            key.y + key.height as i32,
+        );
+        let issues: Vec<String> = issues
+            .iter()
+            .chain(check_chunk(&chunk).iter())
+            .map(|i| i.to_string())
+            .collect();
+        errors += report(&slice.name, &render_ascii(&chunk), &issues);
+    }
+    Ok(errors)
+}
+
+fn check_ldtk(path: &Path) -> Result<usize, String> {
+    // LDtk のプロジェクトは任意です
+    if !path.exists() {
+        return Ok(0);
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let project: LdtkProject =
+        serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut errors = 0;
+    for level in project.levels.iter() {
+        let name = format!("{} (ldtk)", level.identifier);
+        errors += match ldtk_to_tilemap(level) {
+            Ok(chunk) => {
+                let issues: Vec<String> =
+                    check_chunk(&chunk).iter().map(|i| i.to_string()).collect();
+                report(&name, &render_ascii(&chunk), &issues)
+            }
+            Err(err) => report(&name, "", &[err]),
+        };
+    }
+    Ok(errors)
+}
+
+fn main() -> ExitCode {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let mut errors = 0;
+    for result in [
+        check_aseprite(&root.join(LEVEL_ASEPRITE)),
+        check_ldtk(&root.join(LEVEL_LDTK)),
+    ] {
+        match result {
+            Ok(count) => errors += count,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                errors += 1;
+            }
+        }
+    }
+
+    if 0 < errors {
+        eprintln!("{} error(s) found", errors);
+        ExitCode::FAILURE
+    } else {
+        println!("all levels ok");
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,87 @@
+// マルチプレイの開発やテストのためにローカルで動かす中継サーバーです
+// 同じルーム(接続URLのパス)にいる他のクライアントへ、受信したメッセージをそのまま転送します
+//
+// cargo run --features server --bin magia-server -- --port 8080 --latency 100 --loss 0.05
+//
+// --port     待ち受けるポート番号です。省略すると 8080 です
+// --latency  転送するまでに加える遅延(ミリ秒)です。省略すると 0 です
+// --loss     転送するメッセージを破棄する確率(0.0〜1.0)です。省略すると 0.0 です
+
+#[cfg(not(target_arch = "wasm32"))]
+use magiaforge::relay::{run_relay, RelayConfig};
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::ExitCode;
+
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_PORT: u16 = 8080;
+
+/// コマンドライン引数を読み込みます
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_args() -> Result<(u16, RelayConfig), String> {
+    let mut port = DEFAULT_PORT;
+    let mut config = RelayConfig::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "--port" => {
+                port = value
+                    .parse()
+                    .map_err(|_| format!("invalid port: {}", value))?;
+            }
+            "--latency" => {
+                let millis: u64 = value
+                    .parse()
+                    .map_err(|_| format!("invalid latency: {}", value))?;
+                config.latency = std::time::Duration::from_millis(millis);
+            }
+            "--loss" => {
+                let loss: f32 = value
+                    .parse()
+                    .map_err(|_| format!("invalid loss: {}", value))?;
+                if !(0.0..=1.0).contains(&loss) {
+                    return Err(format!("loss must be between 0.0 and 1.0: {}", value));
+                }
+                config.loss = loss;
+            }
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    Ok((port, config))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[tokio::main]
+async fn main() -> ExitCode {
+    let (port, config) = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let listener = match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("error: failed to bind port {}: {}", port, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "magia-server listening on ws://localhost:{} (latency {} ms, loss {})",
+        port,
+        config.latency.as_millis(),
+        config.loss
+    );
+    if let Err(e) = run_relay(listener, config).await {
+        eprintln!("error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// 中継サーバーはブラウザでは動かないので、wasm では何もしません
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -1,5 +1,7 @@
 use crate::constant::CAMERA_SPEED;
 use crate::entity::actor::Actor;
+use crate::level::meta::LevelMetadata;
+use crate::level::CurrentLevel;
 use crate::{controller::player::Player, states::GameState};
 use bevy::core::FrameCount;
 use bevy::prelude::*;
@@ -15,8 +17,6 @@ pub struct GameCamera {
     pub target: Option<Entity>,
 }
 
-static BLIGHTNESS_IN_GAME: f32 = 0.01;
-
 fn setup_camera(mut commands: Commands) {
     let initial_scale_factor = -1.0;
 
@@ -39,9 +39,10 @@ fn setup_camera(mut commands: Commands) {
         },
         // カメラにAmbiendLight2dを追加すると、画面全体が暗くなり、
         // 光が当たっていない部分の明るさを設定できます
+        // ゲーム中はレベルの設定に合わせて変更されます
         AmbientLight2d {
             color: Color::WHITE,
-            brightness: BLIGHTNESS_IN_GAME,
+            brightness: 0.01,
         },
     ));
 }
@@ -100,15 +101,16 @@ fn update_camera_position(
     }
 }
 
+/// プレイヤーのいるレベルの設定に合わせて、光が当たっていない部分の明るさと色を変更します
 fn update_camera_brightness(
     mut camera_query: Query<&mut AmbientLight2d, With<Camera2d>>,
-    state: Res<State<GameState>>,
+    current: Res<CurrentLevel>,
+    metadata: Res<LevelMetadata>,
 ) {
     if let Ok(mut light) = camera_query.get_single_mut() {
-        light.brightness = match state.get() {
-            GameState::InGame => BLIGHTNESS_IN_GAME,
-            _ => 1.0,
-        };
+        let meta = metadata.get(current.level.unwrap_or(current.next_level));
+        light.brightness = meta.brightness;
+        light.color = meta.ambient_color();
     }
 }
 
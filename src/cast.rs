@@ -1,10 +1,11 @@
 use crate::{
     asset::GameAssets,
     constant::{
-        ENEMY_BULLET_GROUP, ENEMY_GROUP, ENTITY_GROUP, MAX_SPELLS_IN_WAND, RABBIT_GROUP,
+        ENEMY_BULLET_GROUP, ENEMY_GROUP, ENTITY_GROUP, MAX_SPELLS_IN_WAND, RABBIT_GROUP, TILE_SIZE,
         WALL_GROUP, WITCH_BULLET_GROUP, WITCH_GROUP,
     },
     controller::remote::{send_remote_message, RemoteMessage},
+    difficulty::Difficulty,
     entity::{
         actor::{Actor, ActorGroup},
         bullet::{spawn_bullet, SpawnBullet, BULLET_SPAWNING_MARGIN},
@@ -12,8 +13,10 @@ use crate::{
         servant_seed::SpawnServantSeed,
         witch::WITCH_COLLIDER_RADIUS,
     },
+    level::{map::LevelChunk, modify::SetTileEvent, tile::Tile, GameLevel},
     se::{SEEvent, SE},
     spell::{SpellCast, SpellType},
+    stats::StatsEvent,
 };
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::ExternalImpulse;
@@ -36,8 +39,14 @@ pub fn cast_spell(
     writer: &mut EventWriter<ClientMessage>,
     se_writer: &mut EventWriter<SEEvent>,
     slime_writer: &mut EventWriter<SpawnServantSeed>,
+    stats_writer: &mut EventWriter<StatsEvent>,
+    tile_writer: &mut EventWriter<SetTileEvent>,
     wand_index: usize,
+    difficulty: &Difficulty,
+    level: Option<GameLevel>,
+    chunk: Option<&LevelChunk>,
 ) {
+    let caster = actor.uuid;
     if let Some(ref mut wand) = &mut actor.wands[wand_index] {
         // 1フレームあたりの残りの呪文詠唱回数
         // MultipleCast で増加することがあります
@@ -54,6 +63,11 @@ pub fn cast_spell(
                 wand.delay += props.cast_delay.max(1);
                 multicast -= 1;
 
+                stats_writer.send(StatsEvent::Cast {
+                    caster,
+                    spell: spell.spell_type,
+                });
+
                 match props.cast {
                     SpellCast::Bullet {
                         slice,
@@ -75,6 +89,13 @@ pub fn cast_spell(
                         let bullet_position =
                             actor_transform.translation.truncate() + range * normalized;
 
+                        // 敵の弾丸のダメージは深度に応じて増加します
+                        let damage = damage + actor.effects.bullet_damage_buff_amount;
+                        let damage = match actor.actor_group {
+                            ActorGroup::Player => damage,
+                            ActorGroup::Enemy => difficulty.scale_damage(damage),
+                        };
+
                         let spawn = SpawnBullet {
                             uuid: Uuid::new_v4(),
                             position: bullet_position,
@@ -83,7 +104,7 @@ pub fn cast_spell(
                                 * (1.0 + actor.effects.bullet_speed_buff_factor),
                             bullet_lifetime: lifetime,
                             sender: Some(actor.uuid),
-                            damage: damage + actor.effects.bullet_damage_buff_amount,
+                            damage,
                             impulse,
                             slice: slice.to_string(),
                             collier_radius,
@@ -106,7 +127,20 @@ pub fn cast_spell(
                         spawn_bullet(commands, assets.atlas.clone(), se_writer, &spawn);
                         actor.effects = default();
 
-                        send_remote_message(writer, online, &RemoteMessage::Fire(spawn));
+                        // 位置はチャンクの左上を原点とする座標で通知します
+                        if let (Some(level), Some(chunk)) = (level, chunk) {
+                            send_remote_message(
+                                writer,
+                                online,
+                                &RemoteMessage::Fire {
+                                    level,
+                                    bullet: SpawnBullet {
+                                        position: spawn.position - chunk.origin(),
+                                        ..spawn
+                                    },
+                                },
+                            );
+                        }
                     }
                     SpellCast::BulletSpeedUpDown { delta } => {
                         actor.effects.bullet_speed_buff_factor =
@@ -164,6 +198,21 @@ pub fn cast_spell(
                             actor_transform.translation.truncate(),
                         ));
                     }
+                    SpellCast::Bridge { length } => {
+                        if let Some(chunk) = chunk {
+                            build_bridge(
+                                chunk,
+                                tile_writer,
+                                actor_transform.translation.truncate(),
+                                actor.pointer.normalize_or_zero(),
+                                length,
+                            );
+                        }
+                        se_writer.send(SEEvent::pos(
+                            SE::Break,
+                            actor_transform.translation.truncate(),
+                        ));
+                    }
                 }
             } else {
                 // 空欄の場合は残り詠唱回数は減りません
@@ -176,3 +225,45 @@ pub fn cast_spell(
         wand.index %= MAX_SPELLS_IN_WAND;
     }
 }
+
+/// 指定した位置から方向に沿ってタイルをたどり、水や溶岩、穴を石の床に変えます
+/// 壁に突き当たるか、長さの上限に達するとそこで止まります
+fn build_bridge(
+    chunk: &LevelChunk,
+    tile_writer: &mut EventWriter<SetTileEvent>,
+    from: Vec2,
+    direction: Vec2,
+    length: i32,
+) {
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let mut last: Option<(i32, i32)> = None;
+    let mut built = 0;
+    // 斜め方向でもタイルを飛ばさないよう、半タイルずつ進めます
+    for step in 1..=length * 4 {
+        let position = from + direction * TILE_SIZE * 0.5 * step as f32;
+        let x = (position.x / TILE_SIZE).floor() as i32;
+        let y = (-position.y / TILE_SIZE).floor() as i32;
+        if last == Some((x, y)) {
+            continue;
+        }
+        last = Some((x, y));
+        match chunk.get_tile(x, y) {
+            Tile::Water | Tile::Lava | Tile::Pit => {
+                tile_writer.send(SetTileEvent {
+                    x,
+                    y,
+                    tile: Tile::StoneTile,
+                    remote: false,
+                });
+                built += 1;
+                if length <= built {
+                    return;
+                }
+            }
+            Tile::StoneTile | Tile::Ice => {}
+            Tile::Wall | Tile::CrackedWall | Tile::Blank => return,
+        }
+    }
+}
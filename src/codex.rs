@@ -0,0 +1,199 @@
+use crate::entity::dropped_item::DroppedItemEntity;
+use crate::equipment::EquipmentType;
+use crate::inventory_item::InventoryItemType;
+use crate::language::Dict;
+use crate::save::{SaveSlot, SaveSlots};
+use crate::spell::SpellType;
+use crate::states::GameState;
+use crate::wand::WandType;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+/// 周回をまたいで引き継がれる図鑑の記録です
+/// セーブスロットごとに保存されます
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Codex {
+    /// 床に落ちているのを見かけたり、商品として並んでいるのを見かけたアイテムです
+    #[serde(default)]
+    pub seen: Vec<InventoryItemType>,
+
+    /// 拾ったり買ったりして手に入れたアイテムです
+    #[serde(default)]
+    pub acquired: Vec<InventoryItemType>,
+
+    /// ボスを倒した回数です
+    #[serde(default)]
+    pub bosses_defeated: u32,
+}
+
+impl Codex {
+    pub fn is_seen(&self, item: InventoryItemType) -> bool {
+        self.seen.contains(&item) || self.is_acquired(item)
+    }
+
+    pub fn is_acquired(&self, item: InventoryItemType) -> bool {
+        self.acquired.contains(&item)
+    }
+}
+
+/// アイテムがドロップの候補に入るための条件です
+#[derive(Clone, Copy, Debug)]
+pub enum Unlock {
+    /// ボスを一度でも倒すと解放されます
+    Boss,
+
+    /// エンドレスモードで指定した深度に到達すると解放されます
+    Depth(i32),
+}
+
+impl Unlock {
+    pub fn is_satisfied(&self, slot: &SaveSlot) -> bool {
+        match self {
+            Unlock::Boss => 0 < slot.codex.bosses_defeated,
+            Unlock::Depth(depth) => *depth <= slot.best_depth,
+        }
+    }
+
+    pub fn hint(&self) -> Dict<String> {
+        match self {
+            Unlock::Boss => Dict {
+                ja: "ボスを倒すと解放されます".to_string(),
+                en: "Unlocked by defeating a boss".to_string(),
+            },
+            Unlock::Depth(depth) => Dict {
+                ja: format!("深度{}に到達すると解放されます", depth),
+                en: format!("Unlocked by reaching depth {}", depth),
+            },
+        }
+    }
+}
+
+/// 解放されるまでドロップや敵の杖に現れないアイテムです
+/// ここにないアイテムは最初から解放されています
+/// 弾丸の呪文、杖、装備がそれぞれひとつ以上は解放された状態で残るようにしてください
+const UNLOCKS: [(InventoryItemType, Unlock); 5] = [
+    (InventoryItemType::Spell(SpellType::HeavyShot), Unlock::Boss),
+    (
+        InventoryItemType::Spell(SpellType::SummonFriendEyeball),
+        Unlock::Boss,
+    ),
+    (
+        InventoryItemType::Spell(SpellType::TripleCast),
+        Unlock::Depth(5),
+    ),
+    (
+        InventoryItemType::Equipment(EquipmentType::Telescope),
+        Unlock::Depth(6),
+    ),
+    (InventoryItemType::Wand(WandType::KeyWand), Unlock::Depth(7)),
+];
+
+pub fn unlock_of(item: InventoryItemType) -> Option<Unlock> {
+    UNLOCKS
+        .iter()
+        .find(|(i, _)| *i == item)
+        .map(|(_, unlock)| *unlock)
+}
+
+/// スロットの記録で、まだ解放されていないアイテムの一覧を返します
+pub fn locked_items(slot: &SaveSlot) -> Vec<InventoryItemType> {
+    UNLOCKS
+        .iter()
+        .filter(|(_, unlock)| !unlock.is_satisfied(slot))
+        .map(|(item, _)| *item)
+        .collect()
+}
+
+/// 図鑑に載るすべてのアイテムを、杖、呪文、装備の順に返します
+pub fn all_items() -> Vec<InventoryItemType> {
+    WandType::iter()
+        .map(InventoryItemType::Wand)
+        .chain(SpellType::iter().map(InventoryItemType::Spell))
+        .chain(EquipmentType::iter().map(InventoryItemType::Equipment))
+        .collect()
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub enum CodexEvent {
+    /// プレイヤーがアイテムを拾いました
+    /// 商品の場合は、拾った時点で購入したものとして扱います
+    Acquire(InventoryItemType),
+
+    DefeatBoss,
+}
+
+/// 図鑑の記録を選択中のスロットに書き込みます
+/// 変更がないときにスロットを保存しないよう、新しい記録があるときだけ書き込みます
+fn record_codex(
+    item_query: Query<&DroppedItemEntity, Added<DroppedItemEntity>>,
+    mut reader: EventReader<CodexEvent>,
+    mut slots: ResMut<SaveSlots>,
+) {
+    for item in item_query.iter() {
+        let item_type = item.item.item_type;
+        if !slots.current().codex.is_seen(item_type) {
+            slots.current_mut().codex.seen.push(item_type);
+        }
+    }
+
+    for event in reader.read() {
+        match *event {
+            CodexEvent::Acquire(item_type) => {
+                if !slots.current().codex.is_acquired(item_type) {
+                    slots.current_mut().codex.acquired.push(item_type);
+                }
+            }
+            CodexEvent::DefeatBoss => {
+                slots.current_mut().codex.bosses_defeated += 1;
+            }
+        }
+    }
+}
+
+pub struct CodexPlugin;
+
+impl Plugin for CodexPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CodexEvent>();
+        app.add_systems(Update, record_codex.run_if(in_state(GameState::InGame)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::spell::SpellCast;
+
+    fn is_unlocked_from_start(item: InventoryItemType) -> bool {
+        unlock_of(item).is_none()
+    }
+
+    #[test]
+    fn leave_items_unlocked_from_start() {
+        assert!(SpellType::iter().any(|s| {
+            matches!(s.to_props().cast, SpellCast::Bullet { .. })
+                && is_unlocked_from_start(InventoryItemType::Spell(s))
+        }));
+        assert!(WandType::iter().any(|w| is_unlocked_from_start(InventoryItemType::Wand(w))));
+        assert!(
+            EquipmentType::iter().any(|e| is_unlocked_from_start(InventoryItemType::Equipment(e)))
+        );
+    }
+
+    #[test]
+    fn choose_items_even_if_all_locked() {
+        let difficulty = Difficulty {
+            life: 1.0,
+            damage: 1.0,
+            spawns: 1.0,
+            price: 1.0,
+            rarity: 0.0,
+            locked: all_items(),
+        };
+        difficulty.choose_spell();
+        difficulty.choose_equipment();
+        assert_eq!(difficulty.choose_spell_from(&[SpellType::MagicBolt]), None);
+    }
+}
@@ -2,42 +2,249 @@ use crate::{constant::*, language::*};
 use bevy::prelude::*;
 use bevy_pkv::PkvStore;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
+/// 設定の形式のバージョンです
+/// 形式を変更したときはこの値を増やし、MIGRATIONS にひとつ前の形式から変換する関数を追加します
+const CONFIG_VERSION: u32 = 2;
+
+/// MIGRATIONS[i] はバージョン i + 1 の設定をバージョン i + 2 の形式に変換します
+const MIGRATIONS: [fn(&mut Map<String, Value>); (CONFIG_VERSION - 1) as usize] =
+    [migrate_profile_to_slot];
+
+/// 設定を保存するキーです
+/// セーブスロットの読み込みでも、バージョン1の設定からプレイヤー名と最深記録を引き継ぐために読み込みます
+pub const CONFIG_KEY: &str = "config";
+
+/// 読み込めなかった設定を退避しておくキーです
+const CONFIG_BACKUP_KEY: &str = "config_backup";
+
+/// 省略されたフィールドは Default の値で補われるので、フィールドを追加しても古い設定を読み込めます
 #[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameConfig {
+    /// バージョンのフィールドがない設定はバージョン1として扱われます
+    pub version: u32,
     pub bgm_volume: f32,
     pub se_volume: f32,
-    pub player_name: String,
     pub language: Languages,
     pub fullscreen: bool,
+
+    /// マルチプレイで接続するサーバーのURLです
+    pub server_url: String,
+
+    /// マルチプレイのルームコードです
+    /// 同じルームコードを入力したプレイヤー同士が同じアリーナで遊びます
+    /// 空の場合は全員共通のロビーに入ります
+    pub room: String,
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             bgm_volume: DEFAULT_BGM_VOLUME,
             se_volume: DEFAULT_SE_VOLUME,
-            player_name: "".to_string(),
             language: Languages::Ja,
             fullscreen: false,
+            server_url: WEBSOCKET_URL.to_string(),
+            room: String::new(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// 保存された設定を現在の形式に変換してから読み込みます
+    /// 値の型が合わないなどの理由で読み込めなかったフィールドはデフォルトの値になり、
+    /// そのフィールドの名前を二番目の値として返します
+    /// 新しいバージョンの設定は、知らないフィールドを無視して読み込みます
+    /// JSONのオブジェクトとして読めない場合は None を返します
+    pub fn from_json(json: &str) -> Option<(GameConfig, Vec<String>)> {
+        let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(json) else {
+            return None;
+        };
+
+        let version = fields.get("version").and_then(|v| v.as_u64()).unwrap_or(1) as u32;
+        for migration in MIGRATIONS.iter().skip(version.max(1) as usize - 1) {
+            migration(&mut fields);
+        }
+        if version < CONFIG_VERSION {
+            fields.insert("version".to_string(), Value::from(CONFIG_VERSION));
+        }
+
+        if let Ok(config) = serde_json::from_value(Value::Object(fields.clone())) {
+            return Some((config, Vec::new()));
+        }
+
+        // 全体を読み込めない場合は、読み込めるフィールドだけをひとつずつ取り込みます
+        let mut merged = match serde_json::to_value(GameConfig::default()) {
+            Ok(Value::Object(merged)) => merged,
+            _ => return None,
+        };
+        let mut dropped = Vec::new();
+        for (key, value) in fields {
+            let mut candidate = merged.clone();
+            candidate.insert(key.clone(), value);
+            if serde_json::from_value::<GameConfig>(Value::Object(candidate.clone())).is_ok() {
+                merged = candidate;
+            } else {
+                dropped.push(key);
+            }
         }
+        let config = serde_json::from_value(Value::Object(merged)).ok()?;
+        Some((config, dropped))
     }
 }
 
+/// ルームコードとして使える文字だけを残し、長さを制限します
+pub fn normalize_room(room: &str) -> String {
+    room.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(MAX_ROOM_CODE_LENGTH)
+        .collect()
+}
+
+/// 接続先のURLを返します
+/// ルームコードが指定されていれば、URLのパスとして付け加えます
+pub fn websocket_url(server_url: &str, room: &str) -> String {
+    let room = normalize_room(room);
+    if room.is_empty() {
+        server_url.to_string()
+    } else {
+        format!("{}/{}", server_url.trim_end_matches('/'), room)
+    }
+}
+
+/// 起動時に指定された接続先とルームコードです
+/// ビルドし直さずにステージングのサーバーやローカルの中継サーバーに接続するために使います
+/// 指定された値は設定には保存されず、設定の値よりも優先されます
+///
+/// デスクトップでは --server と --room のコマンドライン引数、
+/// または MAGIAFORGE_SERVER_URL と MAGIAFORGE_ROOM の環境変数で指定します
+/// WASMでは ?server=...&room=... のクエリ文字列で指定します
+#[derive(Resource, Clone, Debug, Default)]
+pub struct ServerOverride {
+    pub server_url: Option<String>,
+    pub room: Option<String>,
+}
+
+impl ServerOverride {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let get = |option: &str, env: &str| {
+            args.iter()
+                .position(|arg| arg == option)
+                .and_then(|i| args.get(i + 1).cloned())
+                .or_else(|| std::env::var(env).ok())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            server_url: get("--server", "MAGIAFORGE_SERVER_URL"),
+            room: get("--room", "MAGIAFORGE_ROOM"),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        let params = web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok());
+        let get = |name: &str| {
+            params
+                .as_ref()
+                .and_then(|params| params.get(name))
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            server_url: get("server"),
+            room: get("room"),
+        }
+    }
+}
+
+impl GameConfig {
+    /// 起動時の指定を反映した接続先のURLを返します
+    pub fn server_url<'a>(&'a self, server_override: &'a ServerOverride) -> &'a str {
+        server_override
+            .server_url
+            .as_deref()
+            .unwrap_or(&self.server_url)
+    }
+
+    /// 起動時の指定を反映したルームコードを返します
+    pub fn room<'a>(&'a self, server_override: &'a ServerOverride) -> &'a str {
+        server_override.room.as_deref().unwrap_or(&self.room)
+    }
+
+    /// 入力された接続先を設定します
+    /// 起動時に指定された値のままであれば、設定には保存しません
+    /// 書き換えられた場合は、起動時の指定を取り消して設定に保存します
+    /// 空の接続先には接続できないので、保存せずにそれまでの接続先を使います
+    pub fn set_server_url(&mut self, server_override: &mut ServerOverride, url: &str) {
+        let url = url.trim();
+        if url.is_empty() || server_override.server_url.as_deref() == Some(url) {
+            return;
+        }
+        server_override.server_url = None;
+        self.server_url = url.to_string();
+    }
+
+    /// 入力されたルームコードを設定します
+    /// 起動時に指定された値のままであれば、設定には保存しません
+    /// 書き換えられた場合は、空にした場合も含めて、起動時の指定を取り消して設定に保存します
+    pub fn set_room(&mut self, server_override: &mut ServerOverride, room: &str) {
+        let room = normalize_room(room);
+        if server_override.room.as_ref() == Some(&room) {
+            return;
+        }
+        server_override.room = None;
+        self.room = room;
+    }
+}
+
+/// バージョン1ではプレイヤー名と最深記録が設定に含まれていましたが、
+/// バージョン2からはセーブスロットに移ったので取り除きます
+/// セーブスロットへの引き継ぎは、セーブスロットの読み込み時に元の設定から行われます
+fn migrate_profile_to_slot(fields: &mut Map<String, Value>) {
+    fields.remove("player_name");
+    fields.remove("best_depth");
+}
+
 #[allow(dead_code)]
-fn startup(pkv: Res<PkvStore>, mut config: ResMut<GameConfig>) {
-    if let Ok(v) = pkv.get::<String>("config") {
-        if let Ok(deserialized) = serde_json::from_str(v.as_str()) {
-            *config = deserialized;
+fn startup(mut pkv: ResMut<PkvStore>, mut config: ResMut<GameConfig>) {
+    let Ok(v) = pkv.get::<String>(CONFIG_KEY) else {
+        return;
+    };
+    let backup = match GameConfig::from_json(v.as_str()) {
+        Some((loaded, dropped)) => {
+            *config = loaded;
+            if dropped.is_empty() {
+                false
+            } else {
+                warn!("Some config fields could not be read: {:?}", dropped);
+                true
+            }
+        }
+        None => {
+            warn!("Failed to read config");
+            true
         }
     };
+
+    // 読み込めなかった設定は破棄せずに別のキーに退避しておきます
+    if backup {
+        if let Err(err) = pkv.set::<String>(CONFIG_BACKUP_KEY, &v) {
+            warn!("Failed to back up config: {}", err);
+        }
+    }
 }
 
 #[allow(dead_code)]
 fn on_change(mut pkv: ResMut<PkvStore>, config: Res<GameConfig>) {
     if config.is_changed() {
         if let Ok(serialized) = serde_json::to_string(&config.into_inner()) {
-            if let Err(err) = pkv.set::<String>("config", &serialized) {
+            if let Err(err) = pkv.set::<String>(CONFIG_KEY, &serialized) {
                 warn!("Failed to save config: {}", err);
             }
         } else {
@@ -51,9 +258,139 @@ pub struct GameConfigPlugin;
 impl bevy::app::Plugin for GameConfigPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GameConfig::default());
+        app.insert_resource(ServerOverride::load());
         #[cfg(any(not(debug_assertions), target_arch = "wasm32", feature = "save"))]
         app.add_systems(Startup, startup);
         #[cfg(any(not(debug_assertions), target_arch = "wasm32", feature = "save"))]
         app.add_systems(Update, on_change);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_version_1_config() {
+        let json = r#"{"bgm_volume":0.3,"se_volume":0.7,"player_name":"alice","language":"En","fullscreen":true,"best_depth":12}"#;
+        let (config, dropped) = GameConfig::from_json(json).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.bgm_volume, 0.3);
+        assert_eq!(config.se_volume, 0.7);
+        assert!(matches!(config.language, Languages::En));
+        assert!(config.fullscreen);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn load_version_1_config_without_fullscreen() {
+        let json = r#"{"bgm_volume":0.3,"se_volume":0.7,"player_name":"","language":"Ja"}"#;
+        let (config, dropped) = GameConfig::from_json(json).unwrap();
+        assert_eq!(config.bgm_volume, 0.3);
+        assert!(!config.fullscreen);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn fill_missing_fields_with_default() {
+        let (config, dropped) = GameConfig::from_json(r#"{"version":2}"#).unwrap();
+        assert_eq!(config.bgm_volume, DEFAULT_BGM_VOLUME);
+        assert_eq!(config.se_volume, DEFAULT_SE_VOLUME);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn keep_readable_fields_when_some_are_broken() {
+        let json = r#"{"version":2,"bgm_volume":"loud","se_volume":0.2,"language":"Fr"}"#;
+        let (config, mut dropped) = GameConfig::from_json(json).unwrap();
+        dropped.sort();
+        assert_eq!(config.bgm_volume, DEFAULT_BGM_VOLUME);
+        assert_eq!(config.se_volume, 0.2);
+        assert!(matches!(config.language, Languages::Ja));
+        assert_eq!(
+            dropped,
+            vec!["bgm_volume".to_string(), "language".to_string()]
+        );
+    }
+
+    #[test]
+    fn ignore_unknown_fields_from_newer_version() {
+        let json = r#"{"version":99,"bgm_volume":0.1,"vibration":true}"#;
+        let (config, dropped) = GameConfig::from_json(json).unwrap();
+        assert_eq!(config.version, 99);
+        assert_eq!(config.bgm_volume, 0.1);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn reject_unreadable_config() {
+        assert!(GameConfig::from_json("not json").is_none());
+        assert!(GameConfig::from_json("[1, 2, 3]").is_none());
+    }
+
+    #[test]
+    fn append_room_to_server_url() {
+        assert_eq!(
+            websocket_url("ws://localhost:8080", ""),
+            "ws://localhost:8080"
+        );
+        assert_eq!(
+            websocket_url("ws://localhost:8080/", "abc"),
+            "ws://localhost:8080/abc"
+        );
+        assert_eq!(
+            websocket_url("ws://localhost:8080", "a b/c?"),
+            "ws://localhost:8080/abc"
+        );
+    }
+
+    #[test]
+    fn clear_override_when_edited() {
+        let mut config = GameConfig::default();
+        let mut server_override = ServerOverride {
+            server_url: None,
+            room: Some("abc".to_string()),
+        };
+        config.room = "".to_string();
+
+        // 起動時に指定されたルームのままであれば、設定には保存しません
+        config.set_room(&mut server_override, "abc");
+        assert_eq!(config.room(&server_override), "abc");
+        assert_eq!(config.room, "");
+
+        // 設定と同じ値に書き換えた場合も、起動時の指定は取り消されます
+        config.set_room(&mut server_override, "");
+        assert_eq!(server_override.room, None);
+        assert_eq!(config.room(&server_override), "");
+    }
+
+    #[test]
+    fn ignore_empty_server_url() {
+        let mut config = GameConfig::default();
+        let mut server_override = ServerOverride {
+            server_url: Some("ws://localhost:8080".to_string()),
+            room: None,
+        };
+        config.set_server_url(&mut server_override, "  ");
+        assert_eq!(config.server_url(&server_override), "ws://localhost:8080");
+        assert_eq!(config.server_url, GameConfig::default().server_url);
+
+        config.set_server_url(&mut server_override, " ws://example.com ");
+        assert_eq!(server_override.server_url, None);
+        assert_eq!(config.server_url(&server_override), "ws://example.com");
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = GameConfig {
+            bgm_volume: 0.25,
+            language: Languages::En,
+            ..default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let (loaded, dropped) = GameConfig::from_json(&json).unwrap();
+        assert_eq!(loaded.bgm_volume, 0.25);
+        assert!(matches!(loaded.language, Languages::En));
+        assert!(dropped.is_empty());
+    }
+}
@@ -12,6 +12,9 @@ pub const CRATE_NAME: &str = "magiaforge";
 
 pub const WEBSOCKET_URL: &str = "wss://magia-server-38847751193.asia-northeast1.run.app";
 
+/// ルームコードの最大の長さです
+pub const MAX_ROOM_CODE_LENGTH: usize = 16;
+
 pub const DEFAULT_BGM_VOLUME: f32 = 0.4;
 
 pub const DEFAULT_SE_VOLUME: f32 = 0.8;
@@ -29,9 +32,6 @@ pub const MAX_ITEMS_IN_INVENTORY: usize =
 
 pub const MAX_ITEMS_IN_EQUIPMENT: usize = 8;
 
-/// level.aseprite のスライスの最大値 - 1
-pub const LEVELS: i32 = 4;
-
 /// 1タイルのサイズのピクセル数
 /// タイルサイズは意味合いとしてゃ u32 ですが、f32 で扱うことが多いので f32 にしています
 pub const TILE_SIZE: f32 = 16.0;
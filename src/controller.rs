@@ -1,5 +1,8 @@
 pub mod despawn_with_gold;
 pub mod player;
+pub mod protocol;
 pub mod remote;
+pub mod remote_interpolation;
+pub mod remote_monster;
 pub mod servant;
 pub mod training_dummy;
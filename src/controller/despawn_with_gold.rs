@@ -1,6 +1,8 @@
+use crate::entity::actor::{Actor, ActorGroup};
 use crate::entity::gold::spawn_gold;
 use crate::entity::life::Life;
 use crate::se::{SEEvent, SE};
+use crate::stats::StatsEvent;
 use crate::{asset::GameAssets, set::GameSet, states::GameState};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
@@ -17,14 +19,27 @@ pub struct DespawnWithGold {
 fn dead_enemy(
     mut commands: Commands,
     assets: Res<GameAssets>,
-    mut query: Query<(Entity, &DespawnWithGold, &Life, &Transform)>,
+    mut query: Query<(
+        Entity,
+        &DespawnWithGold,
+        &Life,
+        &Transform,
+        &Name,
+        Option<&Actor>,
+    )>,
     mut writer: EventWriter<SEEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
 ) {
-    for (entity, enemy, enemy_life, transform) in query.iter_mut() {
+    for (entity, enemy, enemy_life, transform, name, actor) in query.iter_mut() {
         if enemy_life.life <= 0 {
             commands.entity(entity).despawn_recursive();
             writer.send(SEEvent::pos(SE::Cry, transform.translation.truncate()));
 
+            // 召喚された仲間が倒れた場合は数えません
+            if actor.map_or(false, |a| a.actor_group == ActorGroup::Enemy) {
+                stats_writer.send(StatsEvent::Kill(name.to_string()));
+            }
+
             for _ in 0..enemy.golds {
                 spawn_gold(
                     &mut commands,
@@ -7,8 +7,11 @@ use crate::entity::gold::Gold;
 use crate::entity::life::Life;
 use crate::equipment::EquipmentType;
 use crate::input::{get_direction, get_fire_trigger};
+use crate::level::{CurrentLevel, GameLevel};
+use crate::save::SaveSlots;
 use crate::se::{SEEvent, SE};
 use crate::states::{GameMenuState, GameState};
+use crate::stats::StatsEvent;
 use bevy::core::FrameCount;
 use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
@@ -16,8 +19,10 @@ use bevy_aseprite_ultra::prelude::AseSpriteAnimation;
 use bevy_light_2d::light::PointLight2d;
 use bevy_rapier2d::prelude::*;
 use bevy_simple_websocket::{ClientMessage, ReadyState, WebSocketState};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, Reflect)]
+#[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
 pub struct Equipment {
     pub equipment_type: EquipmentType,
     pub price: u32,
@@ -34,6 +39,10 @@ pub struct Player {
     pub last_idle_vy: f32,
     pub last_idle_life: i32,
     pub last_idle_max_life: i32,
+
+    /// 最後にプレイヤーに弾丸を命中させたアクターです
+    /// アリーナで倒れたときに、倒した相手として通知されます
+    pub last_attacker: Option<Uuid>,
 }
 
 /// プレイヤーの移動
@@ -120,16 +129,17 @@ fn pick_gold(
     mut gold_query: Query<(Entity, &Transform, &mut ExternalForce), With<Gold>>,
     mut player_query: Query<(&mut Actor, &Transform), With<Player>>,
     mut writer: EventWriter<SEEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
 ) {
     if let Ok((mut actor, player_transform)) = player_query.get_single_mut() {
-        let mut got_gold = false;
+        let mut got_gold = 0;
 
         for (gold, gold_transform, mut gold_force) in gold_query.iter_mut() {
             let diff =
                 player_transform.translation.truncate() - gold_transform.translation.truncate();
             if diff.length() < 16.0 {
                 actor.golds += 1;
-                got_gold = true;
+                got_gold += 1;
                 commands.entity(gold).despawn_recursive();
             } else if diff.length() < 48.0 {
                 gold_force.force = diff.normalize() * 1000.0;
@@ -138,11 +148,12 @@ fn pick_gold(
             }
         }
 
-        if got_gold {
+        if 0 < got_gold {
             writer.send(SEEvent::pos(
                 SE::PickUp,
                 player_transform.translation.truncate(),
             ));
+            stats_writer.send(StatsEvent::EarnGold(got_gold));
         }
     }
 }
@@ -150,14 +161,24 @@ fn pick_gold(
 fn die_player(
     mut commands: Commands,
     assets: Res<GameAssets>,
-    player_query: Query<(Entity, &Actor, &Life, &Transform), With<Player>>,
+    player_query: Query<(Entity, &Player, &Actor, &Life, &Transform)>,
     mut writer: EventWriter<ClientMessage>,
     mut game: EventWriter<SEEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
     websocket: Res<WebSocketState>,
+    mut slots: ResMut<SaveSlots>,
+    current: Res<CurrentLevel>,
 ) {
-    if let Ok((entity, actor, player_life, transform)) = player_query.get_single() {
+    if let Ok((entity, player, actor, player_life, transform)) = player_query.get_single() {
         if player_life.life <= 0 {
             commands.entity(entity).despawn_recursive();
+            stats_writer.send(StatsEvent::Die(current.next_level));
+
+            // 倒れた周回は再開できません
+            // アリーナで倒れた場合は、アリーナに入る前の周回をそのまま残します
+            if current.next_level != GameLevel::MultiPlayArena {
+                slots.current_mut().run = None;
+            }
 
             game.send(SEEvent::pos(SE::Cry, transform.translation.truncate()));
 
@@ -186,6 +207,7 @@ fn die_player(
                 &RemoteMessage::Die {
                     sender: actor.uuid,
                     uuid: actor.uuid,
+                    killer: player.last_attacker,
                 },
             );
         }
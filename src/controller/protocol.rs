@@ -0,0 +1,348 @@
+use crate::controller::remote::RemoteMessage;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+/// 通信プロトコルのバージョンです
+/// RemoteMessage の形式を変更したときはこの値を増やします
+/// バージョンの異なるクライアントからのメッセージは読み込まずに破棄します
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// エンベロープの先頭に付ける識別子です
+/// エンベロープを使わない古いクライアントのメッセージを見分けるために使います
+const MAGIC: [u8; 4] = *b"MGFG";
+
+/// 送信したメッセージの通し番号です
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// RemoteMessage の種類です
+/// 受信したメッセージが送信されたときと別の種類として読み込まれていないことを確かめるために使います
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemoteMessageKind {
+    Join,
+    Hello,
+    Position,
+    Fire,
+    Hit,
+    Die,
+    SetTile,
+    SpawnMonster,
+    MonsterPosition,
+    MonsterHit,
+    MonsterDie,
+}
+
+/// 送受信するメッセージの外側の形式です
+/// 中身の RemoteMessage は payload にシリアライズされて格納されます
+/// バージョンが異なっても先頭の magic と version は必ず読み込めるよう、この二つのフィールドの位置は変えないでください
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub magic: [u8; 4],
+    pub version: u32,
+    pub sender: Uuid,
+    pub sequence: u64,
+    pub kind: RemoteMessageKind,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// エンベロープを使わない古いクライアント、または不明なクライアントからのメッセージです
+    UnknownFormat,
+
+    /// プロトコルのバージョンが異なるクライアントからのメッセージです
+    Incompatible { sender: Option<Uuid>, version: u32 },
+
+    /// エンベロープまたは中身を読み込めませんでした
+    Broken(bincode::Error),
+
+    /// 中身の種類がエンベロープに記録された種類と一致しません
+    KindMismatch {
+        expected: RemoteMessageKind,
+        actual: RemoteMessageKind,
+    },
+}
+
+impl RemoteMessage {
+    pub fn kind(&self) -> RemoteMessageKind {
+        match self {
+            RemoteMessage::Join { .. } => RemoteMessageKind::Join,
+            RemoteMessage::Hello { .. } => RemoteMessageKind::Hello,
+            RemoteMessage::Position { .. } => RemoteMessageKind::Position,
+            RemoteMessage::Fire { .. } => RemoteMessageKind::Fire,
+            RemoteMessage::Hit { .. } => RemoteMessageKind::Hit,
+            RemoteMessage::Die { .. } => RemoteMessageKind::Die,
+            RemoteMessage::SetTile { .. } => RemoteMessageKind::SetTile,
+            RemoteMessage::SpawnMonster { .. } => RemoteMessageKind::SpawnMonster,
+            RemoteMessage::MonsterPosition { .. } => RemoteMessageKind::MonsterPosition,
+            RemoteMessage::MonsterHit { .. } => RemoteMessageKind::MonsterHit,
+            RemoteMessage::MonsterDie { .. } => RemoteMessageKind::MonsterDie,
+        }
+    }
+
+    /// メッセージを送信したプレイヤーです
+    /// 弾丸の発射者が不明な場合は nil のUUIDを返します
+    pub fn sender(&self) -> Uuid {
+        match self {
+            RemoteMessage::Join { sender, .. }
+            | RemoteMessage::Hello { sender, .. }
+            | RemoteMessage::Position { sender, .. }
+            | RemoteMessage::Hit { sender, .. }
+            | RemoteMessage::Die { sender, .. }
+            | RemoteMessage::SetTile { sender, .. }
+            | RemoteMessage::SpawnMonster { sender, .. }
+            | RemoteMessage::MonsterPosition { sender, .. }
+            | RemoteMessage::MonsterHit { sender, .. }
+            | RemoteMessage::MonsterDie { sender, .. } => *sender,
+            RemoteMessage::Fire { bullet, .. } => bullet.sender.unwrap_or(Uuid::nil()),
+        }
+    }
+}
+
+/// メッセージをエンベロープに包んでシリアライズします
+pub fn encode(message: &RemoteMessage) -> Vec<u8> {
+    let envelope = Envelope {
+        magic: MAGIC,
+        version: PROTOCOL_VERSION,
+        sender: message.sender(),
+        sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        kind: message.kind(),
+        payload: bincode::serialize(message).unwrap(),
+    };
+    bincode::serialize(&envelope).unwrap()
+}
+
+/// 受信したデータからエンベロープと中身のメッセージを読み込みます
+/// バージョンの異なるクライアントのメッセージは中身を読まずにエラーを返します
+pub fn decode(bin: &[u8]) -> Result<(Envelope, RemoteMessage), DecodeError> {
+    // 先頭の magic と version だけを先に読み込みます
+    let Ok((magic, version)) = bincode::deserialize::<([u8; 4], u32)>(bin) else {
+        return Err(DecodeError::UnknownFormat);
+    };
+    if magic != MAGIC {
+        return Err(DecodeError::UnknownFormat);
+    }
+    if version != PROTOCOL_VERSION {
+        // 送信者の位置もバージョンによって変わる可能性があるため、読めた場合だけ返します
+        let sender = bincode::deserialize::<([u8; 4], u32, Uuid)>(bin)
+            .ok()
+            .map(|(_, _, sender)| sender);
+        return Err(DecodeError::Incompatible { sender, version });
+    }
+
+    let envelope = bincode::deserialize::<Envelope>(bin).map_err(DecodeError::Broken)?;
+    let message =
+        bincode::deserialize::<RemoteMessage>(&envelope.payload).map_err(DecodeError::Broken)?;
+    if message.kind() != envelope.kind {
+        return Err(DecodeError::KindMismatch {
+            expected: envelope.kind,
+            actual: message.kind(),
+        });
+    }
+    Ok((envelope, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::remote_monster::RemoteEntityContent;
+    use crate::entity::bullet::SpawnBullet;
+    use crate::level::tile::Tile;
+    use crate::level::GameLevel;
+    use crate::spell::SpellType;
+    use crate::wand::{Wand, WandSpell, WandType};
+    use bevy::prelude::*;
+    use bevy_rapier2d::prelude::Group;
+
+    fn messages() -> Vec<RemoteMessage> {
+        let sender = Uuid::new_v4();
+        let uuid = Uuid::new_v4();
+        vec![
+            RemoteMessage::Join {
+                sender,
+                name: "alice".to_string(),
+            },
+            RemoteMessage::Hello {
+                sender,
+                name: "bob".to_string(),
+            },
+            RemoteMessage::Position {
+                sender,
+                uuid: sender,
+                time: 12.5,
+                name: "alice".to_string(),
+                golds: 12,
+                level: GameLevel::MultiPlayArena,
+                x: 1.5,
+                y: -2.5,
+                vx: 0.25,
+                vy: -0.75,
+                life: 80,
+                max_life: 100,
+                angle: 0.5,
+                intensity: 1.0,
+            },
+            RemoteMessage::Fire {
+                level: GameLevel::Level(3),
+                bullet: SpawnBullet {
+                    sender: Some(sender),
+                    uuid,
+                    position: Vec2::new(3.0, 4.0),
+                    velocity: Vec2::new(-1.0, 2.0),
+                    bullet_lifetime: 240,
+                    damage: 5,
+                    impulse: 10.0,
+                    slice: "bullet_magic_bolt".to_string(),
+                    collier_radius: 5.0,
+                    light_intensity: 1.0,
+                    light_radius: 50.0,
+                    light_color_hlsa: [245.0, 1.0, 0.6, 1.0],
+                    homing: 0.0,
+                    group: Group::GROUP_1,
+                    filter: Group::GROUP_2,
+                },
+            },
+            RemoteMessage::Hit {
+                sender,
+                uuid,
+                damage: 7,
+            },
+            RemoteMessage::Die {
+                sender,
+                uuid: sender,
+                killer: Some(uuid),
+            },
+            RemoteMessage::SetTile {
+                sender,
+                level: GameLevel::Level(1),
+                x: 3,
+                y: -4,
+                tile: Tile::StoneTile,
+            },
+            RemoteMessage::SpawnMonster {
+                sender,
+                uuid,
+                level: GameLevel::Level(2),
+                content: RemoteEntityContent::Eyeball,
+                x: 10.0,
+                y: 20.0,
+                life: 30,
+                max_life: 30,
+            },
+            RemoteMessage::SpawnMonster {
+                sender,
+                uuid,
+                level: GameLevel::MultiPlayArena,
+                content: RemoteEntityContent::Witch {
+                    wands: [
+                        Some(Wand::with_slots(
+                            WandType::CypressWand,
+                            [
+                                Some(WandSpell {
+                                    spell_type: SpellType::PurpleBolt,
+                                    price: 0,
+                                }),
+                                None,
+                                None,
+                                None,
+                                None,
+                                None,
+                                None,
+                                None,
+                            ],
+                        )),
+                        None,
+                        None,
+                        None,
+                    ],
+                },
+                x: 12.0,
+                y: 24.0,
+                life: 60,
+                max_life: 60,
+            },
+            RemoteMessage::MonsterPosition {
+                sender,
+                uuid,
+                x: 11.0,
+                y: 21.0,
+                vx: 1.0,
+                vy: 1.0,
+                life: 25,
+            },
+            RemoteMessage::MonsterHit {
+                sender,
+                uuid,
+                damage: 5,
+            },
+            RemoteMessage::MonsterDie { sender, uuid },
+        ]
+    }
+
+    #[test]
+    fn round_trip_all_messages() {
+        for message in messages() {
+            let (envelope, decoded) = decode(&encode(&message)).unwrap();
+            assert_eq!(envelope.version, PROTOCOL_VERSION);
+            assert_eq!(envelope.sender, message.sender());
+            assert_eq!(envelope.kind, message.kind());
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+        }
+    }
+
+    #[test]
+    fn increase_sequence() {
+        let message = RemoteMessage::MonsterDie {
+            sender: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+        };
+        let (first, _) = decode(&encode(&message)).unwrap();
+        let (second, _) = decode(&encode(&message)).unwrap();
+        assert!(first.sequence < second.sequence);
+    }
+
+    #[test]
+    fn reject_other_version() {
+        let message = RemoteMessage::Hello {
+            sender: Uuid::new_v4(),
+            name: "carol".to_string(),
+        };
+        let mut envelope: Envelope = bincode::deserialize(&encode(&message)).unwrap();
+        envelope.version = PROTOCOL_VERSION + 1;
+        let bin = bincode::serialize(&envelope).unwrap();
+        match decode(&bin) {
+            Err(DecodeError::Incompatible { sender, version }) => {
+                assert_eq!(sender, Some(message.sender()));
+                assert_eq!(version, PROTOCOL_VERSION + 1);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reject_message_without_envelope() {
+        let message = RemoteMessage::Hit {
+            sender: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+            damage: 1,
+        };
+        let bin = bincode::serialize(&message).unwrap();
+        assert!(matches!(decode(&bin), Err(DecodeError::UnknownFormat)));
+        assert!(matches!(decode(&[]), Err(DecodeError::UnknownFormat)));
+    }
+
+    #[test]
+    fn reject_mismatched_kind() {
+        let message = RemoteMessage::MonsterDie {
+            sender: Uuid::new_v4(),
+            uuid: Uuid::new_v4(),
+        };
+        let mut envelope: Envelope = bincode::deserialize(&encode(&message)).unwrap();
+        envelope.kind = RemoteMessageKind::Hello;
+        let bin = bincode::serialize(&envelope).unwrap();
+        assert!(matches!(
+            decode(&bin),
+            Err(DecodeError::KindMismatch { .. })
+        ));
+    }
+}
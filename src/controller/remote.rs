@@ -1,11 +1,17 @@
-use crate::constant::*;
+use crate::config::{websocket_url, GameConfig, ServerOverride};
 use crate::controller::player::Player;
+use crate::controller::protocol::{decode, encode, DecodeError, PROTOCOL_VERSION};
+use crate::controller::remote_interpolation::RemoteSnapshots;
+use crate::controller::remote_monster::RemoteEntityContent;
 use crate::entity::actor::ActorGroup;
 use crate::entity::bullet::SpawnBullet;
 use crate::entity::life::Life;
 use crate::inventory::Inventory;
+use crate::level::modify::SetTileEvent;
+use crate::level::tile::Tile;
 use crate::level::{setup_level, CurrentLevel, GameLevel};
 use crate::se::SE;
+use crate::stats::StatsEvent;
 use crate::{
     asset::GameAssets,
     entity::{actor::Actor, bullet::spawn_bullet, gold::spawn_gold, witch::spawn_witch},
@@ -13,7 +19,7 @@ use crate::{
     se::SEEvent,
     states::GameState,
 };
-use bevy::{core::FrameCount, prelude::*, utils::HashMap};
+use bevy::{core::FrameCount, prelude::*};
 use bevy_rapier2d::{plugin::PhysicsSet, prelude::Velocity};
 use bevy_simple_websocket::{ClientMessage, ReadyState, ServerMessage, WebSocketState};
 use serde::{Deserialize, Serialize};
@@ -38,24 +44,65 @@ use uuid::Uuid;
 /// そのレベルの「ホスト」はそのレベルにいる最もUUIDの大きいプレイヤーです。
 /// ホストはモンスターの動きを判定し、他のプレイヤーに通知します。
 /// 自分よりuUIDの小さいユーザーから通知が来た場合、その通知は無視されます。
+/// ホストがレベルを離れたり切断したりした場合は、残ったプレイヤーのうち最もUUIDの大きいプレイヤーが
+/// 受信済みのモンスターをそのまま引き継いでホストになります。
+/// モンスターの同期は remote_monster.rs で行います。
+///
+/// サーバーにはマルチプレイヤーのアリーナに入るときに接続し、そのまま続くダンジョンでも接続を保ちます。
+/// 拠点に戻るか、タイトル画面に戻ると切断します。
+/// レベルのチャンクはクライアントごとに異なるワールド座標に配置されるため、
+/// メッセージに含まれる位置は、すべてそのレベルのチャンクの左上を原点とする座標です。
 ///
 #[derive(Component)]
 pub struct RemotePlayer {
     pub name: String,
     pub golds: u32,
     pub last_update: FrameCount,
+
+    /// リモートプレイヤーがいるレベルです
+    /// ホストの選出に使います
+    pub level: GameLevel,
+}
+
+/// 接続しているクライアントのうち、プロトコルのバージョンが異なるものです
+/// これらのクライアントからのメッセージは破棄され、プレイヤーリストに警告が表示されます
+#[derive(Resource, Default)]
+pub struct IncompatiblePeers {
+    /// 送信者とそのプロトコルのバージョンです
+    pub peers: Vec<(Option<Uuid>, u32)>,
+
+    /// エンベロープを使わない古いクライアントなど、形式を読み取れないメッセージを受信したかどうかです
+    pub unknown: bool,
 }
 
+/// 送受信するメッセージです
+/// 送信時には protocol.rs のエンベロープに包まれます
+/// 形式を変更したときは protocol.rs の PROTOCOL_VERSION を増やしてください
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RemoteMessage {
+    // 接続したときに、同じルームのプレイヤーに参加を通知します
+    Join {
+        sender: Uuid,
+        name: String,
+    },
+    // Join を受信したプレイヤーが、自分の存在を新しいプレイヤーに通知します
+    Hello {
+        sender: Uuid,
+        name: String,
+    },
     // エンティティの現在位置を通知します
-    // 前回の通知と比較して、位置が変更されたか60フレーム以上経過した場合、
+    // 前回の通知と比較して、位置か速度が変更されたか60フレーム以上経過した場合、
     // 他のプレイヤーから Join が送られたときは再通知します
+    // 受信側は time を使って位置を補間します
+    // 同じレベルにいるプレイヤーだけが画面に表示されます
     Position {
         sender: Uuid,
         uuid: Uuid,
+        /// 送信側の時計での送信時刻(秒)です
+        time: f32,
         name: String,
         golds: u32,
+        level: GameLevel,
         x: f32,
         y: f32,
         vx: f32,
@@ -66,7 +113,11 @@ pub enum RemoteMessage {
         intensity: f32,
     },
     // 弾を発射したことを通知します
-    Fire(SpawnBullet),
+    // 別のレベルにいるプレイヤーからの通知は無視されます
+    Fire {
+        level: GameLevel,
+        bullet: SpawnBullet,
+    },
     // ダメージを受けたことを通知します
     Hit {
         sender: Uuid,
@@ -76,6 +127,52 @@ pub enum RemoteMessage {
     Die {
         sender: Uuid,
         uuid: Uuid,
+        /// とどめを刺したプレイヤーです
+        killer: Option<Uuid>,
+    },
+    // 壁の破壊や橋の建設などでタイルが変更されたことを通知します
+    // 別のレベルにいるプレイヤーからの通知は無視されます
+    // x, y はチャンクの左上を原点とするタイルの位置です
+    SetTile {
+        sender: Uuid,
+        level: GameLevel,
+        x: i32,
+        y: i32,
+        tile: Tile,
+    },
+    // ホストがモンスターの種類と状態を通知します
+    // 受信したクライアントにまだそのモンスターがいなければ生成します
+    // 途中で参加したプレイヤーにも届くよう、ホストは定期的に再通知します
+    SpawnMonster {
+        sender: Uuid,
+        uuid: Uuid,
+        level: GameLevel,
+        content: RemoteEntityContent,
+        x: f32,
+        y: f32,
+        life: i32,
+        max_life: i32,
+    },
+    // ホストがモンスターの現在位置を通知します
+    MonsterPosition {
+        sender: Uuid,
+        uuid: Uuid,
+        x: f32,
+        y: f32,
+        vx: f32,
+        vy: f32,
+        life: i32,
+    },
+    // ホストがモンスターがダメージを受けたことを通知します
+    MonsterHit {
+        sender: Uuid,
+        uuid: Uuid,
+        damage: i32,
+    },
+    // ホストがモンスターが倒されたことを通知します
+    MonsterDie {
+        sender: Uuid,
+        uuid: Uuid,
     },
 }
 
@@ -85,8 +182,12 @@ fn send_player_states(
     state: Res<WebSocketState>,
     frame_count: Res<FrameCount>,
     current: Res<CurrentLevel>,
+    time: Res<Time>,
 ) {
-    if current.level == Some(GameLevel::MultiPlayArena) && state.ready_state == ReadyState::OPEN {
+    let Some(chunk) = current.chunk.as_ref() else {
+        return;
+    };
+    if state.ready_state == ReadyState::OPEN {
         if let Ok((mut player, actor, actor_life, transform, velocity)) = query.get_single_mut() {
             if actor_life.life <= 0 {
                 return;
@@ -97,16 +198,21 @@ fn send_player_states(
             if 60 < (frame_count.0 as i32 - player.last_idle_frame_count.0 as i32)
                 || translate.x != player.last_ilde_x
                 || translate.y != player.last_ilde_y
+                || velocity.linvel.x != player.last_idle_vx
+                || velocity.linvel.y != player.last_idle_vy
                 || actor_life.life != player.last_idle_life
                 || actor_life.max_life != player.last_idle_max_life
             {
+                let position = translate.truncate() - chunk.origin();
                 let command = RemoteMessage::Position {
                     sender: actor.uuid,
                     uuid: actor.uuid,
+                    time: time.elapsed_secs(),
                     name: player.name.clone(),
                     golds: actor.golds,
-                    x: translate.x,
-                    y: translate.y,
+                    level: current.next_level,
+                    x: position.x,
+                    y: position.y,
                     vx: velocity.linvel.x,
                     vy: velocity.linvel.y,
                     life: actor_life.life,
@@ -114,8 +220,7 @@ fn send_player_states(
                     angle: actor.pointer.to_angle(),
                     intensity: actor.intensity,
                 };
-                let serialized = bincode::serialize(&command).unwrap();
-                writer.send(ClientMessage::Binary(serialized));
+                send_remote_message(&mut writer, true, &command);
                 player.last_idle_frame_count = frame_count.clone();
                 player.last_ilde_x = translate.x;
                 player.last_ilde_y = translate.y;
@@ -126,43 +231,70 @@ fn send_player_states(
     }
 }
 
-fn on_enter(mut writer: EventWriter<ClientMessage>, current: Res<CurrentLevel>) {
+/// 接続が確立したら、同じルームのプレイヤーに参加を通知します
+fn send_join(
+    mut writer: EventWriter<ClientMessage>,
+    player_query: Query<(&Player, &Actor)>,
+    state: Res<WebSocketState>,
+    mut was_open: Local<bool>,
+) {
+    let open = state.ready_state == ReadyState::OPEN;
+    if open && !*was_open {
+        if let Ok((player, actor)) = player_query.get_single() {
+            send_remote_message(
+                &mut writer,
+                true,
+                &RemoteMessage::Join {
+                    sender: actor.uuid,
+                    name: player.name.clone(),
+                },
+            );
+        }
+    }
+    *was_open = open;
+}
+
+/// 設定されたサーバーの、設定されたルームに接続します
+fn on_enter(
+    mut writer: EventWriter<ClientMessage>,
+    current: Res<CurrentLevel>,
+    config: Res<GameConfig>,
+    server_override: Res<ServerOverride>,
+    mut incompatible: ResMut<IncompatiblePeers>,
+) {
     if current.level != Some(GameLevel::MultiPlayArena)
         && current.next_level == GameLevel::MultiPlayArena
     {
-        info!("Connecting to {}", WEBSOCKET_URL);
-        writer.send(ClientMessage::Open(WEBSOCKET_URL.to_string()));
+        *incompatible = IncompatiblePeers::default();
+        let server_url = config.server_url(&server_override);
+        if server_url.is_empty() {
+            warn!("No server URL is configured");
+            return;
+        }
+        let url = websocket_url(server_url, config.room(&server_override));
+        info!("Connecting to {}", url);
+        writer.send(ClientMessage::Open(url));
     }
 }
 
-fn on_exit(mut writer: EventWriter<ClientMessage>, current: Res<CurrentLevel>) {
-    if current.level == Some(GameLevel::MultiPlayArena)
-        && current.next_level != GameLevel::MultiPlayArena
-    {
-        info!("Closing {}", WEBSOCKET_URL);
+/// アリーナから続くダンジョンでは接続を保ち、拠点に戻るときに切断します
+fn on_exit(
+    mut writer: EventWriter<ClientMessage>,
+    current: Res<CurrentLevel>,
+    state: Res<WebSocketState>,
+) {
+    if state.ready_state == ReadyState::OPEN && current.next_level == GameLevel::Level(0) {
+        info!("Closing the connection");
         writer.send(ClientMessage::Close);
     }
 }
 
-#[allow(dead_code)]
-enum RemoteEntityContent {
-    Witch,
-    Slime,
-    Chest,
-    BookShelf,
-}
-
-#[allow(dead_code)]
-struct RemoteEntity {
-    last_update: FrameCount,
-    content: RemoteEntityContent,
-    position: Vec2,
-    level: i32,
-}
-
-#[allow(dead_code)]
-struct RemoteStates {
-    entities: HashMap<Uuid, RemoteEntity>,
+/// ゲームオーバーなどでタイトル画面に戻ったときに切断します
+fn close_on_main_menu(mut writer: EventWriter<ClientMessage>, state: Res<WebSocketState>) {
+    if state.ready_state == ReadyState::OPEN {
+        info!("Closing the connection");
+        writer.send(ClientMessage::Close);
+    }
 }
 
 fn receive_events(
@@ -183,6 +315,14 @@ fn receive_events(
     frame_count: Res<FrameCount>,
     life_bar_res: Res<LifeBarResource>,
     mut writer: EventWriter<SEEvent>,
+    mut tile_writer: EventWriter<SetTileEvent>,
+    mut player_query: Query<(&mut Player, &Actor), Without<RemotePlayer>>,
+    mut stats_writer: EventWriter<StatsEvent>,
+    mut client_writer: EventWriter<ClientMessage>,
+    mut incompatible: ResMut<IncompatiblePeers>,
+    mut snapshots_query: Query<&mut RemoteSnapshots>,
+    time: Res<Time>,
+    current: Res<CurrentLevel>,
 ) {
     // キャラクターを生成されたときに実際に反映させるのは次のフレームからですが、
     // 1フレームに複数のメッセージが届くことがあるため、
@@ -194,17 +334,52 @@ fn receive_events(
             ServerMessage::String(text) => {
                 info!("Received text message: {}", text);
             }
-            ServerMessage::Binary(bin) => match bincode::deserialize::<RemoteMessage>(bin) {
+            ServerMessage::Binary(bin) => match decode(bin) {
+                Err(DecodeError::UnknownFormat) => {
+                    if !incompatible.unknown {
+                        warn!("Received a message in unknown format");
+                        incompatible.unknown = true;
+                    }
+                }
+                Err(DecodeError::Incompatible { sender, version }) => {
+                    if !incompatible.peers.contains(&(sender, version)) {
+                        warn!(
+                            "Ignoring {:?} using protocol version {} (expected {})",
+                            sender, version, PROTOCOL_VERSION
+                        );
+                        incompatible.peers.push((sender, version));
+                    }
+                }
                 Err(err) => {
                     warn!("Failed to deserialize: {:?}", err);
                 }
-                Ok(command) => {
+                Ok((_, command)) => {
                     match command {
+                        RemoteMessage::Join { sender, name } => {
+                            info!("{} joined: {}", name, sender);
+                            // 新しいプレイヤーに自分の存在と現在位置をすぐに通知します
+                            if let Ok((mut player, actor)) = player_query.get_single_mut() {
+                                send_remote_message(
+                                    &mut client_writer,
+                                    true,
+                                    &RemoteMessage::Hello {
+                                        sender: actor.uuid,
+                                        name: player.name.clone(),
+                                    },
+                                );
+                                player.last_idle_frame_count = FrameCount(0);
+                            }
+                        }
+                        RemoteMessage::Hello { sender, name } => {
+                            info!("{} is in the room: {}", name, sender);
+                        }
                         RemoteMessage::Position {
                             sender: _sender,
                             uuid,
+                            time: sent,
                             name,
                             golds,
+                            level,
                             x,
                             y,
                             vx,
@@ -214,34 +389,52 @@ fn receive_events(
                             angle,
                             intensity,
                         } => {
+                            // 別のレベルにいるプレイヤーは画面に表示しません
+                            // レベルを移動したプレイヤーは、いったん削除してから移動先の位置に生成しなおします
+                            let (Some(current_level), Some(chunk)) =
+                                (current.level, current.chunk.as_ref())
+                            else {
+                                continue;
+                            };
+                            let position = chunk.origin() + Vec2::new(x, y);
                             let target = remotes
                                 .iter_mut()
                                 .find(|(_, _, actor, _, _, _)| actor.uuid == uuid);
-                            if let Some((
-                                _,
-                                mut remote,
-                                mut actor,
-                                mut actor_life,
-                                mut transform,
-                                mut velocity,
-                            )) = target
+                            if let Some((entity, remote, ..)) = &target {
+                                if remote.level != level || level != current_level {
+                                    info!("Remote player {} left {:?}", uuid, remote.level);
+                                    commands.entity(*entity).despawn_recursive();
+                                    continue;
+                                }
+                            }
+                            if level != current_level {
+                                continue;
+                            }
+                            if let Some((entity, mut remote, mut actor, mut actor_life, _, _)) =
+                                target
                             {
                                 remote.last_update = *frame_count;
                                 remote.golds = golds;
-                                transform.translation.x = x;
-                                transform.translation.y = y;
-                                velocity.linvel.x = vx;
-                                velocity.linvel.y = vy;
+                                remote.level = level;
+                                // 位置は直接書き換えず、履歴に追加して補間します
+                                if let Ok(mut snapshots) = snapshots_query.get_mut(entity) {
+                                    snapshots.push(
+                                        sent,
+                                        time.elapsed_secs(),
+                                        position,
+                                        Vec2::new(vx, vy),
+                                    );
+                                }
                                 actor_life.life = life;
                                 actor_life.max_life = max_life;
                                 actor.pointer = Vec2::from_angle(angle);
                                 actor.intensity = intensity;
                             } else if !spawned_players.contains(&uuid) {
                                 spawned_players.insert(uuid);
-                                spawn_witch(
+                                let entity = spawn_witch(
                                     &mut commands,
                                     &assets,
-                                    Vec2::new(x, y),
+                                    position,
                                     angle,
                                     uuid,
                                     Some(name.clone()),
@@ -258,19 +451,34 @@ fn receive_events(
                                         name,
                                         golds,
                                         last_update: *frame_count,
+                                        level,
                                     },
                                     ActorGroup::Enemy,
                                 );
+                                commands.entity(entity).insert(RemoteSnapshots::new(
+                                    sent,
+                                    time.elapsed_secs(),
+                                    position,
+                                    Vec2::new(vx, vy),
+                                ));
                                 info!("Remote player spawned: {}", uuid);
                             }
                         }
-                        RemoteMessage::Fire(spawning) => {
-                            spawn_bullet(
-                                &mut commands,
-                                assets.atlas.clone(),
-                                &mut writer,
-                                &spawning,
-                            );
+                        RemoteMessage::Fire { level, bullet } => {
+                            let Some(chunk) = current.chunk.as_ref() else {
+                                continue;
+                            };
+                            if current.level == Some(level) {
+                                spawn_bullet(
+                                    &mut commands,
+                                    assets.atlas.clone(),
+                                    &mut writer,
+                                    &SpawnBullet {
+                                        position: chunk.origin() + bullet.position,
+                                        ..bullet
+                                    },
+                                );
+                            }
                         }
                         RemoteMessage::Hit {
                             sender: _sender,
@@ -289,7 +497,14 @@ fn receive_events(
                         RemoteMessage::Die {
                             sender: _sender,
                             uuid,
+                            killer,
                         } => {
+                            if let Ok((_, player)) = player_query.get_single() {
+                                if killer == Some(player.uuid) {
+                                    stats_writer.send(StatsEvent::PvpKill);
+                                }
+                            }
+
                             let target = remotes
                                 .iter_mut()
                                 .find(|(_, _, actor, _, _, _)| actor.uuid == uuid);
@@ -310,6 +525,31 @@ fn receive_events(
                                 }
                             }
                         }
+                        RemoteMessage::SetTile {
+                            sender: _sender,
+                            level,
+                            x,
+                            y,
+                            tile,
+                        } => {
+                            let Some(chunk) = current.chunk.as_ref() else {
+                                continue;
+                            };
+                            if current.level == Some(level) {
+                                tile_writer.send(SetTileEvent {
+                                    x: chunk.min_x + x,
+                                    y: chunk.min_y + y,
+                                    tile,
+                                    remote: true,
+                                });
+                            }
+                        }
+                        RemoteMessage::SpawnMonster { .. }
+                        | RemoteMessage::MonsterPosition { .. }
+                        | RemoteMessage::MonsterHit { .. }
+                        | RemoteMessage::MonsterDie { .. } => {
+                            // モンスターの同期は receive_monster_events で処理します
+                        }
                     };
                 }
             },
@@ -338,8 +578,7 @@ pub fn send_remote_message(
     message: &RemoteMessage,
 ) {
     if online {
-        let serialized = bincode::serialize::<RemoteMessage>(message).unwrap();
-        writer.send(ClientMessage::Binary(serialized));
+        writer.send(ClientMessage::Binary(encode(message)));
     }
 }
 
@@ -355,9 +594,14 @@ impl Plugin for RemotePlayerPlugin {
 
         app.add_systems(OnExit(GameState::InGame), on_exit);
 
+        app.add_systems(OnEnter(GameState::MainMenu), close_on_main_menu);
+
+        app.init_resource::<IncompatiblePeers>();
+
         app.add_systems(
             FixedUpdate,
             (
+                send_join,
                 send_player_states,
                 receive_events,
                 despawn_no_contact_remotes,
@@ -0,0 +1,269 @@
+use crate::asset::GameAssets;
+use crate::constant::HUD_Z_INDEX;
+use crate::controller::remote::RemotePlayer;
+use crate::states::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::{plugin::PhysicsSet, prelude::Velocity};
+use std::collections::VecDeque;
+
+/// 受信した位置をこの秒数だけ遅らせて表示します
+/// 次の位置が届くまでの間も、前後の位置から補間して滑らかに動かせます
+const INTERPOLATION_DELAY: f32 = 0.1;
+
+/// 最後に受信した位置から速度で推測して動かす最大の秒数です
+/// これを過ぎると、次の位置が届くまでその場に止まります
+const MAX_EXTRAPOLATION: f32 = 0.25;
+
+/// 前後の位置の送信時刻がこの秒数以上離れている場合は補間せず、前の位置から推測して動かします
+/// 静止中は位置が送信されないため、静止していた位置から動き出した位置まで長い時間をかけて補間しないようにします
+const MAX_SNAPSHOT_GAP: f32 = 0.5;
+
+/// 推測した位置と実際の位置の差がこのピクセル数を超えたら、推測した位置へ瞬間移動させます
+/// それ以下であれば、速度を補正して少しずつ推測した位置に近づけます
+const SNAP_THRESHOLD: f32 = 32.0;
+
+/// 推測した位置と実際の位置の差を解消するのにかける秒数です
+const CORRECTION_TIME: f32 = 0.1;
+
+/// 保持しておく位置の最大の個数です
+const MAX_SNAPSHOTS: usize = 32;
+
+#[derive(Clone, Copy, Debug)]
+struct Snapshot {
+    /// 送信側の時計での送信時刻です
+    time: f32,
+
+    /// こちらの時計での受信時刻です
+    received: f32,
+
+    position: Vec2,
+    velocity: Vec2,
+}
+
+/// リモートプレイヤーから受信した位置の履歴です
+/// 受信した位置をそのまま反映せず、履歴から補間した位置へ動かします
+#[derive(Component, Debug)]
+pub struct RemoteSnapshots {
+    snapshots: VecDeque<Snapshot>,
+
+    /// 受信時刻から送信時刻を引いた値のうち最小のものです
+    /// 送信側とこちらの時計のずれとして扱います
+    offset: f32,
+
+    /// 直近に推測した位置と実際の位置の差です
+    error: f32,
+}
+
+impl RemoteSnapshots {
+    pub fn new(time: f32, received: f32, position: Vec2, velocity: Vec2) -> Self {
+        let mut snapshots = Self {
+            snapshots: VecDeque::new(),
+            offset: received - time,
+            error: 0.0,
+        };
+        snapshots.push(time, received, position, velocity);
+        snapshots
+    }
+
+    /// 受信した位置を追加します
+    /// 順番が入れ替わって届いた古い位置は無視します
+    pub fn push(&mut self, time: f32, received: f32, position: Vec2, velocity: Vec2) {
+        if let Some(last) = self.snapshots.back() {
+            if time <= last.time {
+                return;
+            }
+        }
+        self.offset = self.offset.min(received - time);
+        self.snapshots.push_back(Snapshot {
+            time,
+            received,
+            position,
+            velocity,
+        });
+        while MAX_SNAPSHOTS < self.snapshots.len() {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// 送信側の時計で指定した時刻の位置と速度を推測します
+    fn sample(&self, time: f32) -> Option<(Vec2, Vec2)> {
+        let first = self.snapshots.front()?;
+        if time <= first.time {
+            return Some((first.position, first.velocity));
+        }
+        for (a, b) in self.snapshots.iter().zip(self.snapshots.iter().skip(1)) {
+            if time < b.time {
+                if b.time - a.time < MAX_SNAPSHOT_GAP {
+                    let t = (time - a.time) / (b.time - a.time);
+                    return Some((
+                        a.position.lerp(b.position, t),
+                        a.velocity.lerp(b.velocity, t),
+                    ));
+                }
+                return Some(extrapolate(a, time));
+            }
+        }
+        self.snapshots.back().map(|last| extrapolate(last, time))
+    }
+
+    /// 指定した時刻より前の、補間に使わなくなった位置を捨てます
+    fn discard_before(&mut self, time: f32) {
+        while 2 <= self.snapshots.len() && self.snapshots[1].time <= time {
+            self.snapshots.pop_front();
+        }
+    }
+}
+
+fn extrapolate(snapshot: &Snapshot, time: f32) -> (Vec2, Vec2) {
+    let elapsed = time - snapshot.time;
+    if MAX_EXTRAPOLATION < elapsed {
+        (
+            snapshot.position + snapshot.velocity * MAX_EXTRAPOLATION,
+            Vec2::ZERO,
+        )
+    } else {
+        (
+            snapshot.position + snapshot.velocity * elapsed,
+            snapshot.velocity,
+        )
+    }
+}
+
+/// 位置の履歴から推測した位置へリモートプレイヤーを動かします
+fn apply_snapshots(
+    mut query: Query<(&mut RemoteSnapshots, &mut Transform, &mut Velocity), With<RemotePlayer>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    for (mut snapshots, mut transform, mut velocity) in query.iter_mut() {
+        let render_time = now - snapshots.offset - INTERPOLATION_DELAY;
+        let Some((position, target_velocity)) = snapshots.sample(render_time) else {
+            continue;
+        };
+        let error = position - transform.translation.truncate();
+        snapshots.error = error.length();
+        if SNAP_THRESHOLD < error.length() {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            velocity.linvel = target_velocity;
+        } else {
+            velocity.linvel = target_velocity + error / CORRECTION_TIME;
+        }
+        snapshots.discard_before(render_time);
+    }
+}
+
+/// 受信した位置の古さと、推測した位置の誤差を表示するデバッグ用のテキストです
+#[derive(Component)]
+struct SnapshotOverlay;
+
+fn spawn_snapshot_overlay(mut commands: Commands, assets: Res<GameAssets>) {
+    if !cfg!(feature = "debug") {
+        return;
+    }
+    commands.spawn((
+        Name::new("snapshot_overlay"),
+        SnapshotOverlay,
+        StateScoped(GameState::InGame),
+        GlobalZIndex(HUD_Z_INDEX),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            top: Val::Px(120.0),
+            ..default()
+        },
+        Text::new(""),
+        TextColor(Color::srgba(1.0, 1.0, 1.0, 0.7)),
+        TextFont {
+            font: assets.dotgothic.clone(),
+            font_size: 12.0,
+            ..default()
+        },
+    ));
+}
+
+fn update_snapshot_overlay(
+    mut overlay_query: Query<&mut Text, With<SnapshotOverlay>>,
+    remote_query: Query<(&RemotePlayer, &RemoteSnapshots)>,
+    time: Res<Time<Fixed>>,
+) {
+    let Ok(mut text) = overlay_query.get_single_mut() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    text.0 = remote_query
+        .iter()
+        .map(|(remote, snapshots)| {
+            let age = snapshots
+                .snapshots
+                .back()
+                .map_or(0.0, |last| now - last.received);
+            format!(
+                "{}: age {:.0} ms, error {:.1} px, buffered {}",
+                remote.name,
+                age * 1000.0,
+                snapshots.error,
+                snapshots.snapshots.len()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+pub struct RemoteInterpolationPlugin;
+
+impl Plugin for RemoteInterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_snapshot_overlay);
+        app.add_systems(
+            FixedUpdate,
+            apply_snapshots
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+        app.add_systems(
+            Update,
+            update_snapshot_overlay.run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_between_snapshots() {
+        let mut snapshots = RemoteSnapshots::new(1.0, 1.05, Vec2::ZERO, Vec2::new(10.0, 0.0));
+        snapshots.push(1.1, 1.15, Vec2::new(1.0, 0.0), Vec2::new(10.0, 0.0));
+        let (position, velocity) = snapshots.sample(1.05).unwrap();
+        assert!((position.x - 0.5).abs() < 0.001);
+        assert_eq!(velocity, Vec2::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn extrapolate_with_velocity_up_to_limit() {
+        let snapshots = RemoteSnapshots::new(1.0, 1.05, Vec2::ZERO, Vec2::new(10.0, 0.0));
+        let (position, _) = snapshots.sample(1.1).unwrap();
+        assert!((position.x - 1.0).abs() < 0.001);
+        let (position, velocity) = snapshots.sample(10.0).unwrap();
+        assert!((position.x - 10.0 * MAX_EXTRAPOLATION).abs() < 0.001);
+        assert_eq!(velocity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn ignore_out_of_order_snapshots() {
+        let mut snapshots = RemoteSnapshots::new(2.0, 2.05, Vec2::ZERO, Vec2::ZERO);
+        snapshots.push(1.0, 2.1, Vec2::new(100.0, 0.0), Vec2::ZERO);
+        assert_eq!(snapshots.snapshots.len(), 1);
+        assert_eq!(snapshots.offset, 2.05 - 2.0);
+    }
+
+    #[test]
+    fn do_not_interpolate_across_long_gap() {
+        let mut snapshots = RemoteSnapshots::new(1.0, 1.0, Vec2::ZERO, Vec2::ZERO);
+        snapshots.push(5.0, 5.0, Vec2::new(100.0, 0.0), Vec2::ZERO);
+        let (position, _) = snapshots.sample(3.0).unwrap();
+        assert_eq!(position, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,535 @@
+use crate::constant::{MAX_WANDS, TILE_SIZE};
+use crate::controller::player::Player;
+use crate::controller::protocol::decode;
+use crate::controller::remote::{send_remote_message, RemoteMessage, RemotePlayer};
+use crate::enemy::eyeball::{spawn_eyeball, EyeballControl};
+use crate::enemy::slime::{spawn_slime, SlimeControl};
+use crate::enemy::witch::{spawn_enemy_witch, WitchControl};
+use crate::entity::actor::{Actor, ActorGroup};
+use crate::entity::damege::SpawnDamageNumber;
+use crate::entity::life::Life;
+use crate::hud::life_bar::LifeBarResource;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::se::{SEEvent, SE};
+use crate::wand::Wand;
+use crate::{asset::GameAssets, states::GameState};
+use bevy::{core::FrameCount, prelude::*, utils::HashMap};
+use bevy_rapier2d::{plugin::PhysicsSet, prelude::Velocity};
+use bevy_simple_websocket::{ClientMessage, ReadyState, ServerMessage, WebSocketState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// ホストがモンスターの位置を通知する最短の間隔(フレーム)です
+const MONSTER_POSITION_INTERVAL: i32 = 6;
+
+/// ホストがモンスターの種類と状態を再通知する間隔(フレーム)です
+/// あとから参加したプレイヤーにも、この間隔でモンスターが生成されます
+const MONSTER_RESPAWN_INTERVAL: i32 = 60;
+
+/// ホストからの通知がこのフレーム数以上途絶えたモンスターは削除されます
+/// また、ホストでなくなってからこのフレーム数が経過すると、自分で生成したモンスターは削除されます
+const MONSTER_TIMEOUT: i32 = 120;
+
+/// 同期されるモンスターの種類です
+/// ボスは独自の行動をとるため同期の対象外で、各クライアントでそれぞれ動きます
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RemoteEntityContent {
+    /// 敵の魔法使いです
+    /// 杖はホストが生成したものを使うので、撃破したときに落とすアイテムもホストと一致します
+    Witch {
+        wands: [Option<Wand>; MAX_WANDS],
+    },
+    Slime,
+    Eyeball,
+}
+
+impl RemoteEntityContent {
+    fn of(
+        actor: &Actor,
+        slime: Option<&SlimeControl>,
+        eyeball: Option<&EyeballControl>,
+        witch: Option<&WitchControl>,
+    ) -> Option<Self> {
+        match (slime, eyeball, witch) {
+            (Some(_), _, _) => Some(RemoteEntityContent::Slime),
+            (_, Some(_), _) => Some(RemoteEntityContent::Eyeball),
+            (_, _, Some(_)) => Some(RemoteEntityContent::Witch {
+                wands: actor.wands.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// ホストから受信した状態に従って動く、ホスト以外のクライアント上のモンスターです
+/// 自分では移動も詠唱もせず、弾丸が命中してもライフは減りません
+/// ダメージや撃破はホストからの通知で反映されます
+#[derive(Component)]
+pub struct RemoteMonster {
+    last_update: FrameCount,
+}
+
+/// ホストが最後に通知したモンスターの状態です
+struct RemoteEntity {
+    last_update: FrameCount,
+    position: Vec2,
+    life: i32,
+}
+
+#[derive(Resource, Default)]
+struct RemoteStates {
+    /// ホストとして通知済みのモンスターです
+    entities: HashMap<Uuid, RemoteEntity>,
+
+    /// 現在のレベルのホストです
+    host: Option<Uuid>,
+
+    /// ホストでなくなったフレームです
+    demoted: Option<FrameCount>,
+
+    level: Option<GameLevel>,
+}
+
+fn reset_remote_states(mut states: ResMut<RemoteStates>) {
+    *states = RemoteStates::default();
+}
+
+/// 現在のレベルのホストを選出します
+/// オフラインの場合は常に自分がホストです
+/// 自分が新たにホストになった場合は、受信していたモンスターをそのまま引き継いで自分で動かします
+fn elect_host(
+    mut commands: Commands,
+    mut states: ResMut<RemoteStates>,
+    player_query: Query<&Actor, With<Player>>,
+    remote_query: Query<(&Actor, &RemotePlayer)>,
+    puppet_query: Query<Entity, With<RemoteMonster>>,
+    current: Res<CurrentLevel>,
+    websocket: Res<WebSocketState>,
+    frame_count: Res<FrameCount>,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+
+    if states.level != current.level {
+        states.entities.clear();
+        states.level = current.level;
+    }
+
+    let host = if websocket.ready_state == ReadyState::OPEN {
+        remote_query
+            .iter()
+            .filter(|(_, remote)| Some(remote.level) == current.level)
+            .map(|(actor, _)| actor.uuid)
+            .chain(std::iter::once(player.uuid))
+            .max()
+    } else {
+        Some(player.uuid)
+    };
+
+    if host == states.host {
+        return;
+    }
+
+    if host == Some(player.uuid) {
+        info!("Became the host of {:?}", current.level);
+        for entity in puppet_query.iter() {
+            commands.entity(entity).remove::<RemoteMonster>();
+        }
+        states.demoted = None;
+    } else {
+        info!("Host of {:?} is {:?}", current.level, host);
+        if states.demoted.is_none() {
+            states.demoted = Some(*frame_count);
+        }
+    }
+    states.host = host;
+}
+
+/// ホストであれば、現在のレベルにいるモンスターの状態を他のプレイヤーに通知します
+fn send_monster_states(
+    mut writer: EventWriter<ClientMessage>,
+    mut states: ResMut<RemoteStates>,
+    player_query: Query<&Actor, With<Player>>,
+    monster_query: Query<
+        (
+            &Actor,
+            &Life,
+            &Transform,
+            Option<&Velocity>,
+            Option<&SlimeControl>,
+            Option<&EyeballControl>,
+            Option<&WitchControl>,
+        ),
+        (
+            Without<Player>,
+            Without<RemotePlayer>,
+            Without<RemoteMonster>,
+        ),
+    >,
+    current: Res<CurrentLevel>,
+    websocket: Res<WebSocketState>,
+    frame_count: Res<FrameCount>,
+) {
+    if websocket.ready_state != ReadyState::OPEN {
+        return;
+    }
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    if states.host != Some(player.uuid) {
+        return;
+    }
+    let (Some(level), Some(chunk)) = (current.level, current.chunk.as_ref()) else {
+        return;
+    };
+    let sender = player.uuid;
+
+    let mut alive = HashSet::new();
+    for (actor, life, transform, velocity, slime, eyeball, witch) in monster_query.iter() {
+        if actor.actor_group != ActorGroup::Enemy {
+            continue;
+        }
+        let Some(content) = RemoteEntityContent::of(actor, slime, eyeball, witch) else {
+            continue;
+        };
+        let position = transform.translation.truncate();
+        let x = (position.x / TILE_SIZE).floor() as i32;
+        let y = (-position.y / TILE_SIZE).floor() as i32;
+        if !chunk.contains(x, y) {
+            continue;
+        }
+        alive.insert(actor.uuid);
+
+        // 位置はチャンクの左上を原点とする座標で通知します
+        let local = position - chunk.origin();
+        let spawn = RemoteMessage::SpawnMonster {
+            sender,
+            uuid: actor.uuid,
+            level,
+            content,
+            x: local.x,
+            y: local.y,
+            life: life.life,
+            max_life: life.max_life,
+        };
+
+        let Some(entity) = states.entities.get_mut(&actor.uuid) else {
+            send_remote_message(&mut writer, true, &spawn);
+            states.entities.insert(
+                actor.uuid,
+                RemoteEntity {
+                    last_update: *frame_count,
+                    position,
+                    life: life.life,
+                },
+            );
+            continue;
+        };
+
+        if life.life < entity.life {
+            send_remote_message(
+                &mut writer,
+                true,
+                &RemoteMessage::MonsterHit {
+                    sender,
+                    uuid: actor.uuid,
+                    damage: entity.life - life.life,
+                },
+            );
+        }
+        entity.life = life.life;
+
+        let elapsed = frame_count.0 as i32 - entity.last_update.0 as i32;
+        if MONSTER_RESPAWN_INTERVAL <= elapsed {
+            send_remote_message(&mut writer, true, &spawn);
+            entity.last_update = *frame_count;
+            entity.position = position;
+        } else if MONSTER_POSITION_INTERVAL <= elapsed && position != entity.position {
+            let velocity = velocity.map_or(Vec2::ZERO, |v| v.linvel);
+            send_remote_message(
+                &mut writer,
+                true,
+                &RemoteMessage::MonsterPosition {
+                    sender,
+                    uuid: actor.uuid,
+                    x: local.x,
+                    y: local.y,
+                    vx: velocity.x,
+                    vy: velocity.y,
+                    life: life.life,
+                },
+            );
+            entity.last_update = *frame_count;
+            entity.position = position;
+        }
+    }
+
+    // 通知済みで見つからなくなったモンスターは倒されたものとして通知します
+    let dead: Vec<Uuid> = states
+        .entities
+        .keys()
+        .filter(|uuid| !alive.contains(*uuid))
+        .copied()
+        .collect();
+    for uuid in dead {
+        states.entities.remove(&uuid);
+        send_remote_message(
+            &mut writer,
+            true,
+            &RemoteMessage::MonsterDie { sender, uuid },
+        );
+    }
+}
+
+/// ホストから受信したモンスターの状態を反映します
+/// 現在のホスト以外からの通知や、別のレベルのモンスターの通知は無視します
+fn receive_monster_events(
+    mut commands: Commands,
+    mut reader: EventReader<ServerMessage>,
+    mut monster_query: Query<
+        (
+            Entity,
+            &Actor,
+            Option<&mut RemoteMonster>,
+            &mut Life,
+            &mut Transform,
+            Option<&mut Velocity>,
+        ),
+        (Without<Player>, Without<RemotePlayer>),
+    >,
+    states: Res<RemoteStates>,
+    assets: Res<GameAssets>,
+    life_bar_res: Res<LifeBarResource>,
+    current: Res<CurrentLevel>,
+    frame_count: Res<FrameCount>,
+    mut damage_writer: EventWriter<SpawnDamageNumber>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    // 生成したモンスターが実際に反映されるのは次のフレームからなので、
+    // 同じフレームに同じモンスターが重複して生成されないようにセットで管理します
+    let mut spawned = HashSet::new();
+
+    for message in reader.read() {
+        let ServerMessage::Binary(bin) = message else {
+            continue;
+        };
+        // 読み込めないメッセージの警告は receive_events で出力されます
+        let Ok((_, command)) = decode(bin) else {
+            continue;
+        };
+
+        match command {
+            RemoteMessage::SpawnMonster {
+                sender,
+                uuid,
+                level,
+                content,
+                x,
+                y,
+                life,
+                max_life,
+            } => {
+                if Some(sender) != states.host || Some(level) != current.level {
+                    continue;
+                }
+                let Some(ref chunk) = current.chunk else {
+                    continue;
+                };
+                let position = chunk.origin() + Vec2::new(x, y);
+                let target = monster_query
+                    .iter_mut()
+                    .find(|(_, actor, ..)| actor.uuid == uuid);
+                if let Some((entity, _, remote, mut monster_life, mut transform, _)) = target {
+                    match remote {
+                        Some(mut remote) => remote.last_update = *frame_count,
+                        None => {
+                            commands.entity(entity).insert(RemoteMonster {
+                                last_update: *frame_count,
+                            });
+                        }
+                    }
+                    transform.translation.x = position.x;
+                    transform.translation.y = position.y;
+                    monster_life.life = life;
+                    monster_life.max_life = max_life;
+                } else if !spawned.contains(&uuid) {
+                    spawned.insert(uuid);
+                    let entity = match content {
+                        RemoteEntityContent::Slime => spawn_slime(
+                            &mut commands,
+                            &assets,
+                            position,
+                            &life_bar_res,
+                            0,
+                            2,
+                            ActorGroup::Enemy,
+                            None,
+                        ),
+                        RemoteEntityContent::Eyeball => spawn_eyeball(
+                            &mut commands,
+                            &assets,
+                            position,
+                            &life_bar_res,
+                            ActorGroup::Enemy,
+                            None,
+                        ),
+                        RemoteEntityContent::Witch { wands } => spawn_enemy_witch(
+                            &mut commands,
+                            &assets,
+                            &life_bar_res,
+                            position,
+                            wands,
+                        ),
+                    };
+                    commands.entity(entity).insert(RemoteMonster {
+                        last_update: *frame_count,
+                    });
+
+                    // 生成関数は新しいUUIDを割り当てるので、ホストと同じUUIDに置き換えます
+                    commands.queue(move |world: &mut World| {
+                        if let Some(mut actor) = world.get_mut::<Actor>(entity) {
+                            actor.uuid = uuid;
+                        }
+                        if let Some(mut monster_life) = world.get_mut::<Life>(entity) {
+                            monster_life.life = life;
+                            monster_life.max_life = max_life;
+                        }
+                    });
+                    info!("Remote monster spawned: {}", uuid);
+                }
+            }
+            RemoteMessage::MonsterPosition {
+                sender,
+                uuid,
+                x,
+                y,
+                vx,
+                vy,
+                life,
+            } => {
+                if Some(sender) != states.host {
+                    continue;
+                }
+                let Some(ref chunk) = current.chunk else {
+                    continue;
+                };
+                let position = chunk.origin() + Vec2::new(x, y);
+                let target = monster_query
+                    .iter_mut()
+                    .find(|(_, actor, ..)| actor.uuid == uuid);
+                if let Some((_, _, Some(mut remote), mut monster_life, mut transform, velocity)) =
+                    target
+                {
+                    remote.last_update = *frame_count;
+                    transform.translation.x = position.x;
+                    transform.translation.y = position.y;
+                    if let Some(mut velocity) = velocity {
+                        velocity.linvel = Vec2::new(vx, vy);
+                    }
+                    monster_life.life = life;
+                }
+            }
+            RemoteMessage::MonsterHit {
+                sender,
+                uuid,
+                damage,
+            } => {
+                if Some(sender) != states.host {
+                    continue;
+                }
+                let target = monster_query
+                    .iter_mut()
+                    .find(|(_, actor, ..)| actor.uuid == uuid);
+                if let Some((_, _, Some(mut remote), mut monster_life, transform, _)) = target {
+                    let position = transform.translation.truncate();
+                    remote.last_update = *frame_count;
+                    monster_life.life = (monster_life.life - damage).max(0);
+                    monster_life.amplitude = 6.0;
+                    damage_writer.send(SpawnDamageNumber { damage, position });
+                    se_writer.send(SEEvent::pos(SE::Damage, position));
+                }
+            }
+            RemoteMessage::MonsterDie { sender, uuid } => {
+                if Some(sender) != states.host {
+                    continue;
+                }
+                let target = monster_query
+                    .iter_mut()
+                    .find(|(_, actor, ..)| actor.uuid == uuid);
+                // ライフをゼロにすると、通常の撃破と同じ処理で消滅します
+                if let Some((_, _, Some(_), mut monster_life, _, _)) = target {
+                    monster_life.life = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// ホストからの通知が途絶えたモンスターを削除します
+/// また、ホストでなくなってからしばらく経ったら、自分で生成したモンスターを削除します
+/// 同じレベルに同時に到達したときに重複して生成されたモンスターは、これで取り除かれます
+fn despawn_orphan_monsters(
+    mut commands: Commands,
+    puppet_query: Query<(Entity, &Actor, &RemoteMonster)>,
+    local_query: Query<
+        (
+            Entity,
+            &Actor,
+            Option<&SlimeControl>,
+            Option<&EyeballControl>,
+            Option<&WitchControl>,
+        ),
+        (
+            Without<Player>,
+            Without<RemotePlayer>,
+            Without<RemoteMonster>,
+        ),
+    >,
+    states: Res<RemoteStates>,
+    frame_count: Res<FrameCount>,
+) {
+    for (entity, actor, remote) in puppet_query.iter() {
+        if MONSTER_TIMEOUT < (frame_count.0 as i32 - remote.last_update.0 as i32) {
+            info!("Remote monster {} despawned", actor.uuid);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let Some(demoted) = states.demoted else {
+        return;
+    };
+    if (frame_count.0 as i32 - demoted.0 as i32) <= MONSTER_TIMEOUT {
+        return;
+    }
+    for (entity, actor, slime, eyeball, witch) in local_query.iter() {
+        if actor.actor_group == ActorGroup::Enemy
+            && RemoteEntityContent::of(actor, slime, eyeball, witch).is_some()
+        {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub struct RemoteMonsterPlugin;
+
+impl Plugin for RemoteMonsterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RemoteStates>();
+        app.add_systems(OnEnter(GameState::InGame), reset_remote_states);
+        app.add_systems(
+            FixedUpdate,
+            (
+                elect_host,
+                receive_monster_events,
+                send_monster_states,
+                despawn_orphan_monsters,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
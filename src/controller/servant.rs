@@ -1,12 +1,66 @@
+use crate::constant::*;
+use crate::controller::player::Player;
+use crate::entity::actor::{Actor, ActorFireState};
 use crate::entity::life::Life;
+use crate::se::{SEEvent, SE};
 use crate::set::GameSet;
-use crate::states::GameState;
+use crate::states::{GameMenuState, GameState};
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use std::collections::HashMap;
+
+/// ひとりの主人が同時に従えることのできる使い魔の数です
+/// これを超えて召喚した場合は、もっとも古い使い魔から消滅します
+pub const MAX_SERVANTS: usize = 8;
+
+/// 追従中の使い魔がこれ以上主人から離れた場合は、戦闘を中断して主人のもとへ戻ります
+const SERVANT_LEASH_RADIUS: f32 = TILE_SIZE * 6.0;
+
+/// 待機中の使い魔が待機位置からこれ以上離れた場合は、待機位置へ戻ります
+const SERVANT_HOLD_RADIUS: f32 = TILE_SIZE * 1.0;
+
+/// 攻撃命令のとき、ポインターからこの距離にいる敵が攻撃対象に選ばれます
+const SERVANT_TARGET_RADIUS: f32 = TILE_SIZE * 2.0;
+
+/// 使い魔への命令です
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ServantOrder {
+    /// 主人の近くで戦います
+    Follow,
+
+    /// 指定した位置にとどまり、近づいた敵だけを攻撃します
+    Hold(Vec2),
+
+    /// 指定したアクターを集中して攻撃します
+    Attack(Entity),
+}
 
 #[derive(Component, Debug)]
 pub struct Servant {
     pub master: Entity,
+    pub order: ServantOrder,
+
+    /// 召喚された順番です
+    /// 上限を超えたときに、どの使い魔を消すかの判定に使います
+    pub serial: u32,
+}
+
+impl Servant {
+    pub fn new(master: Entity) -> Self {
+        Self {
+            master,
+            order: ServantOrder::Follow,
+            serial: 0,
+        }
+    }
+
+    /// 攻撃命令を受けている場合は、その対象を返します
+    pub fn target(&self) -> Option<Entity> {
+        match self.order {
+            ServantOrder::Attack(target) => Some(target),
+            _ => None,
+        }
+    }
 }
 
 fn despown_if_no_master(
@@ -20,16 +74,165 @@ fn despown_if_no_master(
     }
 }
 
+/// 新しく召喚された使い魔に召喚順の番号を振ります
+fn number_servants(mut servant_query: Query<&mut Servant, Added<Servant>>, mut serial: Local<u32>) {
+    for mut servant in servant_query.iter_mut() {
+        *serial += 1;
+        servant.serial = *serial;
+    }
+}
+
+/// 主人ごとに使い魔の数を数え、上限を超えた分は古いものから消滅させます
+fn limit_servants(mut servant_query: Query<(&mut Life, &Servant)>) {
+    let mut servants: HashMap<Entity, Vec<u32>> = HashMap::new();
+    for (life, servant) in servant_query.iter() {
+        if 0 < life.life {
+            servants
+                .entry(servant.master)
+                .or_default()
+                .push(servant.serial);
+        }
+    }
+
+    // 主人ごとに、残すことのできるもっとも古い使い魔の番号を求める
+    let mut thresholds: HashMap<Entity, u32> = HashMap::new();
+    for (master, mut serials) in servants.into_iter() {
+        if MAX_SERVANTS < serials.len() {
+            serials.sort();
+            thresholds.insert(master, serials[serials.len() - MAX_SERVANTS]);
+        }
+    }
+
+    for (mut life, servant) in servant_query.iter_mut() {
+        if let Some(threshold) = thresholds.get(&servant.master) {
+            if servant.serial < *threshold {
+                life.life = 0;
+            }
+        }
+    }
+}
+
+/// プレイヤーの使い魔に命令を出します
+/// F で追従、G でその場で待機、R でポインターの位置にいる敵を集中攻撃します
+fn order_servants(
+    player_query: Query<(Entity, &Actor, &Transform), With<Player>>,
+    mut servant_query: Query<(&mut Servant, &Transform)>,
+    enemy_query: Query<(Entity, &Actor, &Transform), Without<Player>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    menu: Res<State<GameMenuState>>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    if *menu.get() != GameMenuState::Closed {
+        return;
+    }
+
+    let Ok((player_entity, player_actor, player_transform)) = player_query.get_single() else {
+        return;
+    };
+
+    let hold = keys.just_pressed(KeyCode::KeyG);
+
+    let order = if keys.just_pressed(KeyCode::KeyF) {
+        ServantOrder::Follow
+    } else if hold {
+        // 待機位置は使い魔ごとに異なるので、あとで個別に設定します
+        ServantOrder::Hold(Vec2::ZERO)
+    } else if keys.just_pressed(KeyCode::KeyR) {
+        let pointer = player_transform.translation.truncate() + player_actor.pointer;
+        let target = enemy_query
+            .iter()
+            .filter(|(_, actor, _)| actor.actor_group != player_actor.actor_group)
+            .map(|(entity, _, transform)| {
+                (entity, transform.translation.truncate().distance(pointer))
+            })
+            .filter(|(_, distance)| *distance < SERVANT_TARGET_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match target {
+            Some((entity, _)) => ServantOrder::Attack(entity),
+            None => return,
+        }
+    } else {
+        return;
+    };
+
+    let mut ordered = false;
+    for (mut servant, servant_transform) in servant_query.iter_mut() {
+        if servant.master == player_entity {
+            servant.order = if hold {
+                ServantOrder::Hold(servant_transform.translation.truncate())
+            } else {
+                order
+            };
+            ordered = true;
+        }
+    }
+
+    if ordered {
+        se_writer.send(SEEvent::new(SE::Switch));
+    }
+}
+
+/// 各使い魔の行動を命令に従って補正します
+/// スライムやアイボールの通常の行動を選択したあとに実行される必要があります
+fn control_servants(
+    mut servant_query: Query<(&mut Servant, &mut Actor, &Transform)>,
+    target_query: Query<&Transform, With<Actor>>,
+) {
+    for (mut servant, mut actor, transform) in servant_query.iter_mut() {
+        let origin = transform.translation.truncate();
+        match servant.order {
+            ServantOrder::Follow => {
+                if let Ok(master_transform) = target_query.get(servant.master) {
+                    let diff = master_transform.translation.truncate() - origin;
+                    if SERVANT_LEASH_RADIUS < diff.length() {
+                        actor.move_direction = diff.normalize_or_zero();
+                        actor.fire_state = ActorFireState::Idle;
+                    }
+                }
+            }
+            ServantOrder::Hold(position) => {
+                let diff = position - origin;
+                actor.move_direction = if SERVANT_HOLD_RADIUS < diff.length() {
+                    diff.normalize_or_zero()
+                } else {
+                    Vec2::ZERO
+                };
+            }
+            ServantOrder::Attack(target) => {
+                if let Ok(target_transform) = target_query.get(target) {
+                    // 攻撃できる距離にいなければ、対象に向かって移動します
+                    if actor.fire_state == ActorFireState::Idle {
+                        let diff = target_transform.translation.truncate() - origin;
+                        actor.move_direction = diff.normalize_or_zero();
+                    }
+                } else {
+                    // 対象が倒れたら追従に戻ります
+                    servant.order = ServantOrder::Follow;
+                }
+            }
+        }
+    }
+}
+
 pub struct ServantPlugin;
 
 impl Plugin for ServantPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             FixedUpdate,
-            (despown_if_no_master)
+            (despown_if_no_master, number_servants, limit_servants)
+                .chain()
                 .run_if(in_state(GameState::InGame))
                 .in_set(GameSet)
                 .before(PhysicsSet::SyncBackend),
         );
+        app.add_systems(
+            FixedUpdate,
+            control_servants
+                .run_if(in_state(GameState::InGame))
+                .after(GameSet)
+                .before(PhysicsSet::SyncBackend),
+        );
+        app.add_systems(Update, order_servants.run_if(in_state(GameState::InGame)));
     }
 }
@@ -1,11 +1,12 @@
 use crate::{
-    config::GameConfig,
+    constant::TILE_SIZE,
     controller::player::Player,
     entity::{actor::Actor, life::Life},
     hud::overlay::OverlayEvent,
-    level::{CurrentLevel, GameLevel},
+    level::{meta::LevelMetadata, modify::SetTileEvent, tile::Tile, CurrentLevel, GameLevel},
     physics::GamePhysics,
     player_state::PlayerState,
+    save::SaveSlots,
     states::GameState,
 };
 use bevy::{
@@ -21,10 +22,13 @@ fn process_debug_command(
     mut evr_kbd: EventReader<KeyboardInput>,
     mut local: Local<String>,
     mut level: ResMut<CurrentLevel>,
-    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
     mut writer: EventWriter<OverlayEvent>,
     mut physics: ResMut<GamePhysics>,
     player_query: Query<(&Player, &Actor, &Life)>,
+    pointer_query: Query<(&Actor, &Transform), With<Player>>,
+    mut tile_writer: EventWriter<SetTileEvent>,
+    metadata: Res<LevelMetadata>,
 ) {
     for ev in evr_kbd.read() {
         if ev.state == ButtonState::Released {
@@ -40,35 +44,48 @@ fn process_debug_command(
 
     if local.ends_with("next") {
         local.clear();
-        match level.next_level {
-            GameLevel::Level(n) => {
-                level.next_level = GameLevel::Level((n + 1) % 4);
-                level.next_state = PlayerState::from(player_query.get_single(), &config);
-            }
-            GameLevel::MultiPlayArena => {
-                level.next_level = GameLevel::Level(0);
-                level.next_state = PlayerState::from(player_query.get_single(), &config);
-            }
-        };
+        level.next_level = metadata.next(level.next_level);
+        level.next_state =
+            PlayerState::from(player_query.get_single(), &slots.current().player_name);
         writer.send(OverlayEvent::Close(GameState::Warp));
     } else if local.ends_with("home") {
         local.clear();
         level.next_level = GameLevel::Level(0);
-        level.next_state = PlayerState::from(player_query.get_single(), &config);
+        level.next_state =
+            PlayerState::from(player_query.get_single(), &slots.current().player_name);
         writer.send(OverlayEvent::Close(GameState::Warp));
     } else if local.ends_with("arena") {
         local.clear();
         level.next_level = GameLevel::MultiPlayArena;
-        level.next_state = PlayerState::from(player_query.get_single(), &config);
+        level.next_state =
+            PlayerState::from(player_query.get_single(), &slots.current().player_name);
         writer.send(OverlayEvent::Close(GameState::Warp));
     } else if local.ends_with("boss") {
         local.clear();
-        level.next_level = GameLevel::Level(3);
-        level.next_state = PlayerState::from(player_query.get_single(), &config);
+        level.next_level = GameLevel::Level(metadata.handcrafted_levels() - 1);
+        level.next_state =
+            PlayerState::from(player_query.get_single(), &slots.current().player_name);
         writer.send(OverlayEvent::Close(GameState::Warp));
     } else if local.ends_with("ending") {
         local.clear();
         writer.send(OverlayEvent::Close(GameState::Ending));
+    } else if local.ends_with("dig") || local.ends_with("build") {
+        // ポインターの位置のタイルを床または壁に変更します
+        let tile = if local.ends_with("dig") {
+            Tile::StoneTile
+        } else {
+            Tile::Wall
+        };
+        local.clear();
+        if let Ok((actor, transform)) = pointer_query.get_single() {
+            let pointer = transform.translation.truncate() + actor.pointer;
+            tile_writer.send(SetTileEvent {
+                x: (pointer.x / TILE_SIZE).floor() as i32,
+                y: (-pointer.y / TILE_SIZE).floor() as i32,
+                tile,
+                remote: false,
+            });
+        }
     } else if local.ends_with("pause") {
         local.clear();
         physics.active = false;
@@ -84,7 +101,9 @@ impl Plugin for DebugCommandPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             FixedUpdate,
-            process_debug_command.before(PhysicsSet::SyncBackend),
+            process_debug_command
+                .run_if(resource_exists::<LevelMetadata>)
+                .before(PhysicsSet::SyncBackend),
         );
     }
 }
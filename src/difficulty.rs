@@ -0,0 +1,223 @@
+use crate::asset::GameAssets;
+use crate::entity::actor::{Actor, ActorGroup};
+use crate::entity::life::Life;
+use crate::equipment::EquipmentType;
+use crate::inventory_item::InventoryItemType;
+use crate::level::meta::LevelMetadata;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::spell::SpellType;
+use crate::states::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+/// 深度に応じた難易度の上昇率を表します
+/// 最後の手作りのレベル(スライムの巣窟)を越えたあとのエンドレスモードでは、
+/// 深度がひとつ増えるたびに各係数がこの割合で上昇します
+/// assets/difficulty.curve.json から読み込まれ、読み込みが完了するとリソースとしても登録されます
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug)]
+pub struct DifficultyCurve {
+    /// 難易度の上昇が始まる深度
+    /// ファイルには含まれず、読み込んだときに手作りのレベルの数に合わせて設定されます
+    #[serde(skip)]
+    pub start_depth: i32,
+
+    /// 敵のライフの上昇率
+    pub life: f32,
+
+    /// 敵の弾丸のダメージの上昇率
+    pub damage: f32,
+
+    /// 敵の出現数の上昇率
+    pub spawns: f32,
+
+    /// 商品の価格の上昇率
+    pub price: f32,
+
+    /// 高価なアイテムの出やすさの上昇幅
+    pub rarity: f32,
+}
+
+/// ある深度での難易度の係数です
+/// 手作りのレベルではすべて等倍になります
+#[derive(Clone, Debug)]
+pub struct Difficulty {
+    pub life: f32,
+    pub damage: f32,
+    pub spawns: f32,
+    pub price: f32,
+
+    /// 0のときはすべてのアイテムが等確率で選ばれます
+    /// 大きくなるほど価格の高いアイテムが選ばれやすくなります
+    pub rarity: f32,
+
+    /// まだ解放されていないため、ドロップや敵の杖に現れないアイテムです
+    pub locked: Vec<InventoryItemType>,
+}
+
+impl DifficultyCurve {
+    pub fn at(&self, level: GameLevel) -> Difficulty {
+        let excess = match level.depth() {
+            Some(depth) => (depth - self.start_depth + 1).max(0),
+            None => 0,
+        };
+        Difficulty {
+            life: (1.0 + self.life).powi(excess),
+            damage: (1.0 + self.damage).powi(excess),
+            spawns: (1.0 + self.spawns).powi(excess),
+            price: (1.0 + self.price).powi(excess),
+            rarity: self.rarity * excess as f32,
+            locked: Vec::new(),
+        }
+    }
+}
+
+impl Difficulty {
+    pub fn scale_life(&self, life: i32) -> i32 {
+        (life as f32 * self.life).round() as i32
+    }
+
+    pub fn scale_damage(&self, damage: i32) -> i32 {
+        (damage as f32 * self.damage).round() as i32
+    }
+
+    pub fn scale_spawns(&self, count: u32) -> u32 {
+        (count as f32 * self.spawns).round() as u32
+    }
+
+    pub fn scale_price(&self, price: u32) -> u32 {
+        (price as f32 * self.price).round() as u32
+    }
+
+    /// まだ解放されていないアイテムを設定します
+    pub fn with_locked(self, locked: Vec<InventoryItemType>) -> Self {
+        Self { locked, ..self }
+    }
+
+    pub fn is_unlocked(&self, item: InventoryItemType) -> bool {
+        !self.locked.contains(&item)
+    }
+
+    /// 価格に応じた重みをつけて呪文を選びます
+    /// すべての呪文がまだ解放されていない場合は、解放の条件を無視して選びます
+    pub fn choose_spell(&self) -> SpellType {
+        let spells: Vec<SpellType> = SpellType::iter().collect();
+        self.choose_spell_from(&spells)
+            .or_else(|| {
+                self.clone()
+                    .with_locked(Vec::new())
+                    .choose_spell_from(&spells)
+            })
+            .expect("no spells")
+    }
+
+    /// 指定した呪文のうち解放されているものの中から、価格に応じた重みをつけて呪文を選びます
+    pub fn choose_spell_from(&self, spells: &[SpellType]) -> Option<SpellType> {
+        let spells: Vec<SpellType> = spells
+            .iter()
+            .filter(|s| self.is_unlocked(InventoryItemType::Spell(**s)))
+            .copied()
+            .collect();
+        spells
+            .choose_weighted(&mut rand::thread_rng(), |s| {
+                self.rarity_weight(s.to_props().price)
+            })
+            .ok()
+            .copied()
+    }
+
+    /// 価格に応じた重みをつけて装備を選びます
+    /// すべての装備がまだ解放されていない場合は、解放の条件を無視して選びます
+    pub fn choose_equipment(&self) -> EquipmentType {
+        let mut equipments: Vec<EquipmentType> = EquipmentType::iter()
+            .filter(|e| self.is_unlocked(InventoryItemType::Equipment(*e)))
+            .collect();
+        if equipments.is_empty() {
+            equipments = EquipmentType::iter().collect();
+        }
+        *equipments
+            .choose_weighted(&mut rand::thread_rng(), |e| {
+                self.rarity_weight(e.to_props().price)
+            })
+            .unwrap()
+    }
+
+    fn rarity_weight(&self, price: u32) -> f32 {
+        (price.max(1) as f32).powf(self.rarity)
+    }
+}
+
+/// レベルに出現した敵のライフを深度に応じて増やします
+/// リモートプレイヤーはアリーナにしか現れず、アリーナの係数は常に等倍です
+fn scale_enemy_life(
+    mut query: Query<(&Actor, &mut Life), Added<Actor>>,
+    current: Res<CurrentLevel>,
+    curve: Res<DifficultyCurve>,
+) {
+    if let Some(level) = current.level {
+        let difficulty = curve.at(level);
+        for (actor, mut life) in query.iter_mut() {
+            if actor.actor_group == ActorGroup::Enemy {
+                life.max_life = difficulty.scale_life(life.max_life);
+                life.life = difficulty.scale_life(life.life);
+            }
+        }
+    }
+}
+
+/// 読み込んだ難易度の上昇率をリソースとして登録し、上昇が始まる深度を手作りのレベルの数に合わせます
+fn setup_difficulty_curve(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    curve_assets: Res<Assets<DifficultyCurve>>,
+    metadata_assets: Res<Assets<LevelMetadata>>,
+) {
+    let mut curve = curve_assets
+        .get(&assets.difficulty_curve)
+        .expect("difficulty curve is not loaded")
+        .clone();
+    let metadata = metadata_assets
+        .get(&assets.level_meta)
+        .expect("level metadata is not loaded");
+    curve.start_depth = metadata.handcrafted_levels();
+    commands.insert_resource(curve);
+}
+
+#[derive(Default)]
+pub struct DifficultyCurveLoader;
+
+impl AssetLoader for DifficultyCurveLoader {
+    type Asset = DifficultyCurve;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["curve.json"]
+    }
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DifficultyCurve>();
+        app.init_asset_loader::<DifficultyCurveLoader>();
+        app.add_systems(OnExit(GameState::Setup), setup_difficulty_curve);
+        app.add_systems(Update, scale_enemy_life.run_if(in_state(GameState::InGame)));
+    }
+}
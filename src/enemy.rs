@@ -4,3 +4,4 @@ pub mod eyeball;
 pub mod huge_slime;
 pub mod sandbug;
 pub mod slime;
+pub mod witch;
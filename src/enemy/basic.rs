@@ -28,7 +28,7 @@ pub fn spawn_basic_enemy<T: Component>(
     actor_group: ActorGroup,
     master: Option<Entity>,
     max_life: i32,
-) {
+) -> Entity {
     let mut slots = [None; MAX_SPELLS_IN_WAND];
     slots[0] = Some(WandSpell {
         spell_type: spell,
@@ -114,6 +114,8 @@ pub fn spawn_basic_enemy<T: Component>(
     });
 
     if let Some(owner) = master {
-        builder.insert(Servant { master: owner });
+        builder.insert(Servant::new(owner));
     }
+
+    builder.id()
 }
@@ -1,8 +1,10 @@
 use crate::asset::GameAssets;
 use crate::constant::*;
+use crate::controller::servant::Servant;
 use crate::enemy::basic::spawn_basic_enemy;
 use crate::entity::actor::{Actor, ActorFireState, ActorGroup};
 use crate::hud::life_bar::LifeBarResource;
+use crate::level::terrain::Flying;
 use crate::physics::compare_distance;
 use crate::set::GameSet;
 use crate::spell::SpellType;
@@ -26,8 +28,9 @@ pub fn spawn_eyeball(
     position: Vec2,
     life_bar_locals: &Res<LifeBarResource>,
     actor_group: ActorGroup,
-) {
-    spawn_basic_enemy(
+    master: Option<Entity>,
+) -> Entity {
+    let entity = spawn_basic_enemy(
         &mut commands,
         match actor_group {
             ActorGroup::Player => assets.eyeball_friend.clone(),
@@ -41,13 +44,24 @@ pub fn spawn_eyeball(
         ENEMY_MOVE_FORCE,
         3,
         actor_group,
-        None,
+        master,
         25,
     );
+
+    // アイボールは宙に浮いているので、穴に落ちません
+    commands.entity(entity).insert(Flying);
+
+    entity
 }
 
 fn control_eyeball(
-    mut actor_query: Query<(Entity, Option<&EyeballControl>, &mut Actor, &mut Transform)>,
+    mut actor_query: Query<(
+        Entity,
+        Option<&EyeballControl>,
+        &mut Actor,
+        &mut Transform,
+        Option<&Servant>,
+    )>,
     rapier_context: Query<&RapierContext, With<DefaultRapierContext>>,
 ) {
     let context: &RapierContext = rapier_context.single();
@@ -55,11 +69,11 @@ fn control_eyeball(
     // 多対多の参照になるので、HashMapでキャッシュしておく
     let map: HashMap<Entity, (ActorGroup, Vec2)> = actor_query
         .iter()
-        .map(|(e, _, a, t)| (e, (a.actor_group, t.translation.truncate())))
+        .map(|(e, _, a, t, _)| (e, (a.actor_group, t.translation.truncate())))
         .collect();
 
     // 各アイボールの行動を選択します
-    for (eyeball_entity, eyeball_optional, mut eyeball_actor, eyeball_transform) in
+    for (eyeball_entity, eyeball_optional, mut eyeball_actor, eyeball_transform, servant) in
         actor_query.iter_mut()
     {
         if let Some(_) = eyeball_optional {
@@ -88,6 +102,11 @@ fn control_eyeball(
                 },
             );
 
+            // 攻撃命令を受けた使い魔は、その対象だけを狙います
+            if let Some((_, target)) = servant.and_then(|s| s.target()).and_then(|t| map.get(&t)) {
+                enemies = vec![*target];
+            }
+
             // 最も近くにいる、別グループのアクターに対して接近または攻撃
             let origin = eyeball_transform.translation.truncate();
             enemies.sort_by(compare_distance(origin));
@@ -1,5 +1,6 @@
 use crate::asset::GameAssets;
 use crate::audio::NextBGM;
+use crate::codex::CodexEvent;
 use crate::constant::*;
 use crate::controller::player::Player;
 use crate::entity::actor::{Actor, ActorFireState, ActorGroup, ActorState};
@@ -12,6 +13,7 @@ use crate::inventory::Inventory;
 use crate::se::{SEEvent, SE};
 use crate::spell::SpellType;
 use crate::states::GameState;
+use crate::stats::StatsEvent;
 use crate::wand::{Wand, WandSpell, WandType};
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::*;
@@ -313,11 +315,16 @@ fn despown(
     query: Query<(Entity, &Life), With<DespawnHugeSlime>>,
     mut bgm: ResMut<NextBGM>,
     assets: Res<GameAssets>,
+    mut codex_writer: EventWriter<CodexEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
 ) {
     for (entity, life) in query.iter() {
         if life.life <= 0 {
             commands.entity(entity).despawn_recursive();
             bgm.0 = Some(assets.dokutsu.clone());
+            codex_writer.send(CodexEvent::DefeatBoss);
+            stats_writer.send(StatsEvent::Kill("huge_slime".to_string()));
+            stats_writer.send(StatsEvent::DefeatBoss);
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::asset::GameAssets;
 use crate::constant::*;
+use crate::controller::servant::Servant;
 use crate::enemy::basic::spawn_basic_enemy;
 use crate::entity::actor::{Actor, ActorFireState, ActorGroup};
 use crate::hud::life_bar::LifeBarResource;
@@ -31,7 +32,7 @@ pub fn spawn_slime(
     gold: u32,
     group: ActorGroup,
     owner: Option<Entity>,
-) {
+) -> Entity {
     spawn_basic_enemy(
         &mut commands,
         match group {
@@ -48,7 +49,7 @@ pub fn spawn_slime(
         group,
         owner,
         15,
-    );
+    )
 }
 
 /// 1マス以上5マス以内にプレイヤーがいたら追いかけます
@@ -59,6 +60,7 @@ fn control_slime(
         Option<&mut SlimeControl>,
         &mut Actor,
         &mut Transform,
+        Option<&Servant>,
     )>,
     rapier_context: Query<&RapierContext, With<DefaultRapierContext>>,
 ) {
@@ -67,11 +69,13 @@ fn control_slime(
     // 多対多の参照になるので、HashMapでキャッシュしておく
     let map: HashMap<Entity, (ActorGroup, Vec2)> = actor_query
         .iter()
-        .map(|(e, _, a, t)| (e, (a.actor_group, t.translation.truncate())))
+        .map(|(e, _, a, t, _)| (e, (a.actor_group, t.translation.truncate())))
         .collect();
 
     // 各スライムの行動を選択します
-    for (slime_entity, slime_optional, mut slime_actor, slime_transform) in actor_query.iter_mut() {
+    for (slime_entity, slime_optional, mut slime_actor, slime_transform, servant) in
+        actor_query.iter_mut()
+    {
         if let Some(mut slime) = slime_optional {
             slime_actor.move_direction = Vec2::ZERO;
             slime_actor.fire_state = ActorFireState::Idle;
@@ -103,6 +107,11 @@ fn control_slime(
                 },
             );
 
+            // 攻撃命令を受けた使い魔は、その対象だけを狙います
+            if let Some((_, target)) = servant.and_then(|s| s.target()).and_then(|t| map.get(&t)) {
+                enemies = vec![*target];
+            }
+
             // 最も近くにいる、別グループのアクターに対して接近または攻撃
             let origin = slime_transform.translation.truncate();
             enemies.sort_by(compare_distance(origin));
@@ -0,0 +1,300 @@
+use crate::asset::GameAssets;
+use crate::constant::*;
+use crate::difficulty::Difficulty;
+use crate::entity::actor::{Actor, ActorFireState, ActorGroup};
+use crate::entity::dropped_item::spawn_dropped_item;
+use crate::entity::gold::spawn_gold;
+use crate::entity::life::Life;
+use crate::entity::witch::spawn_witch;
+use crate::hud::life_bar::LifeBarResource;
+use crate::inventory::{Inventory, InventoryItem};
+use crate::inventory_item::InventoryItemType;
+use crate::physics::compare_distance;
+use crate::se::{SEEvent, SE};
+use crate::set::GameSet;
+use crate::spell::{SpellCast, SpellType};
+use crate::states::GameState;
+use crate::stats::StatsEvent;
+use crate::wand::{Wand, WandSpell, WandType};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+/// プレイヤーと同じく杖を持って戦う、敵の魔法使いです
+/// 倒すと持っていた杖と呪文を落とします
+#[derive(Component)]
+pub struct WitchControl {
+    golds: u32,
+}
+
+/// 敵の魔法使いが持つ杖の数
+const WITCH_WANDS: usize = 2;
+
+const WITCH_LIFE: i32 = 60;
+
+const WITCH_GOLDS: u32 = 10;
+
+const WITCH_DETECTION_RANGE: f32 = TILE_SIZE * 12.0;
+
+/// 敵との距離がこれより短い場合は後退します
+const WITCH_RETREAT_RANGE: f32 = TILE_SIZE * 3.0;
+
+/// 敵との距離がこれより長い場合は接近します
+const WITCH_APPROACH_RANGE: f32 = TILE_SIZE * 6.0;
+
+/// 杖は random_witch_wands で生成するか、マルチプレイヤーではホストが生成したものを使います
+pub fn spawn_enemy_witch(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    life_bar_res: &Res<LifeBarResource>,
+    position: Vec2,
+    wands: [Option<Wand>; MAX_WANDS],
+) -> Entity {
+    let entity = spawn_witch(
+        commands,
+        &assets,
+        position,
+        0.0,
+        Uuid::new_v4(),
+        None,
+        WITCH_LIFE,
+        WITCH_LIFE,
+        &life_bar_res,
+        true,
+        3.0,
+        WITCH_GOLDS,
+        wands,
+        Inventory::new(),
+        [None; MAX_ITEMS_IN_EQUIPMENT],
+        WitchControl { golds: WITCH_GOLDS },
+        ActorGroup::Enemy,
+    );
+
+    commands.entity(entity).insert(Name::new("enemy_witch"));
+
+    entity
+}
+
+/// 敵の魔法使いが持つ杖をランダムに生成します
+pub fn random_witch_wands(difficulty: &Difficulty) -> [Option<Wand>; MAX_WANDS] {
+    let mut wands = [None, None, None, None];
+    for wand in wands.iter_mut().take(WITCH_WANDS) {
+        *wand = Some(random_wand(difficulty));
+    }
+    wands
+}
+
+/// 深度に応じた呪文を詰めた杖をランダムに生成します
+/// 杖の前半には弾丸を強化する呪文が、後半には弾丸の呪文が並びます
+/// すべての杖がまだ解放されていない場合は、解放の条件を無視して杖を選びます
+pub fn random_wand(difficulty: &Difficulty) -> Wand {
+    let mut rng = rand::thread_rng();
+
+    let wand_type = WandType::iter()
+        .filter(|w| difficulty.is_unlocked(InventoryItemType::Wand(*w)))
+        .choose(&mut rng)
+        .or_else(|| WandType::iter().choose(&mut rng))
+        .expect("no wands");
+    let capacity = wand_type.to_props().capacity.min(MAX_SPELLS_IN_WAND);
+
+    let bullets: Vec<SpellType> = SpellType::iter()
+        .filter(|s| match s.to_props().cast {
+            SpellCast::Bullet { .. } => true,
+            _ => false,
+        })
+        .collect();
+
+    let modifiers: Vec<SpellType> = SpellType::iter()
+        .filter(|s| match s.to_props().cast {
+            SpellCast::BulletSpeedUpDown { .. }
+            | SpellCast::MultipleCast { .. }
+            | SpellCast::Homing
+            | SpellCast::HeavyShot => true,
+            _ => false,
+        })
+        .collect();
+
+    let count = rng.gen_range(1..=capacity.min(4));
+    let modifier_count = rng.gen_range(0..count);
+
+    let mut slots = [None; MAX_SPELLS_IN_WAND];
+    for (i, slot) in slots.iter_mut().take(count).enumerate() {
+        let pool = if i < modifier_count {
+            &modifiers
+        } else {
+            &bullets
+        };
+        *slot = difficulty
+            .choose_spell_from(pool)
+            .map(|spell_type| WandSpell {
+                spell_type,
+                price: 0,
+            });
+    }
+
+    Wand::with_slots(wand_type, slots)
+}
+
+/// 杖に入っている最初の弾丸の射程をピクセル単位で返します
+fn wand_range(wand: &Wand) -> f32 {
+    wand.slots
+        .iter()
+        .filter_map(|s| *s)
+        .find_map(|s| match s.spell_type.to_props().cast {
+            // speed は 100 で 1フレームに 1ピクセル 移動する速度です
+            SpellCast::Bullet {
+                speed, lifetime, ..
+            } => Some(speed * 0.01 * lifetime as f32),
+            _ => None,
+        })
+        .unwrap_or(0.0)
+}
+
+/// 一定の距離を保ちながら、射程の届く杖を選んで攻撃します
+fn control_witch(
+    mut actor_query: Query<(Entity, Option<&WitchControl>, &mut Actor, &Transform)>,
+    rapier_context: Query<&RapierContext, With<DefaultRapierContext>>,
+) {
+    let context: &RapierContext = rapier_context.single();
+
+    // 多対多の参照になるので、HashMapでキャッシュしておく
+    let map: HashMap<Entity, (ActorGroup, Vec2)> = actor_query
+        .iter()
+        .map(|(e, _, a, t)| (e, (a.actor_group, t.translation.truncate())))
+        .collect();
+
+    for (witch_entity, witch_optional, mut witch_actor, witch_transform) in actor_query.iter_mut() {
+        if witch_optional.is_none() {
+            continue;
+        }
+
+        witch_actor.move_direction = Vec2::ZERO;
+        witch_actor.fire_state = ActorFireState::Idle;
+
+        // 指定した範囲にいる、自分以外で、かつ別のグループに所属するアクターの一覧を取得
+        let mut enemies: Vec<Vec2> = Vec::new();
+        context.intersections_with_shape(
+            witch_transform.translation.truncate(),
+            0.0,
+            &Collider::ball(WITCH_DETECTION_RANGE),
+            QueryFilter {
+                groups: Some(CollisionGroups::new(ENEMY_GROUP, WITCH_GROUP | ENEMY_GROUP)),
+                ..default()
+            },
+            |e| {
+                if e != witch_entity {
+                    if let Some((e_g, e_t)) = map.get(&e) {
+                        if *e_g != witch_actor.actor_group {
+                            enemies.push(*e_t);
+                        }
+                    }
+                }
+                true // 交差図形の検索を続ける
+            },
+        );
+
+        let origin = witch_transform.translation.truncate();
+        enemies.sort_by(compare_distance(origin));
+        if let Some(nearest) = enemies.first() {
+            let diff = nearest - origin;
+            let distance = diff.length();
+
+            witch_actor.pointer = diff;
+
+            witch_actor.move_direction = if distance < WITCH_RETREAT_RANGE {
+                -diff.normalize_or_zero()
+            } else if WITCH_APPROACH_RANGE < distance {
+                diff.normalize_or_zero()
+            } else {
+                Vec2::ZERO
+            };
+
+            // 詠唱の準備ができていて、射程が届く杖を選びます
+            let ready = witch_actor
+                .wands
+                .iter()
+                .enumerate()
+                .take(WITCH_WANDS)
+                .filter_map(|(i, w)| w.as_ref().map(|w| (i, w)))
+                .filter(|(_, w)| w.delay == 0 && distance < wand_range(w))
+                .map(|(i, _)| i)
+                .next();
+
+            if let Some(index) = ready {
+                witch_actor.current_wand = index;
+                witch_actor.fire_state = ActorFireState::Fire;
+            }
+        }
+    }
+}
+
+/// ライフがゼロになった敵の魔法使いを消滅させ、杖と呪文、金塊を落とします
+fn dead_witch(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    query: Query<(Entity, &WitchControl, &Actor, &Life, &Transform, &Name)>,
+    mut writer: EventWriter<SEEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
+) {
+    for (entity, witch, actor, life, transform, name) in query.iter() {
+        if life.life <= 0 {
+            let position = transform.translation.truncate();
+
+            commands.entity(entity).despawn_recursive();
+            writer.send(SEEvent::pos(SE::Cry, position));
+            stats_writer.send(StatsEvent::Kill(name.to_string()));
+
+            for _ in 0..witch.golds {
+                spawn_gold(&mut commands, &assets, position.x, position.y);
+            }
+
+            for wand in actor.wands.iter().filter_map(|w| w.as_ref()) {
+                spawn_dropped_item(
+                    &mut commands,
+                    &assets,
+                    position + random_offset(),
+                    InventoryItem {
+                        item_type: InventoryItemType::Wand(wand.wand_type),
+                        price: 0,
+                    },
+                );
+                for spell in wand.slots.iter().filter_map(|s| *s) {
+                    spawn_dropped_item(
+                        &mut commands,
+                        &assets,
+                        position + random_offset(),
+                        InventoryItem {
+                            item_type: InventoryItemType::Spell(spell.spell_type),
+                            price: 0,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn random_offset() -> Vec2 {
+    Vec2::new(
+        TILE_SIZE * (rand::random::<f32>() - 0.5),
+        TILE_SIZE * (rand::random::<f32>() - 0.5),
+    )
+}
+
+pub struct WitchControlPlugin;
+
+impl Plugin for WitchControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (control_witch, dead_witch)
+                .run_if(in_state(GameState::InGame))
+                .in_set(GameSet)
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
@@ -5,6 +5,7 @@ pub mod bullet;
 pub mod bullet_particle;
 pub mod chest;
 pub mod damege;
+pub mod door;
 pub mod dropped_item;
 pub mod gold;
 pub mod impact;
@@ -13,11 +14,15 @@ pub mod magic_circle;
 pub mod rabbit;
 pub mod servant_seed;
 pub mod shop;
+pub mod stash;
 pub mod stone_lantern;
+pub mod trap;
 pub mod witch;
 
 use crate::{
     constant::{ENTITY_LAYER_Z, Z_ORDER_SCALE},
+    entity::door::DoorLock,
+    entity::trap::TrapKind,
     states::GameState,
 };
 use bevy::{
@@ -34,6 +39,9 @@ pub enum GameEntity {
     MagicCircle,
     MagicCircleHome,
     MultiPlayArenaMagicCircle,
+
+    /// レベルの設定の exits にある、指定した番号の出口へ向かう魔法陣です
+    MagicCircleExit(u8),
     BrokenMagicCircle,
     Usage,
     Routes,
@@ -43,6 +51,10 @@ pub enum GameEntity {
     Rabbit,
     Sandbug,
     ShopDoor,
+    Stash,
+    Door(DoorLock),
+    DoorSwitch(u8),
+    Trap(TrapKind),
 }
 
 #[derive(Component)]
@@ -1,11 +1,17 @@
 use crate::cast::cast_spell;
 use crate::constant::{MAX_ITEMS_IN_EQUIPMENT, MAX_WANDS};
 use crate::controller::player::Equipment;
+use crate::controller::remote_monster::RemoteMonster;
+use crate::difficulty::DifficultyCurve;
 use crate::entity::life::Life;
 use crate::entity::life::LifeBeingSprite;
 use crate::entity::servant_seed::SpawnServantSeed;
 use crate::equipment::EquipmentType;
 use crate::inventory::Inventory;
+use crate::level::modify::SetTileEvent;
+use crate::level::streaming::Dormant;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::stats::StatsEvent;
 use crate::ui::floating::FloatingContent;
 use crate::wand::{Wand, WandSpell};
 use crate::{asset::GameAssets, se::SEEvent, states::GameState};
@@ -96,6 +102,7 @@ impl Actor {
             FloatingContent::WandSpell(w, s) => self.wands[w]
                 .as_ref()
                 .and_then(|wand| wand.slots[s].map(|spell| spell.spell_type.to_props().icon)),
+            FloatingContent::Stash(_) => None,
         }
     }
 
@@ -299,15 +306,21 @@ fn fire_bullet(
             &mut Transform,
             &mut ExternalImpulse,
         ),
-        Without<Camera2d>,
+        (Without<Camera2d>, Without<Dormant>, Without<RemoteMonster>),
     >,
     mut remote_writer: EventWriter<ClientMessage>,
     mut se_writer: EventWriter<SEEvent>,
     mut slime_writer: EventWriter<SpawnServantSeed>,
+    mut stats_writer: EventWriter<StatsEvent>,
+    mut tile_writer: EventWriter<SetTileEvent>,
     websocket: Res<WebSocketState>,
+    current: Res<CurrentLevel>,
+    curve: Res<DifficultyCurve>,
 ) {
     let online = websocket.ready_state == ReadyState::OPEN;
 
+    let difficulty = curve.at(current.level.unwrap_or(GameLevel::MultiPlayArena));
+
     for (actor_entity, mut actor, mut actor_life, actor_transform, mut actor_impulse) in
         actor_query.iter_mut()
     {
@@ -325,7 +338,12 @@ fn fire_bullet(
                 &mut remote_writer,
                 &mut se_writer,
                 &mut slime_writer,
+                &mut stats_writer,
+                &mut tile_writer,
                 current_wand,
+                &difficulty,
+                current.level,
+                current.chunk.as_ref(),
             );
         }
 
@@ -342,7 +360,12 @@ fn fire_bullet(
                 &mut remote_writer,
                 &mut se_writer,
                 &mut slime_writer,
+                &mut stats_writer,
+                &mut tile_writer,
                 MAX_WANDS - 1,
+                &difficulty,
+                current.level,
+                current.chunk.as_ref(),
             );
         }
 
@@ -356,7 +379,12 @@ fn fire_bullet(
 
 /// actor.move_direction の値に従って、アクターに外力を適用します
 /// 魔法の発射中は移動速度が低下します
-fn apply_external_force(mut player_query: Query<(&Actor, &mut ExternalForce)>) {
+fn apply_external_force(
+    mut player_query: Query<
+        (&Actor, &mut ExternalForce),
+        (Without<Dormant>, Without<RemoteMonster>),
+    >,
+) {
     for (actor, mut force) in player_query.iter_mut() {
         force.force = actor.move_direction
             * actor.get_total_move_force()
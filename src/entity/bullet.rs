@@ -1,11 +1,18 @@
+use crate::constant::TILE_SIZE;
+use crate::controller::player::Player;
 use crate::controller::remote::RemotePlayer;
+use crate::controller::remote_monster::RemoteMonster;
 use crate::entity::actor::Actor;
 use crate::entity::bullet_particle::BulletParticleResource;
 use crate::entity::life::Life;
 use crate::entity::EntityDepth;
+use crate::level::modify::SetTileEvent;
+use crate::level::tile::Tile;
 use crate::level::wall::WallCollider;
+use crate::level::CurrentLevel;
 use crate::se::SE;
 use crate::states::GameState;
+use crate::stats::StatsEvent;
 use crate::{entity::bullet_particle::spawn_particle_system, se::SEEvent};
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::{AseSpriteSlice, Aseprite};
@@ -194,7 +201,13 @@ fn bullet_collision(
     mut commands: Commands,
     mut bullet_query: Query<(Entity, &mut Bullet, &Transform, &Velocity)>,
     mut actor_query: Query<
-        (&mut Actor, Option<&mut ExternalImpulse>, &mut Life),
+        (
+            &mut Actor,
+            Option<&mut ExternalImpulse>,
+            &mut Life,
+            Option<&mut Player>,
+            Option<&RemoteMonster>,
+        ),
         Without<RemotePlayer>,
     >,
     mut lifebeing_query: Query<(&mut Life, Option<&mut ExternalImpulse>), Without<Actor>>,
@@ -203,6 +216,9 @@ fn bullet_collision(
     mut writer: EventWriter<SEEvent>,
     mut damage: EventWriter<SpawnDamageNumber>,
     resource: Res<BulletParticleResource>,
+    current: Res<CurrentLevel>,
+    mut tile_writer: EventWriter<SetTileEvent>,
+    mut stats: EventWriter<StatsEvent>,
 ) {
     // 弾丸が壁の角に当たった場合、衝突イベントが同時に複数回発生するため、
     // すでにdespawnしたentityに対して再びdespawnしてしまうことがあり、
@@ -225,6 +241,9 @@ fn bullet_collision(
                     &mut writer,
                     &mut damage,
                     &resource,
+                    &current,
+                    &mut tile_writer,
+                    &mut stats,
                 ) {
                     process_bullet_event(
                         &mut commands,
@@ -238,6 +257,9 @@ fn bullet_collision(
                         &mut writer,
                         &mut damage,
                         &resource,
+                        &current,
+                        &mut tile_writer,
+                        &mut stats,
                     );
                 }
             }
@@ -250,7 +272,13 @@ fn process_bullet_event(
     mut commands: &mut Commands,
     query: &Query<(Entity, &mut Bullet, &Transform, &Velocity)>,
     actors: &mut Query<
-        (&mut Actor, Option<&mut ExternalImpulse>, &mut Life),
+        (
+            &mut Actor,
+            Option<&mut ExternalImpulse>,
+            &mut Life,
+            Option<&mut Player>,
+            Option<&RemoteMonster>,
+        ),
         Without<RemotePlayer>,
     >,
     breakabke_query: &mut Query<(&mut Life, Option<&mut ExternalImpulse>), Without<Actor>>,
@@ -261,14 +289,27 @@ fn process_bullet_event(
     writer: &mut EventWriter<SEEvent>,
     damage: &mut EventWriter<SpawnDamageNumber>,
     resource: &Res<BulletParticleResource>,
+    current: &Res<CurrentLevel>,
+    tile_writer: &mut EventWriter<SetTileEvent>,
+    stats: &mut EventWriter<StatsEvent>,
 ) -> bool {
     if let Ok((bullet_entity, bullet, bullet_transform, bullet_velocity)) = query.get(*a) {
         let bullet_position = bullet_transform.translation.truncate();
 
         if !despownings.contains(&bullet_entity) {
-            if let Ok((actor, impilse, mut lifebeing)) = actors.get_mut(*b) {
+            if let Ok((actor, impilse, mut lifebeing, player, remote)) = actors.get_mut(*b) {
                 trace!("bullet hit actor: {:?}", actor.uuid);
 
+                // ホストから同期されているモンスターのダメージはホストで処理されるため、
+                // ここでは弾丸を消すだけにします
+                if remote.is_some() {
+                    despownings.insert(bullet_entity.clone());
+                    commands.entity(bullet_entity).despawn_recursive();
+                    spawn_particle_system(&mut commands, bullet_position, resource);
+                    writer.send(SEEvent::pos(SE::Damage, bullet_position));
+                    return true;
+                }
+
                 // 弾丸がアクターに衝突したとき
                 // このクエリにはプレイヤーキャラクター自身、発射したキャラクター自身も含まれることに注意
                 // 弾丸の詠唱者自身に命中した場合はダメージやノックバックはなし
@@ -276,6 +317,9 @@ fn process_bullet_event(
                 if bullet.owner == None || Some(actor.uuid) != bullet.owner {
                     lifebeing.life = (lifebeing.life - bullet.damage).max(0);
                     lifebeing.amplitude = 6.0;
+                    if let Some(mut player) = player {
+                        player.last_attacker = bullet.owner;
+                    }
                     if let Some(mut impilse) = impilse {
                         impilse.impulse +=
                             bullet_velocity.linvel.normalize_or_zero() * bullet.impulse;
@@ -288,6 +332,11 @@ fn process_bullet_event(
                         position: bullet_position,
                     });
                     writer.send(SEEvent::pos(SE::Damage, bullet_position));
+                    stats.send(StatsEvent::Damage {
+                        attacker: bullet.owner,
+                        victim: actor.uuid,
+                        damage: bullet.damage,
+                    });
                 }
             } else if let Ok((mut breakabke, impulse_optional)) = breakabke_query.get_mut(*b) {
                 trace!("bullet hit: {:?}", b);
@@ -310,7 +359,27 @@ fn process_bullet_event(
                 despownings.insert(bullet_entity.clone());
                 commands.entity(bullet_entity).despawn_recursive();
                 spawn_particle_system(&mut commands, bullet_position, resource);
-                writer.send(SEEvent::pos(SE::Steps, bullet_position));
+
+                // 弾丸の進行方向の少し先にあるタイルがひび割れた壁であれば、それを壊します
+                let hit =
+                    bullet_position + bullet_velocity.linvel.normalize_or_zero() * TILE_SIZE * 0.5;
+                let x = (hit.x / TILE_SIZE).floor() as i32;
+                let y = (-hit.y / TILE_SIZE).floor() as i32;
+                let cracked = current
+                    .chunk
+                    .as_ref()
+                    .map_or(false, |c| c.get_tile(x, y) == Tile::CrackedWall);
+                if cracked {
+                    tile_writer.send(SetTileEvent {
+                        x,
+                        y,
+                        tile: Tile::StoneTile,
+                        remote: false,
+                    });
+                    writer.send(SEEvent::pos(SE::Break, bullet_position));
+                } else {
+                    writer.send(SEEvent::pos(SE::Steps, bullet_position));
+                }
             } else {
                 trace!("bullet hit unknown entity: {:?}", b);
                 despownings.insert(bullet_entity.clone());
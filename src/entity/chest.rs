@@ -26,7 +26,7 @@ pub enum ChestType {
 pub const CHEST_OR_BARREL: [ChestType; 2] = [ChestType::Crate, ChestType::Barrel];
 
 #[derive(Default, Component, Reflect)]
-struct Chest {
+pub struct Chest {
     pub chest_type: ChestType,
     pub golds: i32,
 }
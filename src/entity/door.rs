@@ -0,0 +1,370 @@
+use crate::{
+    asset::GameAssets,
+    constant::{
+        ENEMY_BULLET_GROUP, ENEMY_GROUP, ENTITY_GROUP, FLOOR_LAYER_Z, PAINT_LAYER_Z, RABBIT_GROUP,
+        SENSOR_GROUP, TILE_SIZE, WALL_GROUP, WITCH_BULLET_GROUP, WITCH_GROUP,
+    },
+    controller::player::Player,
+    enemy::huge_slime::Boss,
+    inventory_item::InventoryItemType,
+    language::Dict,
+    level::{setup_level, CurrentLevel, GameLevel},
+    se::{SEEvent, SE},
+    speech_bubble::SpeechEvent,
+    states::GameState,
+    wand::WandType,
+};
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::AseSpriteSlice;
+use bevy_rapier2d::prelude::*;
+use std::collections::HashSet;
+
+use super::{actor::Actor, trap::PressurePlateEvent, EntityDepth};
+
+/// 扉を開く条件です
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DoorLock {
+    /// 近づくだけで開きます
+    Unlocked,
+
+    /// 鍵の杖を持っていれば、近づいたときに開きます
+    Key,
+
+    /// レベルのボスがすべて倒されると開きます
+    /// ボスのいないレベルでは最初から開いています
+    Boss,
+
+    /// 同じ番号のスイッチか感圧板を踏むと開きます
+    Switch(u8),
+}
+
+#[derive(Component)]
+pub struct Door {
+    level: GameLevel,
+    lock: DoorLock,
+
+    /// レベルの左上を原点とする扉の位置です
+    local: (i32, i32),
+
+    open: bool,
+}
+
+/// 扉に近づいたプレイヤーを検出するセンサーです
+#[derive(Component)]
+struct DoorSensor;
+
+/// 踏むと同じ番号の扉が開くスイッチです
+#[derive(Component)]
+struct DoorSwitch {
+    level: GameLevel,
+    id: u8,
+}
+
+/// 現在のレベルで開いた扉と押されたスイッチです
+/// レベルを移動するとリセットされます
+/// 魔法陣でフェードせずに移動した先のレベルの扉とスイッチも含みます
+/// スイッチの番号はレベルごとに独立しています
+/// レベルは読み込まれるたびに異なる位置に配置されるので、扉はレベルの左上を原点とする位置で記録します
+#[derive(Resource, Default)]
+pub struct DoorStates {
+    level: Option<GameLevel>,
+    opened: HashSet<(GameLevel, (i32, i32))>,
+    switches: HashSet<(GameLevel, u8)>,
+}
+
+pub fn spawn_door(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    level: GameLevel,
+    x: i32,
+    y: i32,
+    local: (i32, i32),
+    lock: DoorLock,
+) {
+    let position = Vec2::new(
+        TILE_SIZE * x as f32 + TILE_SIZE * 0.5,
+        TILE_SIZE * -y as f32 - TILE_SIZE * 0.5,
+    );
+
+    // 扉の専用のスプライトはまだないので、商店の扉を鍵の種類に応じて着色して表示しています
+    let color = match lock {
+        DoorLock::Unlocked => Color::WHITE,
+        DoorLock::Key => Color::srgb(1.0, 0.85, 0.3),
+        DoorLock::Boss => Color::srgb(0.8, 0.4, 1.0),
+        DoorLock::Switch(_) => Color::srgb(0.5, 0.9, 1.0),
+    };
+
+    commands
+        .spawn((
+            Name::new("door"),
+            Door {
+                level,
+                lock,
+                local,
+                open: false,
+            },
+            StateScoped(GameState::InGame),
+            Transform::from_translation(position.extend(0.0)),
+            EntityDepth,
+            Visibility::default(),
+            RigidBody::Fixed,
+            Collider::cuboid(TILE_SIZE * 0.5, TILE_SIZE * 0.5),
+            CollisionGroups::new(
+                WALL_GROUP,
+                ENTITY_GROUP
+                    | WITCH_GROUP
+                    | WITCH_BULLET_GROUP
+                    | ENEMY_GROUP
+                    | ENEMY_BULLET_GROUP
+                    | RABBIT_GROUP,
+            ),
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Sprite { color, ..default() },
+                AseSpriteSlice {
+                    aseprite: assets.atlas.clone(),
+                    name: "door_left".into(),
+                },
+            ));
+
+            builder.spawn((
+                DoorSensor,
+                Sensor,
+                Collider::ball(TILE_SIZE * 1.5),
+                Transform::default(),
+                ActiveEvents::COLLISION_EVENTS,
+                CollisionGroups::new(SENSOR_GROUP, WITCH_GROUP),
+            ));
+        });
+}
+
+pub fn spawn_door_switch(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    level: GameLevel,
+    x: i32,
+    y: i32,
+    id: u8,
+) {
+    commands.spawn((
+        Name::new("door switch"),
+        DoorSwitch { level, id },
+        StateScoped(GameState::InGame),
+        // スイッチの専用のスプライトはまだないので、床のタイルを着色して表示しています
+        Transform::from_translation(Vec3::new(
+            TILE_SIZE * x as f32,
+            TILE_SIZE * -y as f32,
+            (FLOOR_LAYER_Z + PAINT_LAYER_Z) * 0.5,
+        )),
+        Sprite::default(),
+        AseSpriteSlice {
+            aseprite: assets.atlas.clone(),
+            name: "stone tile".into(),
+        },
+        Sensor,
+        Collider::cuboid(TILE_SIZE * 0.4, TILE_SIZE * 0.4),
+        ActiveEvents::COLLISION_EVENTS,
+        CollisionGroups::new(SENSOR_GROUP, WITCH_GROUP),
+    ));
+}
+
+/// レベルが変わったときに扉とスイッチの状態をリセットします
+fn reset_door_states(current: Res<CurrentLevel>, mut states: ResMut<DoorStates>) {
+    if states.level != Some(current.next_level) {
+        states.level = Some(current.next_level);
+        states.opened.clear();
+        states.switches.clear();
+    }
+}
+
+/// 同じレベルですでに開かれた扉を、開いた状態で復元します
+fn restore_doors(mut query: Query<&mut Door, Added<Door>>, states: Res<DoorStates>) {
+    for mut door in query.iter_mut() {
+        if states.opened.contains(&(door.level, door.local)) {
+            door.open = true;
+        }
+    }
+}
+
+fn has_key(actor: &Actor) -> bool {
+    actor
+        .wands
+        .iter()
+        .any(|w| w.as_ref().map(|w| w.wand_type) == Some(WandType::KeyWand))
+        || actor
+            .inventory
+            .0
+            .iter()
+            .any(|i| i.map(|i| i.item_type) == Some(InventoryItemType::Wand(WandType::KeyWand)))
+}
+
+/// プレイヤーが扉に近づいたとき、鍵の種類に応じて扉を開くか、開かない理由を吹き出しで表示します
+fn door_sensor(
+    mut collision_events: EventReader<CollisionEvent>,
+    sensor_query: Query<&Parent, With<DoorSensor>>,
+    mut door_query: Query<(&mut Door, &Transform)>,
+    player_query: Query<&Actor, With<Player>>,
+    mut speech_writer: EventWriter<SpeechEvent>,
+) {
+    for collision_event in collision_events.read() {
+        match collision_event {
+            CollisionEvent::Started(a, b, ..) => {
+                let (sensor, player) = if sensor_query.contains(*a) {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                let (Ok(parent), Ok(actor)) =
+                    (sensor_query.get(*sensor), player_query.get(*player))
+                else {
+                    continue;
+                };
+                let Ok((mut door, transform)) = door_query.get_mut(parent.get()) else {
+                    continue;
+                };
+                if door.open {
+                    continue;
+                }
+                let message = match door.lock {
+                    DoorLock::Unlocked => None,
+                    DoorLock::Key if has_key(actor) => None,
+                    DoorLock::Key => Some(Dict {
+                        ja: "鍵がかかっている".to_string(),
+                        en: "It's locked".to_string(),
+                    }),
+                    DoorLock::Boss => Some(Dict {
+                        ja: "強大な魔物の気配が扉を封じている".to_string(),
+                        en: "A powerful presence seals this door".to_string(),
+                    }),
+                    DoorLock::Switch(_) => Some(Dict {
+                        ja: "どこかに扉を開く仕掛けがあるようだ".to_string(),
+                        en: "There must be a switch somewhere".to_string(),
+                    }),
+                };
+                match message {
+                    Some(message) => {
+                        speech_writer.send(SpeechEvent::SpeechAt(
+                            message,
+                            transform.translation.truncate(),
+                        ));
+                    }
+                    None => door.open = true,
+                }
+            }
+            CollisionEvent::Stopped(a, b, ..) => {
+                if (sensor_query.contains(*a) && player_query.contains(*b))
+                    || (sensor_query.contains(*b) && player_query.contains(*a))
+                {
+                    speech_writer.send(SpeechEvent::Close);
+                }
+            }
+        }
+    }
+}
+
+fn press_switch(
+    mut collision_events: EventReader<CollisionEvent>,
+    switch_query: Query<(&DoorSwitch, &Transform)>,
+    player_query: Query<&Actor, With<Player>>,
+    mut states: ResMut<DoorStates>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    for collision_event in collision_events.read() {
+        if let CollisionEvent::Started(a, b, ..) = collision_event {
+            let switch = if player_query.contains(*b) {
+                switch_query.get(*a)
+            } else if player_query.contains(*a) {
+                switch_query.get(*b)
+            } else {
+                continue;
+            };
+            if let Ok((switch, transform)) = switch {
+                if states.switches.insert((switch.level, switch.id)) {
+                    se_writer.send(SEEvent::pos(SE::Switch, transform.translation.truncate()));
+                }
+            }
+        }
+    }
+}
+
+/// 感圧板が踏まれたときに、同じ番号のスイッチが押されたものとして扱います
+fn press_plate_switch(mut reader: EventReader<PressurePlateEvent>, mut states: ResMut<DoorStates>) {
+    for event in reader.read() {
+        states.switches.insert((event.level, event.link));
+    }
+}
+
+fn update_switch_sprite(mut query: Query<(&DoorSwitch, &mut Sprite)>, states: Res<DoorStates>) {
+    for (switch, mut sprite) in query.iter_mut() {
+        sprite.color = if states.switches.contains(&(switch.level, switch.id)) {
+            Color::srgb(0.4, 1.0, 0.4)
+        } else {
+            Color::srgb(1.0, 0.4, 0.4)
+        };
+    }
+}
+
+/// ボスの撃破とスイッチによって扉を開きます
+fn unlock_doors(mut query: Query<&mut Door>, boss_query: Query<&Boss>, states: Res<DoorStates>) {
+    for mut door in query.iter_mut() {
+        if door.open {
+            continue;
+        }
+        let unlocked = match door.lock {
+            DoorLock::Boss => boss_query.is_empty(),
+            DoorLock::Switch(id) => states.switches.contains(&(door.level, id)),
+            DoorLock::Unlocked | DoorLock::Key => false,
+        };
+        if unlocked {
+            door.open = true;
+        }
+    }
+}
+
+/// 開いた扉の衝突形状とスプライトを取り除き、開いた扉としてレベルに記録します
+fn open_doors(
+    mut commands: Commands,
+    query: Query<(Entity, &Door, &Transform), Changed<Door>>,
+    mut states: ResMut<DoorStates>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    for (entity, door, transform) in query.iter() {
+        if door.open {
+            commands
+                .entity(entity)
+                .remove::<Collider>()
+                .insert(Visibility::Hidden);
+            if states.opened.insert((door.level, door.local)) {
+                se_writer.send(SEEvent::pos(SE::TurnOn, transform.translation.truncate()));
+            }
+        }
+    }
+}
+
+pub struct DoorPlugin;
+
+impl Plugin for DoorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DoorStates>();
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            reset_door_states.before(setup_level),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                restore_doors,
+                door_sensor,
+                press_switch,
+                press_plate_switch,
+                update_switch_sprite,
+                unlock_doors,
+                open_doors,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::codex::CodexEvent;
 use crate::controller::player::Player;
 use crate::entity::EntityDepth;
 use crate::inventory::InventoryItem;
@@ -15,7 +16,7 @@ use super::life::Life;
 
 #[derive(Component)]
 pub struct DroppedItemEntity {
-    item: InventoryItem,
+    pub item: InventoryItem,
 }
 
 #[derive(Component)]
@@ -152,6 +153,7 @@ fn collision(
     item_query: Query<&DroppedItemEntity>,
     mut player_query: Query<&mut Actor, With<Player>>,
     mut global: EventWriter<SEEvent>,
+    mut codex_writer: EventWriter<CodexEvent>,
 ) {
     for collision_event in collision_events.read() {
         match collision_event {
@@ -163,6 +165,7 @@ fn collision(
                     &item_query,
                     &mut player_query,
                     &mut global,
+                    &mut codex_writer,
                 ) || chat_start(
                     &mut commands,
                     b,
@@ -170,6 +173,7 @@ fn collision(
                     &item_query,
                     &mut player_query,
                     &mut global,
+                    &mut codex_writer,
                 );
             }
             CollisionEvent::Stopped(..) => {}
@@ -184,12 +188,14 @@ fn chat_start(
     item_query: &Query<&DroppedItemEntity>,
     player_query: &mut Query<&mut Actor, With<Player>>,
     global: &mut EventWriter<SEEvent>,
+    codex_writer: &mut EventWriter<CodexEvent>,
 ) -> bool {
     match (item_query.get(*a), player_query.get_mut(*b)) {
         (Ok(item), Ok(mut actor)) => {
             if actor.inventory.insert(item.item) {
                 commands.entity(*a).despawn_recursive();
                 global.send(SEEvent::new(SE::PickUp));
+                codex_writer.send(CodexEvent::Acquire(item.item.item_type));
                 return true;
             }
         }
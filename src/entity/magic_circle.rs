@@ -1,14 +1,21 @@
 use crate::{
     asset::GameAssets,
+    config::GameConfig,
     constant::*,
     controller::player::Player,
     hud::overlay::OverlayEvent,
-    level::{CurrentLevel, GameLevel},
+    language::Dict,
+    level::{
+        meta::LevelMetadata,
+        streaming::{is_seamless, SeamlessWarpEvent},
+        CurrentLevel, GameLevel,
+    },
     player_state::PlayerState,
     se::{SEEvent, SE},
+    speech_bubble::SpeechEvent,
     states::GameState,
 };
-use bevy::prelude::*;
+use bevy::{prelude::*, text::FontSmoothing};
 use bevy_aseprite_ultra::prelude::*;
 use bevy_light_2d::light::PointLight2d;
 use bevy_rapier2d::{
@@ -28,6 +35,25 @@ pub enum MagicCircleDestination {
     NextLevel,
     Home,
     MultiplayArena,
+
+    /// レベルの設定の exits にある、指定した番号の出口です
+    Exit(u8),
+}
+
+impl MagicCircleDestination {
+    /// 魔法陣のあるレベルから見た行き先のレベルを返します
+    /// 存在しない出口を指定した場合は、次のレベルへ向かいます
+    fn resolve(&self, from: GameLevel, metadata: &LevelMetadata) -> GameLevel {
+        match self {
+            MagicCircleDestination::NextLevel => metadata.next(from),
+            MagicCircleDestination::Home => GameLevel::Level(0),
+            MagicCircleDestination::MultiplayArena => GameLevel::MultiPlayArena,
+            MagicCircleDestination::Exit(exit) => metadata.exit(from, *exit).unwrap_or_else(|| {
+                warn!("exit {} is not defined in {:?}", exit, from);
+                metadata.next(from)
+            }),
+        }
+    }
 }
 
 #[derive(Component)]
@@ -35,9 +61,14 @@ pub struct MagicCircle {
     players: i32,
     step: i32,
     light: Entity,
+    level: GameLevel,
     destination: MagicCircleDestination,
 }
 
+/// 魔法陣の上に表示される行き先のレベルの名前です
+#[derive(Component)]
+struct MagicCircleLabel;
+
 #[derive(Component)]
 struct MagicStar;
 
@@ -49,6 +80,7 @@ pub fn spawn_magic_circle(
     assets: &Res<GameAssets>,
     x: f32,
     y: f32,
+    level: GameLevel,
     destination: MagicCircleDestination,
 ) {
     let light_entity = commands.spawn_empty().id();
@@ -61,6 +93,7 @@ pub fn spawn_magic_circle(
                 players: 0,
                 step: 0,
                 light: light_entity,
+                level,
                 destination,
             },
             Transform::from_translation(Vec3::new(x, y, PAINT_LAYER_Z)),
@@ -90,6 +123,21 @@ pub fn spawn_magic_circle(
                     ..default()
                 },
             ));
+
+            parent.spawn((
+                Name::new("magic_circle_label"),
+                MagicCircleLabel,
+                Text2d::new(""),
+                TextColor(Color::srgba(1.0, 1.0, 1.0, 0.6)),
+                TextFont {
+                    font: assets.dotgothic.clone(),
+                    font_size: 32.0,
+                    font_smoothing: FontSmoothing::AntiAliased,
+                    ..default()
+                },
+                Transform::from_xyz(0.0, TILE_SIZE, ENTITY_LAYER_Z - PAINT_LAYER_Z)
+                    .with_scale(Vec3::new(0.25, 0.25, 1.0)),
+            ));
         });
 
     // 光源をスプライトの子にすると、画面外に出た時に光が消えてしまうことに注意
@@ -137,6 +185,8 @@ fn warp(
     mut next: ResMut<CurrentLevel>,
     mut writer: EventWriter<SEEvent>,
     mut overlay_event_writer: EventWriter<OverlayEvent>,
+    mut seamless_writer: EventWriter<SeamlessWarpEvent>,
+    metadata: Res<LevelMetadata>,
 ) {
     for (mut circle, transform) in circle_query.iter_mut() {
         if circle.step < MAX_POWER {
@@ -146,11 +196,22 @@ fn warp(
                 circle.step = (circle.step - 1).max(0);
             }
         } else if circle.step == MAX_POWER {
+            let destination = circle.destination.resolve(circle.level, &metadata);
+
+            // 隣り合うレベルへはフェードせずにそのまま移動します
+            if is_seamless(circle.level, destination) {
+                writer.send(SEEvent::pos(SE::Warp, transform.translation.truncate()));
+                seamless_writer.send(SeamlessWarpEvent { level: destination });
+                circle.step = 0;
+                continue;
+            }
+
             if let Ok((entity, player, actor, actor_life)) = player_query.get_single_mut() {
                 writer.send(SEEvent::pos(SE::Warp, transform.translation.truncate()));
                 commands.entity(entity).despawn_recursive();
 
-                let player_state = PlayerState {
+                next.next_level = destination;
+                next.next_state = PlayerState {
                     name: player.name.clone(),
                     life: actor_life.life,
                     max_life: actor_life.max_life,
@@ -159,29 +220,6 @@ fn warp(
                     wands: actor.wands.clone(),
                     golds: actor.golds,
                 };
-
-                match circle.destination {
-                    MagicCircleDestination::NextLevel => {
-                        match next.next_level {
-                            GameLevel::Level(level) => {
-                                next.next_level = GameLevel::Level((level + 1) % LEVELS);
-                                next.next_state = player_state;
-                            }
-                            GameLevel::MultiPlayArena => {
-                                next.next_level = GameLevel::Level(1);
-                                next.next_state = player_state;
-                            }
-                        };
-                    }
-                    MagicCircleDestination::Home => {
-                        next.next_level = GameLevel::Level(0);
-                        next.next_state = player_state;
-                    }
-                    MagicCircleDestination::MultiplayArena => {
-                        next.next_level = GameLevel::MultiPlayArena;
-                        next.next_state = player_state;
-                    }
-                }
             }
             circle.step += 1;
         } else if circle.step == MAX_POWER + 120 {
@@ -210,6 +248,70 @@ fn update_circle_color(
     }
 }
 
+/// 行き先のレベルの名前を魔法陣の上に表示します
+fn update_circle_label(
+    circle_query: Query<&MagicCircle>,
+    mut label_query: Query<(&Parent, &mut Text2d), Added<MagicCircleLabel>>,
+    metadata: Res<LevelMetadata>,
+    config: Res<GameConfig>,
+) {
+    for (parent, mut text) in label_query.iter_mut() {
+        if let Ok(circle) = circle_query.get(parent.get()) {
+            let destination = circle.destination.resolve(circle.level, &metadata);
+            text.0 = metadata.get(destination).name.get(config.language);
+        }
+    }
+}
+
+/// プレイヤーが魔法陣に乗ったときに、行き先のレベルと深度を吹き出しで表示します
+fn preview_destination(
+    mut events: EventReader<CollisionEvent>,
+    player_query: Query<&Player>,
+    circle_query: Query<(&MagicCircle, &Transform)>,
+    metadata: Res<LevelMetadata>,
+    mut speech_writer: EventWriter<SpeechEvent>,
+) {
+    for event in events.read() {
+        match event {
+            CollisionEvent::Started(a, b, _) => {
+                let circle = if player_query.contains(*a) {
+                    circle_query.get(*b)
+                } else if player_query.contains(*b) {
+                    circle_query.get(*a)
+                } else {
+                    continue;
+                };
+                let Ok((circle, transform)) = circle else {
+                    continue;
+                };
+                let destination = circle.destination.resolve(circle.level, &metadata);
+                let name = &metadata.get(destination).name;
+                let message = match destination.depth() {
+                    Some(depth) => Dict {
+                        ja: format!("行き先: {} 深度{}", name.ja, depth),
+                        en: format!("To: {} Depth {}", name.en, depth),
+                    },
+                    None => Dict {
+                        ja: format!("行き先: {}", name.ja),
+                        en: format!("To: {}", name.en),
+                    },
+                };
+                speech_writer.send(SpeechEvent::SpeechAt(
+                    message,
+                    transform.translation.truncate(),
+                ));
+            }
+            CollisionEvent::Stopped(a, b, _) => {
+                if (player_query.contains(*a) && circle_query.contains(*b))
+                    || (player_query.contains(*b) && circle_query.contains(*a))
+                {
+                    speech_writer.send(SpeechEvent::Close);
+                }
+            }
+        }
+    }
+}
+
 fn process_collision_start_event(
     a: &Entity,
     b: &Entity,
@@ -269,13 +371,18 @@ impl Plugin for MagicCirclePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             FixedUpdate,
-            (power_on_circle, warp)
+            (power_on_circle, preview_destination, warp)
                 .run_if(in_state(GameState::InGame))
                 .before(PhysicsSet::SyncBackend),
         );
         app.add_systems(
             Update,
-            (update_circle_color, change_slice, change_star_slice)
+            (
+                update_circle_color,
+                change_slice,
+                change_star_slice,
+                update_circle_label,
+            )
                 .run_if(in_state(GameState::InGame)),
         );
     }
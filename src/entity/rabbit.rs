@@ -11,6 +11,7 @@ use crate::language::Dict;
 use crate::se::{SEEvent, SE};
 use crate::speech_bubble::SpeechEvent;
 use crate::states::GameState;
+use crate::stats::StatsEvent;
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::{AseSpriteAnimation, AseSpriteSlice};
 use bevy_rapier2d::prelude::*;
@@ -123,6 +124,7 @@ fn collision_inner_sensor(
     mut player_query: Query<&mut Actor, With<Player>>,
     mut speech_writer: EventWriter<SpeechEvent>,
     mut se: EventWriter<SEEvent>,
+    mut stats_writer: EventWriter<StatsEvent>,
 ) {
     for collision_event in collision_events.read() {
         match collision_event {
@@ -135,6 +137,7 @@ fn collision_inner_sensor(
                     &mut player_query,
                     &mut speech_writer,
                     &mut se,
+                    &mut stats_writer,
                 ) || chat_start(
                     b,
                     a,
@@ -143,6 +146,7 @@ fn collision_inner_sensor(
                     &mut player_query,
                     &mut speech_writer,
                     &mut se,
+                    &mut stats_writer,
                 );
             }
             CollisionEvent::Stopped(a, b, _option) => {
@@ -161,6 +165,7 @@ fn chat_start(
     player_query: &mut Query<&mut Actor, With<Player>>,
     speech_writer: &mut EventWriter<SpeechEvent>,
     se: &mut EventWriter<SEEvent>,
+    stats_writer: &mut EventWriter<StatsEvent>,
 ) -> bool {
     let mut camera = camera_query.single_mut();
 
@@ -171,6 +176,7 @@ fn chat_start(
                 if actor.liquidate() {
                     camera.target = Some(*a);
                     se.send(SEEvent::new(SE::Register));
+                    stats_writer.send(StatsEvent::SpendGold(dept));
                     speech_writer.send(SpeechEvent::Speech(Dict {
                         ja: format!("合計{}ゴールドのお買い上げ！\nありがとう", dept).to_string(),
                         en: format!("Your total is {} Golds\nThank you", dept).to_string(),
@@ -168,6 +168,7 @@ fn spawn_servant(
                     event.position,
                     &life_bar_locals,
                     event.actor_group,
+                    Some(event.master),
                 );
             }
         }
@@ -0,0 +1,65 @@
+use crate::entity::EntityDepth;
+use crate::level::map::LevelChunk;
+use crate::{asset::GameAssets, constant::*, states::GameState};
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+/// プレイヤーがこの距離より近くにいるときにインベントリを開くと、保管箱の中身も表示されます
+pub const STASH_RANGE: f32 = TILE_SIZE * 2.0;
+
+/// 周回をまたいでアイテムを預けておける保管箱です
+/// 中身はセーブスロットに保存されます
+#[derive(Component)]
+pub struct Stash;
+
+/// 保管箱を生成します
+/// 指定する位置はタイルの中心のピクセル座標です
+pub fn spawn_stash(commands: &mut Commands, assets: &Res<GameAssets>, position: Vec2) {
+    commands
+        .spawn((
+            Name::new("stash"),
+            StateScoped(GameState::InGame),
+            Stash,
+            EntityDepth,
+            Transform::from_translation(position.extend(0.0)),
+            GlobalTransform::default(),
+            InheritedVisibility::default(),
+            RigidBody::Fixed,
+            Collider::cuboid(8.0, 8.0),
+            CollisionGroups::new(
+                ENTITY_GROUP,
+                ENTITY_GROUP | WITCH_GROUP | WITCH_BULLET_GROUP | ENEMY_GROUP | ENEMY_BULLET_GROUP,
+            ),
+        ))
+        .with_child(AseSpriteSlice {
+            aseprite: assets.atlas.clone(),
+            name: "chest".into(),
+        });
+}
+
+/// レベルに保管箱が配置されていない場合に、保管箱を置くタイルを選びます
+/// 最初の出現位置の周囲で、ほかのエンティティと重ならない床を近いほうから探します
+pub fn find_stash_tile(chunk: &LevelChunk) -> Option<(i32, i32)> {
+    let entry_point = chunk.entry_points.first()?;
+    let (ex, ey) = (entry_point.x as i32, entry_point.y as i32);
+    for distance in 2..8 {
+        for dy in -distance..=distance {
+            for dx in -distance..=distance {
+                if dx.abs().max(dy.abs()) != distance {
+                    continue;
+                }
+                let (x, y) = (ex + dx, ey + dy);
+                if chunk.is_floor(x, y)
+                    && !chunk
+                        .entities
+                        .iter()
+                        .any(|(_, entity_x, entity_y)| *entity_x == x && *entity_y == y)
+                {
+                    return Some((x, y));
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,463 @@
+use crate::asset::GameAssets;
+use crate::constant::*;
+use crate::controller::player::Player;
+use crate::controller::remote::RemotePlayer;
+use crate::difficulty::DifficultyCurve;
+use crate::entity::actor::Actor;
+use crate::entity::bullet::{spawn_bullet, SpawnBullet};
+use crate::entity::damege::SpawnDamageNumber;
+use crate::entity::life::{Life, LifeBeingSprite};
+use crate::entity::EntityDepth;
+use crate::level::terrain::Flying;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::se::{SEEvent, SE};
+use crate::states::GameState;
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::AseSpriteSlice;
+use bevy_rapier2d::prelude::*;
+use uuid::Uuid;
+
+/// トゲの床が出入りする周期(フレーム数)
+const SPIKES_CYCLE: u32 = 180;
+
+/// 周期のうち、トゲが出ているフレーム数
+const SPIKES_EXTENDED: u32 = 60;
+
+/// トゲが出ている間にダメージを与える間隔(フレーム数)
+const SPIKES_INTERVAL: u32 = 30;
+
+const SPIKES_DAMAGE: i32 = 4;
+
+/// 連結されていない砲台が弾を撃つ間隔(フレーム数)
+const TURRET_INTERVAL: u32 = 90;
+
+/// 連結された砲台が、感圧板が踏まれてから次に撃てるようになるまでのフレーム数
+const TURRET_LINKED_COOLDOWN: u32 = 20;
+
+/// プレイヤーを狙う砲台がプレイヤーを見つけられる距離
+const TURRET_RANGE: f32 = TILE_SIZE * 10.0;
+
+const TURRET_BULLET_SPEED: f32 = 60.0;
+
+const TURRET_BULLET_DAMAGE: i32 = 4;
+
+const TURRET_LIFE: i32 = 40;
+
+/// 砲台の弾を撃つ向きです
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TurretAim {
+    Up,
+    Down,
+    Left,
+    Right,
+
+    /// 見えている範囲にプレイヤーがいるときだけ、プレイヤーに向かって撃ちます
+    Player,
+}
+
+/// 罠の種類です
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TrapKind {
+    /// 踏むと同じ番号に連結された砲台と扉を作動させる感圧板です
+    PressurePlate(u8),
+
+    /// 一定の周期で出入りし、出ている間は上に立つアクターにダメージを与えるトゲの床です
+    Spikes,
+
+    /// 弾を撃つ壁の砲台です
+    /// 番号で感圧板と連結されている場合は、感圧板が踏まれたときだけ撃ちます
+    /// 連結されていない場合は一定の間隔で撃ち続けます
+    Turret(TurretAim, Option<u8>),
+}
+
+/// 感圧板が踏まれたときに送信されるイベントです
+/// 扉のスイッチと同じく、番号はレベルごとに独立しています
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PressurePlateEvent {
+    pub level: GameLevel,
+    pub link: u8,
+    pub position: Vec2,
+}
+
+#[derive(Component)]
+struct PressurePlate {
+    level: GameLevel,
+    link: u8,
+
+    /// 感圧板の上に乗っているアクターの数
+    pressed: u32,
+}
+
+#[derive(Component)]
+struct Spikes {
+    elapsed: u32,
+}
+
+#[derive(Component)]
+struct Turret {
+    level: GameLevel,
+    aim: TurretAim,
+    link: Option<u8>,
+    cooldown: u32,
+}
+
+/// 指定したタイルに罠を生成します
+pub fn spawn_trap(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    level: GameLevel,
+    x: i32,
+    y: i32,
+    kind: TrapKind,
+) {
+    let position = Vec2::new(
+        TILE_SIZE * x as f32 + TILE_HALF,
+        TILE_SIZE * -y as f32 - TILE_HALF,
+    );
+
+    // 罠の専用のスプライトはまだないので、床や壁のタイルを着色して表示しています
+    match kind {
+        TrapKind::PressurePlate(link) => {
+            commands
+                .spawn((
+                    Name::new("pressure plate"),
+                    PressurePlate {
+                        level,
+                        link,
+                        pressed: 0,
+                    },
+                    StateScoped(GameState::InGame),
+                    Transform::from_translation(
+                        position.extend((FLOOR_LAYER_Z + PAINT_LAYER_Z) * 0.5),
+                    ),
+                    Visibility::default(),
+                    Sensor,
+                    Collider::cuboid(TILE_SIZE * 0.4, TILE_SIZE * 0.4),
+                    ActiveEvents::COLLISION_EVENTS,
+                    CollisionGroups::new(SENSOR_GROUP, WITCH_GROUP),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        Sprite {
+                            color: Color::srgb(0.7, 0.7, 0.9),
+                            ..default()
+                        },
+                        Transform::from_xyz(-TILE_HALF, TILE_HALF, 0.0),
+                        AseSpriteSlice {
+                            aseprite: assets.atlas.clone(),
+                            name: "stone tile".into(),
+                        },
+                    ));
+                });
+        }
+        TrapKind::Spikes => {
+            commands
+                .spawn((
+                    Name::new("spikes"),
+                    // 隣り合ったトゲが同時に出入りするように、周期の位置は共通にしています
+                    Spikes { elapsed: 0 },
+                    StateScoped(GameState::InGame),
+                    Transform::from_translation(
+                        position.extend((FLOOR_LAYER_Z + PAINT_LAYER_Z) * 0.5),
+                    ),
+                    Visibility::default(),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        Sprite::default(),
+                        Transform::from_xyz(-TILE_HALF, TILE_HALF, 0.0),
+                        AseSpriteSlice {
+                            aseprite: assets.atlas.clone(),
+                            name: "stone tile".into(),
+                        },
+                    ));
+                });
+        }
+        TrapKind::Turret(aim, link) => {
+            commands
+                .spawn((
+                    Name::new("turret"),
+                    Turret {
+                        level,
+                        aim,
+                        link,
+                        cooldown: TURRET_INTERVAL,
+                    },
+                    Life {
+                        life: TURRET_LIFE,
+                        max_life: TURRET_LIFE,
+                        amplitude: 0.0,
+                    },
+                    StateScoped(GameState::InGame),
+                    Transform::from_translation(position.extend(0.0)),
+                    EntityDepth,
+                    Visibility::default(),
+                    RigidBody::Fixed,
+                    Collider::cuboid(TILE_HALF, TILE_HALF),
+                    CollisionGroups::new(
+                        WALL_GROUP,
+                        ENTITY_GROUP
+                            | WITCH_GROUP
+                            | WITCH_BULLET_GROUP
+                            | ENEMY_GROUP
+                            | ENEMY_BULLET_GROUP
+                            | RABBIT_GROUP,
+                    ),
+                ))
+                .with_children(|builder| {
+                    // 振動でx座標が上書きされるので、スプライトの位置の補正は孫で行います
+                    builder
+                        .spawn((LifeBeingSprite, Transform::default(), Visibility::default()))
+                        .with_children(|builder| {
+                            builder.spawn((
+                                Sprite {
+                                    color: Color::srgb(0.8, 0.5, 0.6),
+                                    ..default()
+                                },
+                                Transform::from_xyz(-TILE_HALF, 0.0, 0.0),
+                                AseSpriteSlice {
+                                    aseprite: assets.atlas.clone(),
+                                    name: "stone wall".into(),
+                                },
+                            ));
+                        });
+                });
+        }
+    }
+}
+
+/// 感圧板に乗ったアクターを数え、誰も乗っていない感圧板が踏まれたときにイベントを送信します
+fn press_plates(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut plate_query: Query<(&mut PressurePlate, &Transform)>,
+    mut writer: EventWriter<PressurePlateEvent>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    for collision_event in collision_events.read() {
+        match collision_event {
+            CollisionEvent::Started(a, b, ..) => {
+                let plate = if plate_query.contains(*a) { *a } else { *b };
+                let Ok((mut plate, transform)) = plate_query.get_mut(plate) else {
+                    continue;
+                };
+                plate.pressed += 1;
+                if plate.pressed == 1 {
+                    let position = transform.translation.truncate();
+                    writer.send(PressurePlateEvent {
+                        level: plate.level,
+                        link: plate.link,
+                        position,
+                    });
+                    se_writer.send(SEEvent::pos(SE::Switch, position));
+                }
+            }
+            CollisionEvent::Stopped(a, b, ..) => {
+                let plate = if plate_query.contains(*a) { *a } else { *b };
+                let Ok((mut plate, _)) = plate_query.get_mut(plate) else {
+                    continue;
+                };
+                plate.pressed = plate.pressed.saturating_sub(1);
+            }
+        }
+    }
+}
+
+fn update_plate_sprite(
+    plate_query: Query<(&PressurePlate, &Children), Changed<PressurePlate>>,
+    mut sprite_query: Query<&mut Sprite>,
+) {
+    for (plate, children) in plate_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut sprite) = sprite_query.get_mut(*child) {
+                sprite.color = if 0 < plate.pressed {
+                    Color::srgb(0.4, 0.4, 0.6)
+                } else {
+                    Color::srgb(0.7, 0.7, 0.9)
+                };
+            }
+        }
+    }
+}
+
+/// トゲの床を出し入れし、出ている間は上に立つアクターに一定の間隔でダメージを与えます
+/// 飛行しているアクターと、体力が送信元で処理されるリモートプレイヤーは対象外です
+fn cycle_spikes(
+    mut spikes_query: Query<(&mut Spikes, &Transform, &Children)>,
+    mut sprite_query: Query<&mut Sprite>,
+    mut actor_query: Query<
+        (&mut Life, &Transform),
+        (With<Actor>, Without<Flying>, Without<RemotePlayer>),
+    >,
+    mut damage_writer: EventWriter<SpawnDamageNumber>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    for (mut spikes, spikes_transform, children) in spikes_query.iter_mut() {
+        spikes.elapsed = (spikes.elapsed + 1) % SPIKES_CYCLE;
+        let extended_at = SPIKES_CYCLE - SPIKES_EXTENDED;
+        let extended = extended_at <= spikes.elapsed;
+
+        for child in children.iter() {
+            if let Ok(mut sprite) = sprite_query.get_mut(*child) {
+                sprite.color = if extended {
+                    Color::srgb(0.9, 0.4, 0.4)
+                } else {
+                    Color::srgb(0.5, 0.45, 0.45)
+                };
+            }
+        }
+
+        if !extended || (spikes.elapsed - extended_at) % SPIKES_INTERVAL != 0 {
+            continue;
+        }
+
+        let center = spikes_transform.translation.truncate();
+        for (mut life, transform) in actor_query.iter_mut() {
+            let position = transform.translation.truncate();
+            let delta = (position - center).abs();
+            if TILE_HALF <= delta.x || TILE_HALF <= delta.y {
+                continue;
+            }
+            life.life = (life.life - SPIKES_DAMAGE).max(0);
+            life.amplitude = 4.0;
+            damage_writer.send(SpawnDamageNumber {
+                damage: SPIKES_DAMAGE,
+                position,
+            });
+            se_writer.send(SEEvent::pos(SE::Damage, position));
+        }
+    }
+}
+
+/// 砲台から弾を撃ちます
+/// 読み込まれているほかのレベルの砲台は、プレイヤーがそのレベルに入るまで撃ちません
+fn fire_turrets(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    current: Res<CurrentLevel>,
+    curve: Res<DifficultyCurve>,
+    mut turret_query: Query<(Entity, &mut Turret, &Transform)>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    rapier_context: Query<&RapierContext, With<DefaultRapierContext>>,
+    mut plate_reader: EventReader<PressurePlateEvent>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    let context: &RapierContext = rapier_context.single();
+    let plates: Vec<PressurePlateEvent> = plate_reader.read().copied().collect();
+
+    for (entity, mut turret, transform) in turret_query.iter_mut() {
+        if Some(turret.level) != current.level {
+            continue;
+        }
+
+        turret.cooldown = turret.cooldown.saturating_sub(1);
+        if 0 < turret.cooldown {
+            continue;
+        }
+
+        let triggered = match turret.link {
+            None => true,
+            Some(link) => plates
+                .iter()
+                .any(|p| p.level == turret.level && p.link == link),
+        };
+        if !triggered {
+            continue;
+        }
+
+        let origin = transform.translation.truncate();
+        let direction = match turret.aim {
+            TurretAim::Up => Some(Vec2::Y),
+            TurretAim::Down => Some(Vec2::NEG_Y),
+            TurretAim::Left => Some(Vec2::NEG_X),
+            TurretAim::Right => Some(Vec2::X),
+            TurretAim::Player => player_query.iter().find_map(|(player, player_transform)| {
+                let delta = player_transform.translation.truncate() - origin;
+                if TURRET_RANGE < delta.length() {
+                    return None;
+                }
+                let direction = delta.normalize_or_zero();
+                // 壁に遮られていない場合のみ、プレイヤーが見えているとみなします
+                let hit = context.cast_ray(
+                    origin,
+                    direction,
+                    TURRET_RANGE,
+                    true,
+                    QueryFilter {
+                        groups: Some(CollisionGroups::new(
+                            ENEMY_BULLET_GROUP,
+                            WITCH_GROUP | WALL_GROUP,
+                        )),
+                        exclude_collider: Some(entity),
+                        ..default()
+                    },
+                );
+                match hit {
+                    Some((hit, _)) if hit == player => Some(direction),
+                    _ => None,
+                }
+            }),
+        };
+        let Some(direction) = direction else {
+            continue;
+        };
+
+        turret.cooldown = match turret.link {
+            None => TURRET_INTERVAL,
+            Some(_) => TURRET_LINKED_COOLDOWN,
+        };
+
+        let spawn = SpawnBullet {
+            sender: None,
+            uuid: Uuid::new_v4(),
+            // 砲台自身の衝突形状の外側から撃ちます
+            position: origin + direction * (TILE_SIZE * 0.75 + 1.0),
+            velocity: direction * TURRET_BULLET_SPEED,
+            bullet_lifetime: 240,
+            damage: curve.at(turret.level).scale_damage(TURRET_BULLET_DAMAGE),
+            impulse: 0.0,
+            slice: "bullet_purple".to_string(),
+            collier_radius: 5.0,
+            light_intensity: 0.0,
+            light_radius: 0.0,
+            light_color_hlsa: [0.0, 0.0, 0.0, 1.0],
+            homing: 0.0,
+            group: ENEMY_BULLET_GROUP,
+            filter: WITCH_GROUP | ENTITY_GROUP | WALL_GROUP | RABBIT_GROUP,
+        };
+        spawn_bullet(&mut commands, assets.atlas.clone(), &mut se_writer, &spawn);
+    }
+}
+
+fn break_turret(
+    mut commands: Commands,
+    query: Query<(Entity, &Life, &Transform), With<Turret>>,
+    mut writer: EventWriter<SEEvent>,
+) {
+    for (entity, life, transform) in query.iter() {
+        if life.life <= 0 {
+            commands.entity(entity).despawn_recursive();
+            writer.send(SEEvent::pos(SE::Break, transform.translation.truncate()));
+        }
+    }
+}
+
+pub struct TrapPlugin;
+
+impl Plugin for TrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PressurePlateEvent>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                press_plates,
+                update_plate_sprite,
+                cycle_spikes,
+                fire_turrets,
+                break_turret,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
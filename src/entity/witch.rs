@@ -1,15 +1,12 @@
 use super::actor::{ActorGroup, ActorState};
 use super::EntityChildrenAutoDepth;
 use crate::asset::GameAssets;
-use crate::config::GameConfig;
 use crate::constant::*;
 use crate::controller::player::Equipment;
-use crate::controller::training_dummy::TraningDummyController;
 use crate::entity::actor::{Actor, ActorFireState};
 use crate::entity::life::{Life, LifeBeingSprite};
 use crate::hud::life_bar::{spawn_life_bar, LifeBarResource};
 use crate::inventory::Inventory;
-use crate::player_state::PlayerState;
 use crate::states::GameState;
 use crate::wand::Wand;
 use bevy::audio::Volume;
@@ -178,39 +175,6 @@ pub fn spawn_witch<T: Component>(
     return entity.id();
 }
 
-#[allow(dead_code)]
-pub fn spawn_enemy_witch(
-    commands: &mut Commands,
-    assets: &Res<GameAssets>,
-    life_bar_res: &Res<LifeBarResource>,
-    position: Vec2,
-) {
-    let player = PlayerState::from_config(&GameConfig::default());
-
-    spawn_witch(
-        commands,
-        &assets,
-        position,
-        0.0,
-        Uuid::new_v4(),
-        None,
-        200,
-        200,
-        &life_bar_res,
-        false,
-        3.0,
-        10,
-        player.wands,
-        player.inventory,
-        player.equipments,
-        TraningDummyController {
-            home: position,
-            fire: false,
-        },
-        ActorGroup::Enemy,
-    );
-}
-
 fn update_witch_animation(
     witch_query: Query<(&Actor, &ActorState), With<Witch>>,
     mut witch_animation_query: Query<
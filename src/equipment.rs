@@ -1,8 +1,21 @@
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
 
 use crate::language::Dict;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Reflect, strum::EnumIter)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Reflect,
+    strum::EnumIter,
+    Serialize,
+    Deserialize,
+)]
 pub enum EquipmentType {
     Lantern,
     SpikeBoots,
@@ -1,23 +1,29 @@
 use crate::asset::GameAssets;
 use crate::audio::GameAudioPlugin;
 use crate::camera::*;
+use crate::codex::CodexPlugin;
 use crate::config::GameConfigPlugin;
 use crate::constant::*;
 use crate::controller::despawn_with_gold::DespawnWithGoldPlugin;
 use crate::controller::player::PlayerPlugin;
 use crate::controller::remote::RemotePlayerPlugin;
+use crate::controller::remote_interpolation::RemoteInterpolationPlugin;
+use crate::controller::remote_monster::RemoteMonsterPlugin;
 use crate::controller::servant::ServantPlugin;
 use crate::controller::training_dummy::TrainingDummyPlugin;
 use crate::debug::DebugCommandPlugin;
+use crate::difficulty::DifficultyPlugin;
 use crate::enemy::eyeball::EyeballControlPlugin;
 use crate::enemy::huge_slime::HugeSlimePlugin;
 use crate::enemy::slime::SlimeControlPlugin;
+use crate::enemy::witch::WitchControlPlugin;
 use crate::entity::actor::ActorPlugin;
 use crate::entity::book_shelf::BookshelfPlugin;
 use crate::entity::bullet::BulletPlugin;
 use crate::entity::bullet_particle::BulletParticlePlugin;
 use crate::entity::chest::ChestPlugin;
 use crate::entity::damege::DamagePlugin;
+use crate::entity::door::DoorPlugin;
 use crate::entity::dropped_item::SpellEntityPlugin;
 use crate::entity::gold::GoldPlugin;
 use crate::entity::impact::ImpactPlugin;
@@ -27,24 +33,31 @@ use crate::entity::rabbit::RabbitPlugin;
 use crate::entity::servant_seed::ServantSeedPlugin;
 use crate::entity::shop::ShopPlugin;
 use crate::entity::stone_lantern::StoneLanternPlugin;
+use crate::entity::trap::TrapPlugin;
 use crate::entity::witch::WitchPlugin;
 use crate::entity::EntityPlugin;
 use crate::footsteps::FootStepsPlugin;
 use crate::hud::life_bar::LifeBarPlugin;
+use crate::hud::minimap::MinimapPlugin;
 use crate::hud::overlay::*;
 use crate::hud::pointer::PointerPlugin;
+use crate::hud::toast::ToastPlugin;
 use crate::hud::*;
 use crate::input::GameInputPlugin;
 use crate::level::*;
+use crate::page::achievements::AchievementsPagePlugin;
+use crate::page::codex::CodexPagePlugin;
 use crate::page::ending::EndingPlugin;
 use crate::page::main_menu::MainMenuPlugin;
 use crate::page::name_input::NameInputPagePlugin;
 use crate::page::setup::SetupPlugin;
 use crate::page::warp::WarpPagePlugin;
 use crate::physics::GamePhysicsPlugin;
+use crate::save::SavePlugin;
 use crate::se::SECommandPlugin;
 use crate::speech_bubble::SpeechBubblePlugin;
 use crate::states::*;
+use crate::stats::StatsPlugin;
 use crate::ui::bar::StatusBarPlugin;
 use crate::ui::boss_hitpoint_bar::BossHitpointBarPlugin;
 use crate::ui::command_button::CommandButtonPlugin;
@@ -60,7 +73,9 @@ use crate::ui::on_press::OnPressPlugin;
 use crate::ui::pause_menu::GameMenuPlugin;
 use crate::ui::player_list::PlayerListPlugin;
 use crate::ui::popup::PopUpPlugin;
+use crate::ui::servant_list::ServantListPlugin;
 use crate::ui::spell_in_wand::SpellInWandPlugin;
+use crate::ui::stash::StashPanelPlugin;
 use crate::ui::wand_editor::WandEditorPlugin;
 use crate::ui::wand_list::WandListPlugin;
 use crate::ui::wand_sprite::WandSpritePlugin;
@@ -75,6 +90,10 @@ use bevy_rapier2d::prelude::*;
 use bevy_simple_text_input::TextInputPlugin;
 use bevy_simple_websocket::WebSocketPlugin;
 use gameover::GameoverPlugin;
+use meta::LevelMetadataPlugin;
+use modify::TileModifyPlugin;
+use streaming::LevelStreamingPlugin;
+use terrain::TerrainPlugin;
 use wall::WallPlugin;
 
 #[cfg(all(not(debug_assertions), not(target_arch = "wasm32")))]
@@ -153,6 +172,7 @@ pub fn run_game() {
         // 以下はこのゲーム本体で定義されたプラグイン
         //
         .add_systems(Update, toggle_fullscreen)
+        .add_plugins(AchievementsPagePlugin)
         .add_plugins(ActorPlugin)
         .add_plugins(BookshelfPlugin)
         .add_plugins(BossHitpointBarPlugin)
@@ -161,10 +181,13 @@ pub fn run_game() {
         .add_plugins(BulletParticlePlugin)
         .add_plugins(CameraPlugin)
         .add_plugins(ChestPlugin)
+        .add_plugins(CodexPlugin)
+        .add_plugins(CodexPagePlugin)
         .add_plugins(CommandButtonPlugin)
         .add_plugins(DamagePlugin)
         .add_plugins(DebugCommandPlugin)
         .add_plugins(DespawnWithGoldPlugin)
+        .add_plugins(DifficultyPlugin)
         .add_plugins(EndingPlugin)
         .add_plugins(EyeballControlPlugin)
         .add_plugins(EntityPlugin)
@@ -174,6 +197,7 @@ pub fn run_game() {
         .add_plugins(GameoverPlugin)
         .add_plugins(SECommandPlugin)
         .add_plugins(GameConfigPlugin)
+        .add_plugins(SavePlugin)
         .add_plugins(GameMenuPlugin)
         .add_plugins(GameInputPlugin)
         .add_plugins(GamePhysicsPlugin)
@@ -187,6 +211,7 @@ pub fn run_game() {
         .add_plugins(ItemPanelPlugin)
         .add_plugins(LabelPlugin)
         .add_plugins(LifeBarPlugin)
+        .add_plugins(MinimapPlugin)
         .add_plugins(MagicCirclePlugin)
         .add_plugins(MainMenuPlugin)
         .add_plugins(MenuButtonPlugin)
@@ -198,18 +223,30 @@ pub fn run_game() {
         .add_plugins(PlayerPlugin)
         .add_plugins(PointerPlugin)
         .add_plugins(RabbitPlugin)
+        .add_plugins(RemoteInterpolationPlugin)
+        .add_plugins(RemoteMonsterPlugin)
         .add_plugins(RemotePlayerPlugin)
         .add_plugins(SetupPlugin)
         .add_plugins(ServantPlugin)
+        .add_plugins(ServantListPlugin)
         .add_plugins(ShopPlugin)
+        .add_plugins(DoorPlugin)
+        .add_plugins(TrapPlugin)
+        .add_plugins(LevelMetadataPlugin)
+        .add_plugins(LevelStreamingPlugin)
         .add_plugins(SlimeControlPlugin)
         .add_plugins(ServantSeedPlugin)
         .add_plugins(SpeechBubblePlugin)
         .add_plugins(PopUpPlugin)
         .add_plugins(SpellEntityPlugin)
         .add_plugins(SpellInWandPlugin)
+        .add_plugins(StashPanelPlugin)
+        .add_plugins(StatsPlugin)
         .add_plugins(StatusBarPlugin)
         .add_plugins(StoneLanternPlugin)
+        .add_plugins(TerrainPlugin)
+        .add_plugins(TileModifyPlugin)
+        .add_plugins(ToastPlugin)
         .add_plugins(TrainingDummyPlugin)
         .add_plugins(WallPlugin)
         .add_plugins(WandEditorPlugin)
@@ -218,6 +255,7 @@ pub fn run_game() {
         .add_plugins(WarpPagePlugin)
         .add_plugins(WebSocketPlugin)
         .add_plugins(WitchPlugin)
+        .add_plugins(WitchControlPlugin)
         .add_plugins(WorldPlugin)
         //
         // メインメニューやゲームプレイ画面などのシーンを定義するstate
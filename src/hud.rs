@@ -1,7 +1,9 @@
 pub mod gameover;
 pub mod life_bar;
+pub mod minimap;
 pub mod overlay;
 pub mod pointer;
+pub mod toast;
 
 use crate::asset::GameAssets;
 use crate::config::GameConfig;
@@ -9,13 +11,18 @@ use crate::constant::HUD_Z_INDEX;
 use crate::controller::player::Player;
 use crate::entity::actor::Actor;
 use crate::entity::life::Life;
-use crate::level::{level_to_name, CurrentLevel, GameLevel};
+use crate::language::Dict;
+use crate::level::meta::LevelMetadata;
+use crate::level::CurrentLevel;
+use crate::save::SaveSlots;
 use crate::speech_bubble::spawn_speech_bubble;
 use crate::states::GameState;
 use crate::ui::bar::{spawn_status_bar, StatusBar};
 use crate::ui::boss_hitpoint_bar::spawn_boss_hitpoint_bar;
 use crate::ui::equipment_list::spawn_equipment_list;
 use crate::ui::floating::{spawn_inventory_floating, Floating};
+use crate::ui::servant_list::spawn_servant_list;
+use crate::ui::stash::spawn_stash_panel;
 use crate::ui::wand_editor::spawn_wand_editor;
 use crate::ui::wand_list::spawn_wand_list;
 use bevy::prelude::*;
@@ -30,12 +37,11 @@ pub struct PlayerLifeBar;
 #[derive(Component)]
 pub struct PlayerGold;
 
-fn setup_hud(
-    mut commands: Commands,
-    assets: Res<GameAssets>,
-    next: Res<CurrentLevel>,
-    config: Res<GameConfig>,
-) {
+/// 画面右下のレベル名と深度の表示です
+#[derive(Component)]
+struct LevelName;
+
+fn setup_hud(mut commands: Commands, assets: Res<GameAssets>) {
     commands
         .spawn((
             Name::new("hud_root"),
@@ -96,14 +102,9 @@ fn setup_hud(
 
                     // 右下
 
-                    let level: GameLevel = match next.next_level {
-                        GameLevel::Level(i) => GameLevel::Level(i),
-                        GameLevel::MultiPlayArena => GameLevel::MultiPlayArena,
-                    };
-                    let name = level_to_name(level).get(config.language).to_string();
-
                     parent.spawn((
-                        Text(name),
+                        LevelName,
+                        Text::new(""),
                         TextColor(Color::srgba(1.0, 1.0, 1.0, 0.3)),
                         TextFont {
                             font: assets.dotgothic.clone(),
@@ -115,6 +116,8 @@ fn setup_hud(
 
             spawn_wand_editor(&mut parent, &assets);
 
+            spawn_stash_panel(&mut parent, &assets);
+
             spawn_inventory_floating(&mut parent, &assets);
 
             spawn_boss_hitpoint_bar(&mut parent, &assets);
@@ -231,6 +234,8 @@ fn spawn_status_bars(parent: &mut ChildBuilder, assets: &Res<GameAssets>) {
                         HUD,
                     ));
                 });
+
+            spawn_servant_list(&mut parent);
         });
 }
 
@@ -250,6 +255,44 @@ fn update_hud(
     }
 }
 
+/// レベル名と深度の表示を更新します
+/// 魔法陣でフェードせずに別のレベルへ移動したときにも更新されます
+fn update_level_name(
+    next: Res<CurrentLevel>,
+    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
+    metadata: Res<LevelMetadata>,
+    mut query: Query<(&mut Text, Ref<LevelName>)>,
+) {
+    for (mut text, label) in query.iter_mut() {
+        if !next.is_changed() && !label.is_added() {
+            continue;
+        }
+        let level_name = metadata.get(next.next_level).name.get(config.language);
+        let name = match next.next_level.depth() {
+            Some(depth) => Dict {
+                ja: format!(
+                    "{} 深度{} (最深{})",
+                    level_name,
+                    depth,
+                    slots.current().best_depth.max(depth)
+                ),
+                en: format!(
+                    "{} Depth {} (Best {})",
+                    level_name,
+                    depth,
+                    slots.current().best_depth.max(depth)
+                ),
+            }
+            .get(config.language),
+            None => level_name,
+        };
+        if text.0 != name {
+            text.0 = name;
+        }
+    }
+}
+
 pub struct HudPlugin;
 
 impl Plugin for HudPlugin {
@@ -257,7 +300,12 @@ impl Plugin for HudPlugin {
         app.add_systems(OnEnter(GameState::InGame), setup_hud);
         app.add_systems(
             Update,
-            (update_hud, drop_area_interaction, drop_area_visibility)
+            (
+                update_hud,
+                update_level_name,
+                drop_area_interaction,
+                drop_area_visibility,
+            )
                 .run_if(in_state(GameState::InGame)),
         );
     }
@@ -0,0 +1,355 @@
+use crate::constant::{HUD_Z_INDEX, TILE_SIZE};
+use crate::controller::player::Player;
+use crate::enemy::huge_slime::Boss;
+use crate::entity::actor::Actor;
+use crate::entity::chest::{Chest, ChestType};
+use crate::entity::magic_circle::MagicCircle;
+use crate::entity::rabbit::Rabbit;
+use crate::level::map::LevelChunk;
+use crate::level::tile::Tile;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::states::GameState;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use std::collections::{HashMap, HashSet};
+
+/// 画面右上のミニマップの大きさ(ピクセル)
+const MINIMAP_SIZE: f32 = 192.0;
+
+/// 全画面の地図を表示したときの、画面の短辺に対する地図の大きさの割合
+const FULL_MAP_RATIO: f32 = 0.8;
+
+/// ランタンを装備していなくても探索済みになる、プレイヤーの周囲の範囲(タイル数)
+const MIN_REVEAL_RADIUS: f32 = 4.0;
+
+/// 光の強さあたりの、探索済みになる範囲の広がり(タイル数)
+/// ランタンを装備したときの光の半径(160ピクセル)とおおよそ一致するようにしています
+const REVEAL_RADIUS_PER_INTENSITY: f32 = 2.0;
+
+/// 地図の画像を描き直す間隔(フレーム数)
+const REDRAW_INTERVAL: u32 = 10;
+
+/// 探索済みのタイルの位置をレベルごとに記録します
+/// 同じ周回のあいだは、同じレベルに戻ってきたときにも探索済みの状態が引き継がれます
+/// レベルは読み込まれるたびに異なる位置に配置されるので、チャンクの左上を原点とする位置で記録します
+/// タイトル画面に戻るとリセットされます
+#[derive(Resource, Default)]
+pub struct Exploration {
+    levels: HashMap<GameLevel, HashSet<(i32, i32)>>,
+}
+
+/// 地図を全画面で表示しているかどうか
+#[derive(Resource, Default)]
+struct FullMap(bool);
+
+#[derive(Resource)]
+struct MinimapImage(Handle<Image>);
+
+#[derive(Component)]
+struct Minimap;
+
+fn setup_minimap_image(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let handle = images.add(create_map_image(1, 1));
+    commands.insert_resource(MinimapImage(handle));
+}
+
+fn create_map_image(width: u32, height: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn reset_exploration(mut exploration: ResMut<Exploration>) {
+    exploration.levels.clear();
+}
+
+fn setup_minimap(mut commands: Commands, image: Res<MinimapImage>, mut full: ResMut<FullMap>) {
+    full.0 = false;
+    commands.spawn((
+        Name::new("minimap"),
+        Minimap,
+        StateScoped(GameState::InGame),
+        GlobalZIndex(HUD_Z_INDEX),
+        ImageNode::new(image.0.clone()),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            width: Val::Px(MINIMAP_SIZE),
+            height: Val::Px(MINIMAP_SIZE),
+            ..default()
+        },
+    ));
+}
+
+fn toggle_full_map(keys: Res<ButtonInput<KeyCode>>, mut full: ResMut<FullMap>) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        full.0 = !full.0;
+    }
+}
+
+/// プレイヤーの光が届く範囲のタイルを探索済みにします
+fn explore(
+    current: Res<CurrentLevel>,
+    player_query: Query<(&Actor, &Transform), With<Player>>,
+    mut exploration: ResMut<Exploration>,
+) {
+    let (Some(level), Some(chunk)) = (current.level, current.chunk.as_ref()) else {
+        return;
+    };
+    let Ok((actor, transform)) = player_query.get_single() else {
+        return;
+    };
+
+    let radius = MIN_REVEAL_RADIUS + actor.intensity * REVEAL_RADIUS_PER_INTENSITY;
+    let px = (transform.translation.x / TILE_SIZE).floor() as i32;
+    let py = (-transform.translation.y / TILE_SIZE).floor() as i32;
+
+    reveal(
+        exploration.levels.entry(level).or_default(),
+        chunk,
+        px,
+        py,
+        radius,
+    );
+}
+
+/// 指定した位置から半径 radius 以内にあるチャンクのタイルを探索済みにします
+fn reveal(explored: &mut HashSet<(i32, i32)>, chunk: &LevelChunk, px: i32, py: i32, radius: f32) {
+    let r = radius.ceil() as i32;
+    for y in py - r..=py + r {
+        for x in px - r..=px + r {
+            let d = Vec2::new((x - px) as f32, (y - py) as f32).length();
+            if d <= radius && chunk.contains(x, y) {
+                explored.insert(chunk.to_local(x, y));
+            }
+        }
+    }
+}
+
+fn is_explored(explored: &HashSet<(i32, i32)>, chunk: &LevelChunk, x: i32, y: i32) -> bool {
+    explored.contains(&chunk.to_local(x, y))
+}
+
+fn tile_color(tile: Tile) -> [u8; 4] {
+    match tile {
+        Tile::Wall | Tile::CrackedWall => [120, 110, 100, 255],
+        Tile::StoneTile => [40, 44, 60, 255],
+        Tile::Water => [50, 70, 160, 255],
+        Tile::Lava => [180, 60, 20, 255],
+        Tile::Pit => [0, 0, 0, 255],
+        Tile::Ice => [120, 180, 200, 255],
+        Tile::Blank => [0, 0, 0, 0],
+    }
+}
+
+/// 地図上にタイルひとつより大きな印を描きます
+fn draw_marker(image: &mut Image, chunk: &LevelChunk, position: Vec2, color: [u8; 4]) {
+    let width = (chunk.max_x - chunk.min_x) as i32;
+    let height = (chunk.max_y - chunk.min_y) as i32;
+    let x = (position.x / TILE_SIZE).floor() as i32 - chunk.min_x;
+    let y = (-position.y / TILE_SIZE).floor() as i32 - chunk.min_y;
+    for (dx, dy) in [(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let (mx, my) = (x + dx, y + dy);
+        if 0 <= mx && mx < width && 0 <= my && my < height {
+            let i = 4 * (my * width + mx) as usize;
+            image.data[i..i + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+/// 探索済みのタイルと、探索済みの場所にある魔法陣、商店、宝箱、ボスを地図の画像に描きます
+fn draw_minimap(
+    mut frame: Local<u32>,
+    current: Res<CurrentLevel>,
+    exploration: Res<Exploration>,
+    minimap_image: Res<MinimapImage>,
+    mut images: ResMut<Assets<Image>>,
+    player_query: Query<&Transform, With<Player>>,
+    circle_query: Query<&Transform, With<MagicCircle>>,
+    rabbit_query: Query<&Transform, With<Rabbit>>,
+    chest_query: Query<(&Chest, &Transform)>,
+    boss_query: Query<&Transform, With<Boss>>,
+) {
+    *frame += 1;
+    if !current.is_changed() && *frame % REDRAW_INTERVAL != 0 {
+        return;
+    }
+
+    let (Some(level), Some(chunk)) = (current.level, current.chunk.as_ref()) else {
+        return;
+    };
+    let Some(image) = images.get_mut(&minimap_image.0) else {
+        return;
+    };
+
+    let width = (chunk.max_x - chunk.min_x) as u32;
+    let height = (chunk.max_y - chunk.min_y) as u32;
+    if image.width() != width || image.height() != height {
+        *image = create_map_image(width, height);
+    }
+
+    let empty = HashSet::new();
+    let explored = exploration.levels.get(&level).unwrap_or(&empty);
+
+    for y in chunk.min_y..chunk.max_y {
+        for x in chunk.min_x..chunk.max_x {
+            let i = 4 * ((y - chunk.min_y) as u32 * width + (x - chunk.min_x) as u32) as usize;
+            let color = if is_explored(explored, chunk, x, y) {
+                tile_color(chunk.get_tile(x, y))
+            } else {
+                [0, 0, 0, 0]
+            };
+            image.data[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    let marker_explored = |p: Vec2| {
+        let x = (p.x / TILE_SIZE).floor() as i32;
+        let y = (-p.y / TILE_SIZE).floor() as i32;
+        is_explored(explored, chunk, x, y)
+    };
+
+    let markers = circle_query
+        .iter()
+        .map(|t| (t.translation.truncate(), [80, 200, 255, 255]))
+        .chain(
+            rabbit_query
+                .iter()
+                .map(|t| (t.translation.truncate(), [255, 230, 60, 255])),
+        )
+        .chain(
+            chest_query
+                .iter()
+                .filter(|(c, _)| c.chest_type == ChestType::Chest)
+                .map(|(_, t)| (t.translation.truncate(), [255, 150, 50, 255])),
+        )
+        .chain(
+            boss_query
+                .iter()
+                .map(|t| (t.translation.truncate(), [255, 40, 40, 255])),
+        );
+    for (position, color) in markers {
+        if chunk.contains(
+            (position.x / TILE_SIZE).floor() as i32,
+            (-position.y / TILE_SIZE).floor() as i32,
+        ) && marker_explored(position)
+        {
+            draw_marker(image, chunk, position, color);
+        }
+    }
+
+    if let Ok(player) = player_query.get_single() {
+        draw_marker(
+            image,
+            chunk,
+            player.translation.truncate(),
+            [255, 255, 255, 255],
+        );
+    }
+}
+
+/// ミニマップと全画面の地図の大きさと位置を、チャンクの縦横比に合わせて調整します
+fn update_minimap_layout(
+    current: Res<CurrentLevel>,
+    full: Res<FullMap>,
+    window_query: Query<&Window>,
+    mut query: Query<&mut Node, With<Minimap>>,
+) {
+    let Some(chunk) = current.chunk.as_ref() else {
+        return;
+    };
+    let Ok(mut node) = query.get_single_mut() else {
+        return;
+    };
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let width = (chunk.max_x - chunk.min_x) as f32;
+    let height = (chunk.max_y - chunk.min_y) as f32;
+    let size = if full.0 {
+        window.width().min(window.height()) * FULL_MAP_RATIO
+    } else {
+        MINIMAP_SIZE
+    };
+    let scale = size / width.max(height);
+    node.width = Val::Px(width * scale);
+    node.height = Val::Px(height * scale);
+
+    if full.0 {
+        node.top = Val::Px((window.height() - height * scale) * 0.5);
+        node.right = Val::Px((window.width() - width * scale) * 0.5);
+    } else {
+        node.top = Val::Px(8.0);
+        node.right = Val::Px(8.0);
+    }
+}
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Exploration>();
+        app.init_resource::<FullMap>();
+        app.add_systems(Startup, setup_minimap_image);
+        app.add_systems(OnEnter(GameState::MainMenu), reset_exploration);
+        app.add_systems(OnEnter(GameState::InGame), setup_minimap);
+        app.add_systems(
+            Update,
+            (
+                toggle_full_map,
+                explore,
+                draw_minimap,
+                update_minimap_layout,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::streaming::LevelWorld;
+
+    #[test]
+    fn keep_exploration_when_level_moves() {
+        let mut world = LevelWorld::default();
+        let level = GameLevel::Level(1);
+
+        let mut chunk = LevelChunk::new(0, 20, 0, 20);
+        let (ox, oy) = world.allocate(level);
+        chunk.translate(ox, oy);
+
+        let mut explored = HashSet::new();
+        reveal(&mut explored, &chunk, ox + 5, oy + 5, MIN_REVEAL_RADIUS);
+        assert!(is_explored(&explored, &chunk, ox + 5, oy + 5));
+        assert!(!is_explored(&explored, &chunk, ox + 15, oy + 15));
+
+        // 別のレベルが先に配置されると、同じレベルでも異なる位置に配置されます
+        world.clear();
+        world.allocate(GameLevel::Level(2));
+        let (nx, ny) = world.allocate(level);
+        assert_ne!((nx, ny), (ox, oy));
+        let mut moved = LevelChunk::new(0, 20, 0, 20);
+        moved.translate(nx, ny);
+
+        assert!(is_explored(&explored, &moved, nx + 5, ny + 5));
+        assert!(!is_explored(&explored, &moved, nx + 15, ny + 15));
+    }
+}
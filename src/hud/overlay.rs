@@ -100,7 +100,9 @@ impl Plugin for OverlayPlugin {
                         .or(in_state(GameState::MainMenu))
                         .or(in_state(GameState::NameInput))
                         .or(in_state(GameState::Warp))
-                        .or(in_state(GameState::Ending)),
+                        .or(in_state(GameState::Ending))
+                        .or(in_state(GameState::Codex))
+                        .or(in_state(GameState::Achievements)),
                 ),
             ),
         );
@@ -83,7 +83,9 @@ impl Plugin for PointerPlugin {
             update_pointer_image_by_angle.run_if(
                 in_state(GameState::InGame).or(in_state(GameState::MainMenu)
                     .or(in_state(GameState::NameInput))
-                    .or(in_state(GameState::Ending))),
+                    .or(in_state(GameState::Ending))
+                    .or(in_state(GameState::Codex))
+                    .or(in_state(GameState::Achievements))),
             ),
         );
     }
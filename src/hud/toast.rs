@@ -0,0 +1,137 @@
+use crate::asset::GameAssets;
+use crate::config::GameConfig;
+use crate::constant::HUD_Z_INDEX;
+use crate::language::Dict;
+use crate::se::{SEEvent, SE};
+use crate::states::GameState;
+use crate::stats::{AchievementEvent, Achievements};
+use bevy::prelude::*;
+
+/// 実績の通知を表示しておく時間(秒)です
+const TOAST_DURATION: f32 = 4.0;
+
+/// 実績の通知がフェードアウトする時間(秒)です
+const TOAST_FADE: f32 = 1.0;
+
+#[derive(Component)]
+struct ToastList;
+
+/// 実績が解除されたときに画面右上に表示される通知です
+#[derive(Component)]
+struct Toast {
+    elapsed: f32,
+}
+
+fn setup_toast_list(mut commands: Commands) {
+    commands.spawn((
+        Name::new("toast_list"),
+        ToastList,
+        StateScoped(GameState::InGame),
+        GlobalZIndex(HUD_Z_INDEX),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(8.0),
+            top: Val::Px(8.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+    ));
+}
+
+fn spawn_toast(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    mut reader: EventReader<AchievementEvent>,
+    list_query: Query<Entity, With<ToastList>>,
+    mut writer: EventWriter<SEEvent>,
+    achievements: Res<Achievements>,
+) {
+    let Ok(list) = list_query.get_single() else {
+        return;
+    };
+    for AchievementEvent(index) in reader.read() {
+        let achievement = &achievements.0[*index];
+        commands.entity(list).with_children(|parent| {
+            parent
+                .spawn((
+                    Toast { elapsed: 0.0 },
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::new(
+                            Val::Px(12.0),
+                            Val::Px(12.0),
+                            Val::Px(6.0),
+                            Val::Px(6.0),
+                        ),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(
+                            Dict {
+                                ja: "実績解除",
+                                en: "Achievement Unlocked",
+                            }
+                            .get(config.language),
+                        ),
+                        TextColor(Color::hsla(57.0, 1.0, 0.5, 1.0)),
+                        TextFont {
+                            font: assets.dotgothic.clone(),
+                            font_size: 12.0,
+                            ..default()
+                        },
+                    ));
+                    parent.spawn((
+                        Text::new(achievement.name.get(config.language)),
+                        TextColor(Color::WHITE),
+                        TextFont {
+                            font: assets.dotgothic.clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+        writer.send(SEEvent::new(SE::TurnOn));
+    }
+}
+
+/// 表示してから一定時間が経った通知をフェードアウトさせて取り除きます
+fn update_toast(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_query: Query<(Entity, &mut Toast, &mut BackgroundColor, &Children)>,
+    mut text_query: Query<&mut TextColor>,
+) {
+    for (entity, mut toast, mut background, children) in toast_query.iter_mut() {
+        toast.elapsed += time.delta_secs();
+        if TOAST_DURATION <= toast.elapsed {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = ((TOAST_DURATION - toast.elapsed) / TOAST_FADE).min(1.0);
+        background.0.set_alpha(0.7 * alpha);
+        for child in children.iter() {
+            if let Ok(mut color) = text_query.get_mut(*child) {
+                color.0.set_alpha(alpha);
+            }
+        }
+    }
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), setup_toast_list);
+        app.add_systems(
+            Update,
+            (spawn_toast, update_toast).run_if(in_state(GameState::InGame)),
+        );
+    }
+}
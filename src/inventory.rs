@@ -1,11 +1,12 @@
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     constant::{MAX_ITEMS_IN_INVENTORY, MAX_ITEMS_IN_INVENTORY_COLUMN, MAX_ITEMS_IN_INVENTORY_ROW},
     inventory_item::InventoryItemType,
 };
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Reflect)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Reflect, Serialize, Deserialize)]
 pub struct InventoryItem {
     pub item_type: InventoryItemType,
     pub price: u32,
@@ -14,6 +15,25 @@ pub struct InventoryItem {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Reflect)]
 pub struct Inventory(pub [Option<InventoryItem>; MAX_ITEMS_IN_INVENTORY]);
 
+/// 配列の長さが serde の対応する上限を越えるため、可変長の配列としてシリアライズします
+impl Serialize for Inventory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.as_slice().serialize(serializer)
+    }
+}
+
+/// 足りない分は空きで埋め、インベントリに入りきらない分は切り捨てます
+impl<'de> Deserialize<'de> for Inventory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Option<InventoryItem>>::deserialize(deserializer)?;
+        let mut inventory = Inventory::new();
+        for (index, item) in items.into_iter().take(MAX_ITEMS_IN_INVENTORY).enumerate() {
+            inventory.set(index, item);
+        }
+        Ok(inventory)
+    }
+}
+
 impl Inventory {
     pub fn new() -> Inventory {
         Inventory([None; MAX_ITEMS_IN_INVENTORY])
@@ -5,8 +5,9 @@ use crate::{
     wand::WandType,
 };
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize)]
 pub enum InventoryItemType {
     Wand(WandType),
     Spell(SpellType),
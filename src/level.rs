@@ -1,44 +1,67 @@
 pub mod ceil;
+pub mod check;
+pub mod generator;
+pub mod ldtk;
 pub mod map;
+pub mod meta;
+pub mod modify;
+pub mod streaming;
+pub mod terrain;
 pub mod tile;
 pub mod wall;
 
 use crate::asset::GameAssets;
 use crate::audio::NextBGM;
 use crate::camera::GameCamera;
-use crate::config::GameConfig;
+use crate::codex::locked_items;
 use crate::constant::*;
 use crate::controller::player::Player;
+use crate::difficulty::Difficulty;
+use crate::difficulty::DifficultyCurve;
 use crate::enemy::eyeball::spawn_eyeball;
 use crate::enemy::huge_slime::spawn_huge_slime;
 use crate::enemy::sandbug::spawn_sandbag;
 use crate::enemy::slime::spawn_slime;
+use crate::enemy::witch::{random_witch_wands, spawn_enemy_witch};
 use crate::entity::actor::ActorGroup;
 use crate::entity::book_shelf::spawn_book_shelf;
 use crate::entity::broken_magic_circle::spawn_broken_magic_circle;
 use crate::entity::chest::spawn_chest;
 use crate::entity::chest::ChestType;
 use crate::entity::chest::CHEST_OR_BARREL;
+use crate::entity::door::spawn_door;
+use crate::entity::door::spawn_door_switch;
 use crate::entity::dropped_item::spawn_dropped_item;
 use crate::entity::magic_circle::spawn_magic_circle;
 use crate::entity::magic_circle::MagicCircleDestination;
 use crate::entity::rabbit::spawn_rabbit;
 use crate::entity::shop::spawn_shop_door;
+use crate::entity::stash::{find_stash_tile, spawn_stash};
 use crate::entity::stone_lantern::spawn_stone_lantern;
+use crate::entity::trap::spawn_trap;
 use crate::entity::witch::spawn_witch;
 use crate::entity::GameEntity;
-use crate::equipment::EquipmentType;
 use crate::hud::life_bar::LifeBarResource;
 use crate::inventory::InventoryItem;
 use crate::inventory_item::InventoryItemType;
-use crate::language::Dict;
 use crate::level::ceil::spawn_roof_tiles;
+use crate::level::generator::generate_dungeon;
+use crate::level::generator::generate_vault;
+use crate::level::generator::GENERATED_LEVEL_HEIGHT;
+use crate::level::generator::GENERATED_LEVEL_WIDTH;
+use crate::level::ldtk::ldtk_to_tilemap;
+use crate::level::ldtk::LdtkLoader;
+use crate::level::ldtk::LdtkProject;
 use crate::level::map::image_to_tilemap;
 use crate::level::map::LevelChunk;
+use crate::level::map::SpawnTable;
+use crate::level::meta::BranchLayout;
+use crate::level::meta::LevelLayout;
+use crate::level::meta::LevelMetadata;
 use crate::level::tile::*;
 use crate::player_state::PlayerState;
 use crate::random::random_select_mut;
-use crate::spell::SpellType;
+use crate::save::SaveSlots;
 use crate::states::GameState;
 use bevy::asset::*;
 use bevy::core::FrameCount;
@@ -47,23 +70,50 @@ use bevy_aseprite_ultra::prelude::*;
 use map::image_to_spawn_tiles;
 use rand::seq::IteratorRandom;
 use rand::seq::SliceRandom;
-use strum::IntoEnumIterator;
+use serde::{Deserialize, Serialize};
+use streaming::LevelWorld;
 use uuid::Uuid;
-use wall::spawn_wall_collisions;
-use wall::WallCollider;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum GameLevel {
     Level(i32),
+
+    /// 本来の順路から外れた分岐のレベルです
+    /// 深度と、level.meta.json の branches での番号を持ちます
+    Branch(i32, u8),
+
     MultiPlayArena,
 }
 
+impl GameLevel {
+    /// 難易度や最深記録に使われる深度です
+    /// アリーナには深度がありません
+    pub fn depth(&self) -> Option<i32> {
+        match self {
+            GameLevel::Level(depth) | GameLevel::Branch(depth, _) => Some(*depth),
+            GameLevel::MultiPlayArena => None,
+        }
+    }
+}
+
 #[derive(Resource, Debug, Clone)]
 pub struct CurrentLevel {
     pub level: Option<GameLevel>,
     pub chunk: Option<LevelChunk>,
     pub next_level: GameLevel,
     pub next_state: PlayerState,
+
+    /// この周回でこれまでに訪れたレベルを、訪れた順に並べたものです
+    pub path: Vec<GameLevel>,
+}
+
+impl CurrentLevel {
+    /// 訪れたレベルを記録します
+    pub fn visit(&mut self, level: GameLevel) {
+        if self.path.last() != Some(&level) {
+            self.path.push(level);
+        }
+    }
 }
 
 impl Default for CurrentLevel {
@@ -72,7 +122,8 @@ impl Default for CurrentLevel {
             level: None,
             chunk: None,
             next_level: GameLevel::Level(INITIAL_LEVEL),
-            next_state: PlayerState::from_config(&GameConfig::default()),
+            next_state: PlayerState::initial(""),
+            path: Vec::new(),
         }
     }
 }
@@ -82,28 +133,46 @@ pub fn setup_level(
     mut commands: Commands,
     level_aseprites: Res<Assets<Aseprite>>,
     images: Res<Assets<Image>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
     assets: Res<GameAssets>,
     life_bar_res: Res<LifeBarResource>,
     mut camera: Query<(&mut GameCamera, &mut Transform), With<Camera2d>>,
     mut current: ResMut<CurrentLevel>,
+    curve: Res<DifficultyCurve>,
+    metadata: Res<LevelMetadata>,
+    mut slots: ResMut<SaveSlots>,
+    mut world: ResMut<LevelWorld>,
 ) {
-    let level = match current.next_level {
-        GameLevel::Level(level) => GameLevel::Level(level % LEVELS),
-        GameLevel::MultiPlayArena => GameLevel::MultiPlayArena,
-    };
+    let level = current.next_level;
+
+    // フェードを伴う移動では、それまでに読み込んだチャンクはすべて破棄されます
+    world.clear();
+    let origin = world.allocate(level);
 
     let player = current.next_state.clone();
 
-    let mut chunk = spawn_level(
+    let chunk = spawn_level(
         &mut commands,
         &level_aseprites,
         &images,
+        &ldtk_projects,
         &assets,
         &life_bar_res,
+        &metadata,
         level,
+        origin,
+        &curve.at(level).with_locked(locked_items(slots.current())),
     );
 
-    let entry_point = random_select_mut(&mut chunk.entry_points);
+    // 到達した最も深い深度を記録
+    if let Some(depth) = level.depth() {
+        if slots.current().best_depth < depth {
+            slots.current_mut().best_depth = depth;
+        }
+    }
+
+    // 魔法陣で戻ってきたときにも使うので、出現位置はチャンクから取り除かずに選びます
+    let entry_point = *chunk.entry_points.choose(&mut rand::thread_rng()).unwrap();
 
     let player_x = TILE_SIZE * entry_point.x as f32 + TILE_HALF;
     let player_y = -TILE_SIZE * entry_point.y as f32 - TILE_HALF;
@@ -141,112 +210,123 @@ pub fn setup_level(
             last_idle_vy: 0.0,
             last_idle_life: player.life,
             last_idle_max_life: player.max_life,
+            last_attacker: None,
         },
         ActorGroup::Player,
     );
 
     current.level = Some(level);
     current.chunk = Some(chunk);
+    current.visit(level);
 }
 
 fn select_level_bgm(
     next_level: Res<CurrentLevel>,
     mut next_bgm: ResMut<NextBGM>,
-    assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    metadata: Res<LevelMetadata>,
 ) {
     if next_level.is_changed() {
         info!("select_level_bgm {:?}", next_level.next_level);
-        *next_bgm = NextBGM(Some(match next_level.next_level {
-            GameLevel::Level(0) => assets.dokutsu.clone(),
-            GameLevel::Level(3) => {
-                let mut rng = rand::thread_rng();
-                let mut bgms = vec![
-                    assets.deamon.clone(),
-                    assets.action.clone(),
-                    assets.decisive.clone(),
-                    assets.enjin.clone(),
-                    assets.sacred.clone(),
-                    assets.battle_cinematic.clone(),
-                    assets.battle_fight.clone(),
-                    assets.final_battle.clone(),
-                    assets.human_vs_machine.clone(),
-                ];
-                bgms.shuffle(&mut rng);
-                bgms.pop().unwrap()
-            }
-            _ => {
-                let mut rng = rand::thread_rng();
-                let mut bgms = vec![
-                    assets.arechi.clone(),
-                    assets.touha.clone(),
-                    assets.mori.clone(),
-                    assets.meikyu.clone(),
-                    assets.shiden.clone(),
-                    assets.midnight_forest.clone(),
-                ];
-                bgms.shuffle(&mut rng);
-                bgms.pop().unwrap()
-            }
-        }));
-    }
-}
-
-/// 現状は StateScopedですべてのエンティティが削除されるので以下のコードは不要ですが、
-/// 今後レベルのシームレスなスポーンを実装する場合は、以下のようなコードが必要になるかも
-#[allow(dead_code)]
-fn despawn_level(
-    commands: &mut Commands,
-    collider_query: &Query<Entity, With<WallCollider>>,
-    world_tile: &Query<Entity, With<WorldTile>>,
-) {
-    for entity in world_tile {
-        commands.entity(entity).despawn_recursive();
-    }
-    for entity in collider_query {
-        commands.entity(entity).despawn_recursive();
+        *next_bgm = NextBGM(
+            metadata
+                .get(next_level.next_level)
+                .choose_bgm(&asset_server),
+        );
     }
 }
 
+/// レベルのチャンクを読み込み、ワールド上の配置位置に移動したうえで、エンティティと敵を生成します
+/// タイルのスプライトと壁の衝突形状は、プレイヤーの周囲の区画だけを LevelStreamingPlugin が生成します
 fn spawn_level(
     mut commands: &mut Commands,
     level_aseprites: &Res<Assets<Aseprite>>,
     images: &Res<Assets<Image>>,
+    ldtk_projects: &Res<Assets<LdtkProject>>,
     assets: &Res<GameAssets>,
     life_bar_res: &Res<LifeBarResource>,
+    metadata: &LevelMetadata,
     level: GameLevel,
+    (origin_x, origin_y): (i32, i32),
+    difficulty: &Difficulty,
 ) -> LevelChunk {
-    let level_slice = match level {
-        GameLevel::Level(level) => &format!("level{}", level % LEVELS),
-        GameLevel::MultiPlayArena => "multiplay_arena",
+    let mut chunk = match level {
+        GameLevel::Level(depth) => match metadata.layout(depth) {
+            LevelLayout::Slice(layout) => load_chunk(
+                level_aseprites,
+                images,
+                ldtk_projects,
+                assets,
+                &format!("level{}", layout),
+            ),
+            LevelLayout::Generated(seed) => generate_dungeon(
+                seed,
+                GENERATED_LEVEL_WIDTH,
+                GENERATED_LEVEL_HEIGHT,
+                seed % 3 == 0,
+                metadata.generated.exits.len() as u8,
+            ),
+        },
+        GameLevel::Branch(depth, branch) => {
+            // 分岐のレベルは深度ごとに決まった形になります
+            let seed = depth as u64 * 256 + branch as u64;
+            match &metadata.branches[branch as usize].layout {
+                BranchLayout::Slice(name) => {
+                    load_chunk(level_aseprites, images, ldtk_projects, assets, name)
+                }
+                BranchLayout::Dungeon { shop } => generate_dungeon(
+                    seed,
+                    GENERATED_LEVEL_WIDTH,
+                    GENERATED_LEVEL_HEIGHT,
+                    *shop,
+                    0,
+                ),
+                BranchLayout::Vault => generate_vault(seed),
+            }
+        }
+        GameLevel::MultiPlayArena => load_chunk(
+            level_aseprites,
+            images,
+            ldtk_projects,
+            assets,
+            "multiplay_arena",
+        ),
     };
 
-    let level_aseprite = level_aseprites.get(assets.level.id()).unwrap();
-    let level_image = images.get(level_aseprite.atlas_image.id()).unwrap();
-    let slice = level_aseprite.slices.get(level_slice).unwrap();
-
-    info!(
-        "bounds min_x:{} max_x:{} min_y:{} max_y:{}",
-        slice.rect.min.x, slice.rect.max.x, slice.rect.min.y, slice.rect.max.y
-    );
-
-    let chunk = image_to_tilemap(
-        &level_image,
-        slice.rect.min.x as i32,
-        slice.rect.max.x as i32,
-        slice.rect.min.y as i32,
-        slice.rect.max.y as i32,
-    );
+    chunk.translate(origin_x - chunk.min_x, origin_y - chunk.min_y);
 
     let mut empties = image_to_spawn_tiles(&chunk);
 
-    spawn_world_tilemap(&mut commands, &assets, &chunk);
+    // 保管箱を置くレベルで、レベルのデータに保管箱が配置されていなければ出現位置の近くに置きます
+    if metadata.get(level).stash && !chunk.entities.iter().any(|(e, ..)| *e == GameEntity::Stash) {
+        if let Some((x, y)) = find_stash_tile(&chunk) {
+            chunk.entities.push((GameEntity::Stash, x, y));
+        }
+    }
 
-    spawn_wall_collisions(&mut commands, &chunk);
+    spawn_entities(
+        &mut commands,
+        &assets,
+        &life_bar_res,
+        &chunk,
+        level,
+        difficulty,
+    );
 
-    spawn_entities(&mut commands, &assets, &life_bar_res, &chunk);
+    // 安全地帯では敵は出現しません
+    // レベルのデータで出現する敵が指定されていなければ、レベルの設定に従います
+    let meta = metadata.get(level);
+    let spawn_table = if meta.safe_zone {
+        SpawnTable::Empty
+    } else if chunk.spawn_table == SpawnTable::Default {
+        meta.spawn_table
+    } else {
+        chunk.spawn_table
+    };
+    let (slimes, eyeballs, witches) = spawn_table.counts(level);
 
     if 30 < empties.len() {
-        for _ in 0..10 {
+        for _ in 0..difficulty.scale_spawns(slimes) {
             let (x, y) = random_select_mut(&mut empties);
             spawn_slime(
                 &mut commands,
@@ -263,7 +343,7 @@ fn spawn_level(
             );
         }
 
-        for _ in 0..10 {
+        for _ in 0..difficulty.scale_spawns(eyeballs) {
             let (x, y) = random_select_mut(&mut empties);
             spawn_eyeball(
                 &mut commands,
@@ -274,13 +354,27 @@ fn spawn_level(
                 ),
                 &life_bar_res,
                 ActorGroup::Enemy,
+                None,
+            );
+        }
+
+        for _ in 0..difficulty.scale_spawns(witches) {
+            let (x, y) = random_select_mut(&mut empties);
+            spawn_enemy_witch(
+                &mut commands,
+                &assets,
+                &life_bar_res,
+                Vec2::new(
+                    TILE_SIZE * x as f32 + TILE_HALF,
+                    TILE_SIZE * -y as f32 - TILE_HALF,
+                ),
+                random_witch_wands(difficulty),
             );
         }
 
-        let mut rng = rand::thread_rng();
         for _ in 0..3 {
             let (x, y) = random_select_mut(&mut empties);
-            let spell = SpellType::iter().choose(&mut rng).unwrap();
+            let spell = difficulty.choose_spell();
             spawn_dropped_item(
                 &mut commands,
                 &assets,
@@ -299,64 +393,133 @@ fn spawn_level(
     return chunk;
 }
 
-fn spawn_world_tilemap(commands: &mut Commands, assets: &Res<GameAssets>, chunk: &LevelChunk) {
-    // 床と壁の生成
-    for y in chunk.min_y..chunk.max_y as i32 {
-        for x in chunk.min_x..chunk.max_x as i32 {
-            match chunk.get_tile(x, y) {
-                Tile::StoneTile => {
-                    commands.spawn((
-                        WorldTile,
-                        Name::new("stone_tile"),
-                        StateScoped(GameState::InGame),
-                        Transform::from_translation(Vec3::new(
-                            x as f32 * TILE_SIZE,
-                            y as f32 * -TILE_SIZE,
-                            FLOOR_LAYER_Z,
-                        )),
-                        AseSpriteSlice {
-                            aseprite: assets.atlas.clone(),
-                            name: "stone tile".into(),
-                        },
-                    ));
-                }
-                Tile::Wall => {
-                    let tx = x as f32 * TILE_SIZE;
-                    let ty = y as f32 * -TILE_SIZE;
-                    let tz = ENTITY_LAYER_Z + (-ty * Z_ORDER_SCALE);
-
-                    // 壁
-                    if !chunk.equals(x as i32, y as i32 + 1, Tile::Wall) {
-                        commands.spawn((
-                            WorldTile,
-                            Name::new("wall"),
-                            StateScoped(GameState::InGame),
-                            Transform::from_translation(Vec3::new(tx, ty - TILE_HALF, tz)),
-                            AseSpriteSlice {
-                                aseprite: assets.atlas.clone(),
-                                name: "stone wall".into(),
-                            },
-                        ));
-                    }
-
-                    // // 天井
-                    if false
-                        || chunk.is_empty(x - 1, y - 1)
-                        || chunk.is_empty(x + 0, y - 1)
-                        || chunk.is_empty(x + 1, y - 1)
-                        || chunk.is_empty(x - 1, y + 0)
-                        || chunk.is_empty(x + 0, y + 0)
-                        || chunk.is_empty(x + 1, y + 0)
-                        || chunk.is_empty(x - 1, y + 1)
-                        || chunk.is_empty(x + 0, y + 1)
-                        || chunk.is_empty(x + 1, y + 1)
-                    {
-                        spawn_roof_tiles(commands, assets, &chunk, x, y)
-                    }
-                }
-                _ => {}
+/// 指定した名前のレベルのチャンクを読み込みます
+/// level.ldtk に同じ識別子のレベルがあればそちらを優先し、なければ level.aseprite のスライスから読み込みます
+fn load_chunk(
+    level_aseprites: &Res<Assets<Aseprite>>,
+    images: &Res<Assets<Image>>,
+    ldtk_projects: &Res<Assets<LdtkProject>>,
+    assets: &Res<GameAssets>,
+    name: &str,
+) -> LevelChunk {
+    let ldtk_level = ldtk_projects
+        .get(assets.ldtk.id())
+        .and_then(|project| project.level(name));
+    if let Some(ldtk_level) = ldtk_level {
+        match ldtk_to_tilemap(ldtk_level) {
+            Ok(chunk) => return chunk,
+            Err(err) => error!("failed to load ldtk level: {}", err),
+        }
+    }
+    load_slice(level_aseprites, images, assets, name)
+}
+
+/// level.aseprite の指定したスライスからチャンクを読み込みます
+fn load_slice(
+    level_aseprites: &Res<Assets<Aseprite>>,
+    images: &Res<Assets<Image>>,
+    assets: &Res<GameAssets>,
+    slice_name: &str,
+) -> LevelChunk {
+    let level_aseprite = level_aseprites.get(assets.level.id()).unwrap();
+    let level_image = images.get(level_aseprite.atlas_image.id()).unwrap();
+    let slice = level_aseprite.slices.get(slice_name).unwrap();
+
+    info!(
+        "bounds min_x:{} max_x:{} min_y:{} max_y:{}",
+        slice.rect.min.x, slice.rect.max.x, slice.rect.min.y, slice.rect.max.y
+    );
+
+    let (chunk, issues) = image_to_tilemap(
+        &level_image.data,
+        level_image.width(),
+        slice.rect.min.x as i32,
+        slice.rect.max.x as i32,
+        slice.rect.min.y as i32,
+        slice.rect.max.y as i32,
+    );
+
+    // 問題のあるレベルでもゲームは続行します
+    // 詳しくは cargo run --bin level-check で確認できます
+    for issue in issues.iter() {
+        error!("{}: {}", slice_name, issue);
+    }
+
+    chunk
+}
+
+/// 指定した位置のタイルの床、壁、天井のスプライトを生成します
+fn spawn_world_tile(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    chunk: &LevelChunk,
+    x: i32,
+    y: i32,
+) {
+    match chunk.get_tile(x, y) {
+        Tile::StoneTile | Tile::Water | Tile::Lava | Tile::Pit | Tile::Ice => {
+            let (name, slice) = match chunk.get_tile(x, y) {
+                Tile::Water => ("water", "water tile"),
+                Tile::Lava => ("lava", "lava tile"),
+                Tile::Pit => ("pit", "pit tile"),
+                Tile::Ice => ("ice", "ice tile"),
+                _ => ("stone_tile", "stone tile"),
+            };
+            commands.spawn((
+                WorldTile { x, y },
+                Name::new(name),
+                StateScoped(GameState::InGame),
+                Transform::from_translation(Vec3::new(
+                    x as f32 * TILE_SIZE,
+                    y as f32 * -TILE_SIZE,
+                    FLOOR_LAYER_Z,
+                )),
+                AseSpriteSlice {
+                    aseprite: assets.atlas.clone(),
+                    name: slice.into(),
+                },
+            ));
+        }
+        Tile::Wall | Tile::CrackedWall => {
+            let tx = x as f32 * TILE_SIZE;
+            let ty = y as f32 * -TILE_SIZE;
+            let tz = ENTITY_LAYER_Z + (-ty * Z_ORDER_SCALE);
+
+            // 壁
+            // ひび割れた壁は、弾丸で壊せることがわかるように亀裂の入った壁で表示します
+            if !chunk.is_wall(x, y + 1) {
+                let slice = match chunk.get_tile(x, y) {
+                    Tile::CrackedWall => "cracked wall",
+                    _ => "stone wall",
+                };
+                commands.spawn((
+                    WorldTile { x, y },
+                    Name::new("wall"),
+                    StateScoped(GameState::InGame),
+                    Transform::from_translation(Vec3::new(tx, ty - TILE_HALF, tz)),
+                    AseSpriteSlice {
+                        aseprite: assets.atlas.clone(),
+                        name: slice.into(),
+                    },
+                ));
+            }
+
+            // // 天井
+            if false
+                || chunk.is_floor(x - 1, y - 1)
+                || chunk.is_floor(x + 0, y - 1)
+                || chunk.is_floor(x + 1, y - 1)
+                || chunk.is_floor(x - 1, y + 0)
+                || chunk.is_floor(x + 0, y + 0)
+                || chunk.is_floor(x + 1, y + 0)
+                || chunk.is_floor(x - 1, y + 1)
+                || chunk.is_floor(x + 0, y + 1)
+                || chunk.is_floor(x + 1, y + 1)
+            {
+                spawn_roof_tiles(commands, assets, &chunk, x, y)
             }
         }
+        _ => {}
     }
 }
 
@@ -365,6 +528,8 @@ fn spawn_entities(
     assets: &Res<GameAssets>,
     life_bar_resource: &Res<LifeBarResource>,
     chunk: &LevelChunk,
+    level: GameLevel,
+    difficulty: &Difficulty,
 ) {
     // エンティティの生成
     for (entity, x, y) in &chunk.entities {
@@ -415,6 +580,7 @@ fn spawn_entities(
                     &assets,
                     tx + TILE_HALF,
                     ty - TILE_HALF,
+                    level,
                     MagicCircleDestination::NextLevel,
                 );
             }
@@ -424,6 +590,7 @@ fn spawn_entities(
                     &assets,
                     tx + TILE_HALF,
                     ty - TILE_HALF,
+                    level,
                     MagicCircleDestination::Home,
                 );
             }
@@ -433,9 +600,20 @@ fn spawn_entities(
                     &assets,
                     tx + TILE_HALF,
                     ty - TILE_HALF,
+                    level,
                     MagicCircleDestination::MultiplayArena,
                 );
             }
+            GameEntity::MagicCircleExit(exit) => {
+                spawn_magic_circle(
+                    &mut commands,
+                    &assets,
+                    tx + TILE_HALF,
+                    ty - TILE_HALF,
+                    level,
+                    MagicCircleDestination::Exit(*exit),
+                );
+            }
             GameEntity::BrokenMagicCircle => {
                 spawn_broken_magic_circle(
                     &mut commands,
@@ -476,9 +654,8 @@ fn spawn_entities(
                 ));
             }
             GameEntity::Spell => {
-                let mut rng = rand::thread_rng();
                 if 0.5 < rand::random::<f32>() {
-                    let spell = SpellType::iter().choose(&mut rng).unwrap();
+                    let spell = difficulty.choose_spell();
                     let props = spell.to_props();
                     spawn_dropped_item(
                         &mut commands,
@@ -486,12 +663,11 @@ fn spawn_entities(
                         Vec2::new(tx + TILE_HALF, ty - TILE_HALF),
                         InventoryItem {
                             item_type: InventoryItemType::Spell(spell),
-                            price: props.price,
+                            price: difficulty.scale_price(props.price),
                         },
                     );
                 } else {
-                    let mut rng = rand::thread_rng();
-                    let equipment = EquipmentType::iter().choose(&mut rng).unwrap();
+                    let equipment = difficulty.choose_equipment();
                     let props = equipment.to_props();
                     spawn_dropped_item(
                         &mut commands,
@@ -499,7 +675,7 @@ fn spawn_entities(
                         Vec2::new(tx + TILE_HALF, ty - TILE_HALF),
                         InventoryItem {
                             item_type: InventoryItemType::Equipment(equipment),
-                            price: props.price,
+                            price: difficulty.scale_price(props.price),
                         },
                     );
                 }
@@ -533,44 +709,34 @@ fn spawn_entities(
                     Vec2::new(tx + TILE_HALF, ty - TILE_HALF),
                 );
             }
+            GameEntity::Stash => {
+                spawn_stash(
+                    &mut commands,
+                    &assets,
+                    Vec2::new(tx + TILE_HALF, ty - TILE_HALF),
+                );
+            }
+            GameEntity::Door(lock) => {
+                spawn_door(
+                    &mut commands,
+                    &assets,
+                    level,
+                    *x,
+                    *y,
+                    chunk.to_local(*x, *y),
+                    *lock,
+                );
+            }
+            GameEntity::DoorSwitch(id) => {
+                spawn_door_switch(&mut commands, &assets, level, *x, *y, *id);
+            }
+            GameEntity::Trap(kind) => {
+                spawn_trap(&mut commands, &assets, level, *x, *y, *kind);
+            }
         }
     }
 }
 
-pub fn level_to_name(level: GameLevel) -> Dict<&'static str> {
-    match level {
-        GameLevel::Level(0) => Dict {
-            ja: "見捨てられた工房",
-            en: "Abandoned Workshop",
-        },
-
-        GameLevel::Level(1) => Dict {
-            ja: "図書館跡",
-            en: "Library Ruins",
-        },
-
-        GameLevel::Level(2) => Dict {
-            ja: "洞窟",
-            en: "Cave",
-        },
-
-        GameLevel::Level(3) => Dict {
-            ja: "スライムの巣窟",
-            en: "Slime Nest",
-        },
-
-        GameLevel::MultiPlayArena => Dict {
-            ja: "対決の洞窟",
-            en: "Arena Cave",
-        },
-
-        _ => Dict {
-            ja: "不明",
-            en: "Unknown",
-        },
-    }
-}
-
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
@@ -578,5 +744,7 @@ impl Plugin for WorldPlugin {
         app.add_systems(OnEnter(GameState::InGame), setup_level);
         app.add_systems(OnEnter(GameState::InGame), select_level_bgm);
         app.init_resource::<CurrentLevel>();
+        app.init_asset::<LdtkProject>();
+        app.init_asset_loader::<LdtkLoader>();
     }
 }
@@ -1,4 +1,4 @@
-use crate::level::{map::LevelChunk, Tile, WorldTile, TILE_HALF, TILE_SIZE, WALL_HEIGHT};
+use crate::level::{map::LevelChunk, WorldTile, TILE_HALF, TILE_SIZE, WALL_HEIGHT};
 use crate::{asset::GameAssets, entity::get_entity_z, states::GameState};
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::AseSpriteSlice;
@@ -11,9 +11,9 @@ pub fn spawn_roof_tiles(
     y: i32,
 ) {
     let left_top = match (
-        chunk.equals(x - 1, y - 1, Tile::Wall),
-        chunk.equals(x + 0, y - 1, Tile::Wall),
-        chunk.equals(x - 1, y + 0, Tile::Wall),
+        chunk.is_wall(x - 1, y - 1),
+        chunk.is_wall(x + 0, y - 1),
+        chunk.is_wall(x - 1, y + 0),
     ) {
         (false, false, false) => 0,
         (false, false, true) => 1, // 2
@@ -27,9 +27,9 @@ pub fn spawn_roof_tiles(
     spawn_roof_tile(commands, assets, x, y, 0, 0, left_top);
 
     let right_top = match (
-        chunk.equals(x + 0, y - 1, Tile::Wall),
-        chunk.equals(x + 1, y - 1, Tile::Wall),
-        chunk.equals(x + 1, y + 0, Tile::Wall),
+        chunk.is_wall(x + 0, y - 1),
+        chunk.is_wall(x + 1, y - 1),
+        chunk.is_wall(x + 1, y + 0),
     ) {
         (false, false, false) => 3,
         (false, false, true) => 1, // 2
@@ -43,9 +43,9 @@ pub fn spawn_roof_tiles(
     spawn_roof_tile(commands, assets, x, y, 1, 0, right_top);
 
     let left_bottom = match (
-        chunk.equals(x - 1, y + 0, Tile::Wall),
-        chunk.equals(x - 1, y + 1, Tile::Wall),
-        chunk.equals(x + 0, y + 1, Tile::Wall),
+        chunk.is_wall(x - 1, y + 0),
+        chunk.is_wall(x - 1, y + 1),
+        chunk.is_wall(x + 0, y + 1),
     ) {
         (false, false, false) => 12,
         (false, false, true) => 4, // 8
@@ -59,9 +59,9 @@ pub fn spawn_roof_tiles(
     spawn_roof_tile(commands, assets, x, y, 0, 1, left_bottom);
 
     let right_bottom = match (
-        chunk.equals(x + 1, y + 0, Tile::Wall),
-        chunk.equals(x + 0, y + 1, Tile::Wall),
-        chunk.equals(x + 1, y + 1, Tile::Wall),
+        chunk.is_wall(x + 1, y + 0),
+        chunk.is_wall(x + 0, y + 1),
+        chunk.is_wall(x + 1, y + 1),
     ) {
         (false, false, false) => 15,
         (false, false, true) => 15,
@@ -84,12 +84,13 @@ fn spawn_roof_tile(
     dy: i32,
     roof_index: i32,
 ) {
+    let tile = WorldTile { x, y };
     let x = TILE_SIZE * x as f32 + TILE_HALF * dx as f32;
     let y = (TILE_SIZE * -y as f32) + TILE_HALF * -dy as f32 + WALL_HEIGHT;
     let z = get_entity_z(y - WALL_HEIGHT);
     commands.spawn((
         Name::new("ceil"),
-        WorldTile,
+        tile,
         StateScoped(GameState::InGame),
         Transform::from_xyz(x, y, z),
         AseSpriteSlice {
@@ -0,0 +1,225 @@
+use crate::entity::trap::TrapKind;
+use crate::entity::GameEntity;
+use crate::level::generator::{chunk_index, distance_map};
+use crate::level::map::{Biome, LevelChunk};
+use crate::level::tile::Tile;
+use std::fmt;
+
+/// レベルのデータに含まれる問題です
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelIssue {
+    /// タイルにもエンティティにも対応しない色のピクセルがあります
+    UnknownColor { x: i32, y: i32, color: [u8; 4] },
+
+    /// プレイヤーの出現位置がひとつもありません
+    NoEntryPoint,
+
+    /// 出現位置から歩いて到達できない魔法陣があります
+    UnreachableExit { x: i32, y: i32 },
+
+    /// 歩いて通過できない場所にエンティティが置かれています
+    EntityOnWall { entity: GameEntity, x: i32, y: i32 },
+}
+
+impl fmt::Display for LevelIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelIssue::UnknownColor { x, y, color } => write!(
+                f,
+                "unknown color ({}, {}, {}, {}) at ({}, {})",
+                color[0], color[1], color[2], color[3], x, y
+            ),
+            LevelIssue::NoEntryPoint => write!(f, "no entry point"),
+            LevelIssue::UnreachableExit { x, y } => {
+                write!(f, "magic circle at ({}, {}) is unreachable", x, y)
+            }
+            LevelIssue::EntityOnWall { entity, x, y } => {
+                write!(
+                    f,
+                    "{:?} at ({}, {}) is not placed on a walkable tile",
+                    entity, x, y
+                )
+            }
+        }
+    }
+}
+
+fn is_exit(entity: GameEntity) -> bool {
+    match entity {
+        GameEntity::MagicCircle
+        | GameEntity::MagicCircleHome
+        | GameEntity::MultiPlayArenaMagicCircle
+        | GameEntity::MagicCircleExit(_) => true,
+        _ => false,
+    }
+}
+
+/// 読み込んだチャンクの構造を検査します
+/// 出現位置はランダムに選ばれるので、どの出現位置からもすべての魔法陣に到達できなければなりません
+pub fn check_chunk(chunk: &LevelChunk) -> Vec<LevelIssue> {
+    let mut issues = Vec::new();
+
+    for (entity, x, y) in chunk.entities.iter() {
+        if !chunk.is_empty(*x, *y) {
+            issues.push(LevelIssue::EntityOnWall {
+                entity: *entity,
+                x: *x,
+                y: *y,
+            });
+        }
+    }
+
+    if chunk.entry_points.is_empty() {
+        issues.push(LevelIssue::NoEntryPoint);
+    }
+
+    let distances: Vec<Vec<Option<u32>>> = chunk
+        .entry_points
+        .iter()
+        .map(|p| distance_map(chunk, p.x as i32, p.y as i32))
+        .collect();
+
+    for (entity, x, y) in chunk.entities.iter() {
+        if is_exit(*entity) && chunk.is_empty(*x, *y) {
+            let i = chunk_index(chunk, *x, *y);
+            if distances.iter().any(|d| d[i].is_none()) {
+                issues.push(LevelIssue::UnreachableExit { x: *x, y: *y });
+            }
+        }
+    }
+
+    issues
+}
+
+/// チャンクを一文字一マスのテキストで描画します
+/// # は壁、% はひび割れた壁、. はモンスターの出現する床、_ はモンスターの出現しない床、~ は水、^ は溶岩、* は穴、= は氷で、
+/// エンティティのあるマスはエンティティの種類を表す文字になります
+/// 見分けられるよう、タイルとエンティティにはそれぞれ異なる文字を割り当てています
+pub fn render_ascii(chunk: &LevelChunk) -> String {
+    let mut text = String::new();
+    for y in chunk.min_y..chunk.max_y {
+        for x in chunk.min_x..chunk.max_x {
+            let entity = chunk
+                .entities
+                .iter()
+                .find(|(_, ex, ey)| *ex == x && *ey == y)
+                .map(|(e, _, _)| *e);
+            let c = match entity {
+                Some(entity) => entity_char(entity),
+                None => tile_char(chunk.get_tile(x, y), chunk.get_biome(x, y)),
+            };
+            text.push(c);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn tile_char(tile: Tile, biome: Biome) -> char {
+    match (tile, biome) {
+        (Tile::Wall, _) => '#',
+        (Tile::CrackedWall, _) => '%',
+        (Tile::StoneTile, Biome::Dungeon) => '.',
+        (Tile::StoneTile, Biome::SafeZone) => '_',
+        (Tile::Water, _) => '~',
+        (Tile::Lava, _) => '^',
+        (Tile::Pit, _) => '*',
+        (Tile::Ice, _) => '=',
+        (Tile::Blank, _) => ' ',
+    }
+}
+
+fn entity_char(entity: GameEntity) -> char {
+    match entity {
+        GameEntity::Chest => 'C',
+        GameEntity::Crate => 'c',
+        GameEntity::CrateOrBarrel => 'b',
+        GameEntity::BookShelf => 'B',
+        GameEntity::MagicCircle => 'M',
+        GameEntity::MagicCircleHome => 'H',
+        GameEntity::MultiPlayArenaMagicCircle => 'A',
+        GameEntity::MagicCircleExit(_) => 'X',
+        GameEntity::BrokenMagicCircle => 'E',
+        GameEntity::Usage => 'u',
+        GameEntity::Routes => 'r',
+        GameEntity::StoneLantern => 'L',
+        GameEntity::Spell => 'S',
+        GameEntity::HugeSlime => 'K',
+        GameEntity::Rabbit => 'R',
+        GameEntity::Sandbug => 'D',
+        GameEntity::ShopDoor => 'O',
+        GameEntity::Stash => 'Z',
+        GameEntity::Door(_) => '+',
+        GameEntity::DoorSwitch(_) => 'o',
+        GameEntity::Trap(TrapKind::PressurePlate(_)) => 'p',
+        GameEntity::Trap(TrapKind::Spikes) => '!',
+        GameEntity::Trap(TrapKind::Turret(..)) => 'T',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::door::DoorLock;
+    use crate::entity::trap::TurretAim;
+    use std::collections::HashMap;
+
+    #[test]
+    fn ascii_characters_are_unique() {
+        let mut chars: HashMap<char, String> = HashMap::new();
+        let mut insert = |c: char, name: String| {
+            if let Some(other) = chars.insert(c, name.clone()) {
+                panic!("'{}' is used for both {} and {}", c, other, name);
+            }
+        };
+
+        for tile in [
+            Tile::Blank,
+            Tile::Wall,
+            Tile::StoneTile,
+            Tile::Water,
+            Tile::Lava,
+            Tile::Pit,
+            Tile::Ice,
+            Tile::CrackedWall,
+        ] {
+            // 床だけはバイオームによって文字を変えています
+            let biomes: &[Biome] = if tile == Tile::StoneTile {
+                &[Biome::Dungeon, Biome::SafeZone]
+            } else {
+                &[Biome::Dungeon]
+            };
+            for biome in biomes {
+                insert(tile_char(tile, *biome), format!("{:?} {:?}", tile, biome));
+            }
+        }
+
+        for entity in [
+            GameEntity::Chest,
+            GameEntity::Crate,
+            GameEntity::CrateOrBarrel,
+            GameEntity::BookShelf,
+            GameEntity::MagicCircle,
+            GameEntity::MagicCircleHome,
+            GameEntity::MultiPlayArenaMagicCircle,
+            GameEntity::MagicCircleExit(0),
+            GameEntity::BrokenMagicCircle,
+            GameEntity::Usage,
+            GameEntity::Routes,
+            GameEntity::StoneLantern,
+            GameEntity::Spell,
+            GameEntity::HugeSlime,
+            GameEntity::Rabbit,
+            GameEntity::Sandbug,
+            GameEntity::ShopDoor,
+            GameEntity::Stash,
+            GameEntity::Door(DoorLock::Unlocked),
+            GameEntity::DoorSwitch(0),
+            GameEntity::Trap(TrapKind::PressurePlate(0)),
+            GameEntity::Trap(TrapKind::Spikes),
+            GameEntity::Trap(TrapKind::Turret(TurretAim::Down, None)),
+        ] {
+            insert(entity_char(entity), format!("{:?}", entity));
+        }
+    }
+}
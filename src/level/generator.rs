@@ -0,0 +1,645 @@
+use crate::entity::GameEntity;
+use crate::level::map::{Biome, LevelChunk};
+use crate::level::tile::Tile;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::collections::{HashSet, VecDeque};
+
+/// 自動生成されるレベルの幅(タイル数)
+pub const GENERATED_LEVEL_WIDTH: i32 = 64;
+
+/// 自動生成されるレベルの高さ(タイル数)
+pub const GENERATED_LEVEL_HEIGHT: i32 = 48;
+
+/// 自動生成できるダンジョンの最小の幅(タイル数)
+/// もっとも幅の広い部屋を、周囲の余白を含めて配置できる大きさです
+pub const MIN_DUNGEON_WIDTH: i32 = 16;
+
+/// 自動生成できるダンジョンの最小の高さ(タイル数)
+/// 商店や隠し部屋を、下側の通路を含めて配置できる大きさです
+pub const MIN_DUNGEON_HEIGHT: i32 = 15;
+
+/// 部屋の配置を試みる回数
+const ROOM_ATTEMPTS: u32 = 200;
+
+const MAX_ROOMS: usize = 10;
+
+/// 通路の幅
+const CORRIDOR_WIDTH: i32 = 2;
+
+/// 隠し部屋が配置される確率
+const SECRET_ROOM_PROBABILITY: f64 = 0.5;
+
+/// 分岐する出口の魔法陣が、それぞれ配置される確率
+/// ただし、分岐の出口があるレベルでは少なくともひとつは必ず配置されます
+const BRANCH_EXIT_PROBABILITY: f64 = 0.35;
+
+/// 宝物庫のレベルの幅と高さ(タイル数)
+const VAULT_WIDTH: i32 = 24;
+const VAULT_HEIGHT: i32 = 16;
+
+/// 部屋を表す矩形です
+/// x, y は左上の床のタイル、w, h は床の大きさです
+#[derive(Clone, Copy, Debug)]
+struct Room {
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+}
+
+impl Room {
+    fn center(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+
+    /// 周囲に margin マスの余白を含めて、別の部屋と重なっているかどうかを返します
+    fn intersects(&self, other: &Room, margin: i32) -> bool {
+        self.x - margin < other.x + other.w
+            && other.x < self.x + self.w + margin
+            && self.y - margin < other.y + other.h
+            && other.y < self.y + self.h + margin
+    }
+}
+
+/// シードから部屋と通路で構成されたダンジョンを生成します
+/// 同じシードからは常に同じレベルが生成されます
+/// 入口の部屋には壊れた魔法陣とプレイヤーの出現位置が、もっとも遠い部屋には次のレベルへの魔法陣が配置されます
+/// shop が true の場合は、ほかの部屋から扉を通ってのみ入ることのできる商店の部屋も配置します
+/// また、一定の確率でひび割れた壁の奥に隠し部屋を配置します
+/// exits にはレベルの設定にある分岐の出口の数を指定し、それぞれ一定の確率で入口と出口以外の部屋に配置します
+/// exits が 1 以上であれば、分岐の出口のうちひとつは必ず配置されます
+/// 幅と高さは MIN_DUNGEON_WIDTH と MIN_DUNGEON_HEIGHT 以上でなければなりません
+pub fn generate_dungeon(seed: u64, width: i32, height: i32, shop: bool, exits: u8) -> LevelChunk {
+    assert!(
+        MIN_DUNGEON_WIDTH <= width && MIN_DUNGEON_HEIGHT <= height,
+        "dungeon size {}x{} is smaller than {}x{}",
+        width,
+        height,
+        MIN_DUNGEON_WIDTH,
+        MIN_DUNGEON_HEIGHT
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut chunk = LevelChunk::new(0, width, 0, height);
+
+    // 部屋の配置
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..ROOM_ATTEMPTS {
+        if MAX_ROOMS <= rooms.len() {
+            break;
+        }
+        let w = rng.gen_range(6..12);
+        let h = rng.gen_range(5..9);
+        let room = Room {
+            x: rng.gen_range(2..width - w - 2),
+            y: rng.gen_range(2..height - h - 2),
+            w,
+            h,
+        };
+        if rooms.iter().all(|r| !room.intersects(r, 3)) {
+            rooms.push(room);
+        }
+    }
+
+    // 部屋がひとつもない状態では必ず配置できるので、少なくとも入口の部屋は存在します
+    // 左の部屋から順に通路でつなぐことで、すべての部屋が連結されることを保証します
+    rooms.sort_by_key(|r| r.center());
+    for room in rooms.iter() {
+        carve_room(&mut chunk, room, Biome::Dungeon);
+    }
+    for pair in rooms.windows(2) {
+        carve_corridor(&mut chunk, pair[0].center(), pair[1].center(), &mut rng);
+    }
+
+    let mut occupied: HashSet<(i32, i32)> = HashSet::new();
+
+    // 入口の部屋にはモンスターが出現しないようにします
+    let entry = rooms[0];
+    carve_room(&mut chunk, &entry, Biome::SafeZone);
+    let (ex, ey) = entry.center();
+    chunk.entry_points.push(Vec2::new(ex as f32, ey as f32));
+    chunk.entities.push((GameEntity::BrokenMagicCircle, ex, ey));
+    occupy_around(&mut occupied, ex, ey);
+
+    // 入口からもっとも遠い部屋に出口の魔法陣を置きます
+    // 小さなダンジョンで部屋がひとつしか配置できなかった場合は、入口から離れた隅まで通路を伸ばして、その先に置きます
+    let distances = distance_map(&chunk, ex, ey);
+    let (xx, xy) = match rooms.iter().skip(1).max_by_key(|r| {
+        let (cx, cy) = r.center();
+        distances[chunk_index(&chunk, cx, cy)].unwrap_or(0)
+    }) {
+        Some(exit) => exit.center(),
+        None => carve_dead_end(&mut chunk, (ex, ey), &[(ex, ey)], &mut rng),
+    };
+    chunk.entities.push((GameEntity::MagicCircle, xx, xy));
+    occupy_around(&mut occupied, xx, xy);
+
+    // 分岐の出口の魔法陣
+    let mut branch_rooms: Vec<(i32, i32)> = rooms
+        .iter()
+        .map(|r| r.center())
+        .filter(|c| *c != (ex, ey) && *c != (xx, xy))
+        .collect();
+    let guaranteed = if 0 < exits {
+        Some(rng.gen_range(0..exits))
+    } else {
+        None
+    };
+    for i in 0..exits {
+        if Some(i) != guaranteed && !rng.gen_bool(BRANCH_EXIT_PROBABILITY) {
+            continue;
+        }
+        let (bx, by) = if !branch_rooms.is_empty() {
+            branch_rooms.swap_remove(rng.gen_range(0..branch_rooms.len()))
+        } else if Some(i) == guaranteed {
+            // 空いている部屋がなければ、入口と出口から離れた隅まで通路を伸ばして配置します
+            carve_dead_end(&mut chunk, (ex, ey), &[(ex, ey), (xx, xy)], &mut rng)
+        } else {
+            continue;
+        };
+        chunk
+            .entities
+            .push((GameEntity::MagicCircleExit(i), bx, by));
+        occupy_around(&mut occupied, bx, by);
+    }
+
+    // 宝箱と木箱、灯籠
+    for (i, room) in rooms.iter().enumerate() {
+        if occupied.insert((room.x, room.y)) {
+            chunk
+                .entities
+                .push((GameEntity::StoneLantern, room.x, room.y));
+        }
+
+        if i != 0 && rng.gen_bool(0.4) {
+            let (x, y) = (room.x + room.w - 1, room.y);
+            if occupied.insert((x, y)) {
+                chunk.entities.push((GameEntity::Chest, x, y));
+            }
+        }
+
+        for _ in 0..rng.gen_range(0..4) {
+            // 部屋の下端の壁沿いに並べます
+            let x = rng.gen_range(room.x..room.x + room.w);
+            let y = room.y + room.h - 1;
+            if occupied.insert((x, y)) {
+                chunk.entities.push((GameEntity::CrateOrBarrel, x, y));
+            }
+        }
+    }
+
+    if shop {
+        place_shop(&mut chunk, &rooms, &mut rng);
+    }
+
+    if rng.gen_bool(SECRET_ROOM_PROBABILITY) {
+        place_secret_room(&mut chunk, &rooms, &mut rng);
+    }
+
+    debug_assert!(is_connected(&chunk), "dungeon {} is not connected", seed);
+
+    chunk
+}
+
+/// 敵のいない小さな部屋に宝箱を並べた宝物庫を生成します
+/// 左端に入口が、右端に次のレベルへの魔法陣が配置されます
+pub fn generate_vault(seed: u64) -> LevelChunk {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut chunk = LevelChunk::new(0, VAULT_WIDTH, 0, VAULT_HEIGHT);
+
+    let room = Room {
+        x: 3,
+        y: 3,
+        w: VAULT_WIDTH - 6,
+        h: VAULT_HEIGHT - 6,
+    };
+    carve_room(&mut chunk, &room, Biome::SafeZone);
+
+    let (_, cy) = room.center();
+    let (ex, xx) = (room.x + 1, room.x + room.w - 2);
+    chunk.entry_points.push(Vec2::new(ex as f32, cy as f32));
+    chunk.entities.push((GameEntity::BrokenMagicCircle, ex, cy));
+    chunk.entities.push((GameEntity::MagicCircle, xx, cy));
+
+    // 上下の壁沿いに宝箱を並べます
+    for x in (room.x + 4..room.x + room.w - 4).step_by(2) {
+        chunk.entities.push((GameEntity::Chest, x, room.y));
+        if rng.gen_bool(0.5) {
+            chunk
+                .entities
+                .push((GameEntity::Chest, x, room.y + room.h - 1));
+        } else {
+            chunk
+                .entities
+                .push((GameEntity::CrateOrBarrel, x, room.y + room.h - 1));
+        }
+    }
+    for (x, y) in [
+        (room.x, room.y),
+        (room.x + room.w - 1, room.y),
+        (room.x, room.y + room.h - 1),
+        (room.x + room.w - 1, room.y + room.h - 1),
+    ] {
+        chunk.entities.push((GameEntity::StoneLantern, x, y));
+    }
+    chunk
+        .entities
+        .push((GameEntity::Spell, room.x + room.w / 2, cy));
+
+    chunk
+}
+
+fn carve_room(chunk: &mut LevelChunk, room: &Room, biome: Biome) {
+    for y in room.y..room.y + room.h {
+        for x in room.x..room.x + room.w {
+            chunk.set_tile(x, y, Tile::StoneTile);
+            chunk.set_biome(x, y, biome);
+        }
+    }
+}
+
+/// 壁の部分だけを床に変えます
+/// 部屋の中を通過する場合でも、部屋のバイオームは変わりません
+fn carve_floor(chunk: &mut LevelChunk, x: i32, y: i32) {
+    if chunk.get_tile(x, y) == Tile::Wall {
+        chunk.set_tile(x, y, Tile::StoneTile);
+        chunk.set_biome(x, y, Biome::Dungeon);
+    }
+}
+
+/// 二点をL字型の通路でつなぎます
+fn carve_corridor(chunk: &mut LevelChunk, from: (i32, i32), to: (i32, i32), rng: &mut StdRng) {
+    let (fx, fy) = from;
+    let (tx, ty) = to;
+    let corner = if rng.gen_bool(0.5) {
+        (tx, fy)
+    } else {
+        (fx, ty)
+    };
+    carve_line(chunk, (fx, fy), corner);
+    carve_line(chunk, corner, (tx, ty));
+}
+
+fn carve_line(chunk: &mut LevelChunk, from: (i32, i32), to: (i32, i32)) {
+    let (fx, fy) = from;
+    let (tx, ty) = to;
+    for y in fy.min(ty)..=fy.max(ty) {
+        for x in fx.min(tx)..=fx.max(tx) {
+            for dy in 0..CORRIDOR_WIDTH {
+                for dx in 0..CORRIDOR_WIDTH {
+                    carve_floor(chunk, x + dx, y + dy);
+                }
+            }
+        }
+    }
+}
+
+/// 指定した位置から、避けたい位置からもっとも離れたレベルの隅まで通路を伸ばし、その行き止まりの位置を返します
+/// 部屋が足りないときに、魔法陣を置く場所を確保するために使います
+fn carve_dead_end(
+    chunk: &mut LevelChunk,
+    from: (i32, i32),
+    avoid: &[(i32, i32)],
+    rng: &mut StdRng,
+) -> (i32, i32) {
+    // 通路は CORRIDOR_WIDTH の幅で右下に広がるので、外周の2マスの壁を残せる位置を隅とします
+    let (min_x, min_y) = (chunk.min_x + 2, chunk.min_y + 2);
+    let (max_x, max_y) = (
+        chunk.max_x - 2 - CORRIDOR_WIDTH,
+        chunk.max_y - 2 - CORRIDOR_WIDTH,
+    );
+    let corner = [
+        (min_x, min_y),
+        (max_x, min_y),
+        (min_x, max_y),
+        (max_x, max_y),
+    ]
+    .into_iter()
+    .max_by_key(|(x, y)| {
+        avoid
+            .iter()
+            .map(|(ax, ay)| (x - ax).abs() + (y - ay).abs())
+            .min()
+            .unwrap_or(0)
+    })
+    .unwrap();
+    carve_corridor(chunk, from, corner, rng);
+    corner
+}
+
+/// ほかの部屋から離れた場所に、下側の入口からだけ出入りできる部屋を配置します
+/// 入口から真下に伸びる通路でほかの床につながります
+/// 配置できた場合は、部屋と入口の左端の位置を返します
+fn place_annex(
+    chunk: &mut LevelChunk,
+    rooms: &[Room],
+    rng: &mut StdRng,
+    w: i32,
+    h: i32,
+) -> Option<(Room, i32, i32)> {
+    for _ in 0..ROOM_ATTEMPTS {
+        let annex = Room {
+            x: rng.gen_range(2..chunk.max_x - w - 2),
+            y: rng.gen_range(2..chunk.max_y - h - 7),
+            w,
+            h,
+        };
+
+        // 既存の床に接していない場所でなければなりません
+        if rooms.iter().any(|r| annex.intersects(r, 3)) || !is_solid(chunk, &annex, 2) {
+            continue;
+        }
+
+        // 入口の位置は部屋の下の壁の中央です
+        let door_x = annex.x + annex.w / 2 - 1;
+        let door_y = annex.y + annex.h;
+
+        // 入口の真下にある床を探し、そこまで通路を伸ばします
+        let target = (door_y + 2..chunk.max_y - 1).find_map(|y| {
+            (0..chunk.max_x)
+                .filter(|x| chunk.is_empty(*x, y))
+                .min_by_key(|x| (x - door_x).abs())
+                .map(|x| (x, y))
+        });
+        let Some((target_x, target_y)) = target else {
+            continue;
+        };
+
+        carve_room(chunk, &annex, Biome::SafeZone);
+        for x in door_x..door_x + CORRIDOR_WIDTH {
+            chunk.set_tile(x, door_y, Tile::StoneTile);
+            chunk.set_biome(x, door_y, Biome::SafeZone);
+        }
+        carve_line(chunk, (door_x, door_y + 1), (door_x, target_y));
+        carve_line(chunk, (door_x, target_y), (target_x, target_y));
+
+        return Some((annex, door_x, door_y));
+    }
+    None
+}
+
+/// 商店の部屋を配置します
+/// 商店の部屋は下側の扉からだけ出入りできます
+fn place_shop(chunk: &mut LevelChunk, rooms: &[Room], rng: &mut StdRng) {
+    if let Some((shop, door_x, door_y)) = place_annex(chunk, rooms, rng, 8, 5) {
+        chunk.entities.push((GameEntity::ShopDoor, door_x, door_y));
+        chunk
+            .entities
+            .push((GameEntity::Rabbit, shop.x + 1, shop.y + shop.h - 1));
+        for i in 0..4 {
+            chunk
+                .entities
+                .push((GameEntity::Spell, shop.x + 1 + i * 2, shop.y + 1));
+        }
+    }
+}
+
+/// 隠し部屋を配置します
+/// 隠し部屋の入口はひび割れた壁で塞がれていて、弾丸で壊すと中の宝箱を取ることができます
+fn place_secret_room(chunk: &mut LevelChunk, rooms: &[Room], rng: &mut StdRng) {
+    if let Some((secret, door_x, door_y)) = place_annex(chunk, rooms, rng, 4, 3) {
+        for x in door_x..door_x + CORRIDOR_WIDTH {
+            chunk.set_tile(x, door_y, Tile::CrackedWall);
+        }
+        let (cx, cy) = secret.center();
+        chunk.entities.push((GameEntity::Chest, cx, cy - 1));
+        chunk.entities.push((GameEntity::Spell, cx - 1, cy));
+    }
+}
+
+/// 部屋の周囲 margin マスまでがすべて壁かどうかを返します
+fn is_solid(chunk: &LevelChunk, room: &Room, margin: i32) -> bool {
+    for y in room.y - margin..room.y + room.h + margin {
+        for x in room.x - margin..room.x + room.w + margin {
+            if chunk.get_tile(x, y) != Tile::Wall {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn occupy_around(occupied: &mut HashSet<(i32, i32)>, x: i32, y: i32) {
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            occupied.insert((x + dx, y + dy));
+        }
+    }
+}
+
+pub fn chunk_index(chunk: &LevelChunk, x: i32, y: i32) -> usize {
+    let w = chunk.max_x - chunk.min_x;
+    ((y - chunk.min_y) * w + (x - chunk.min_x)) as usize
+}
+
+/// 指定した位置から各床タイルまでの歩数を求めます
+/// 到達できないタイルや床以外のタイルは None になります
+pub fn distance_map(chunk: &LevelChunk, x: i32, y: i32) -> Vec<Option<u32>> {
+    let w = chunk.max_x - chunk.min_x;
+    let h = chunk.max_y - chunk.min_y;
+    let mut distances: Vec<Option<u32>> = vec![None; (w * h) as usize];
+    if !chunk.is_empty(x, y) {
+        return distances;
+    }
+    let mut queue: VecDeque<(i32, i32)> = VecDeque::new();
+    distances[chunk_index(chunk, x, y)] = Some(0);
+    queue.push_back((x, y));
+    while let Some((cx, cy)) = queue.pop_front() {
+        let d = distances[chunk_index(chunk, cx, cy)].unwrap();
+        for (nx, ny) in [(cx + 1, cy), (cx - 1, cy), (cx, cy + 1), (cx, cy - 1)] {
+            if chunk.is_empty(nx, ny) {
+                let i = chunk_index(chunk, nx, ny);
+                if distances[i].is_none() {
+                    distances[i] = Some(d + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// すべての床タイルが、最初の入口から歩いて到達できるかどうかを返します
+/// ひび割れた壁は弾丸で壊せるので、通過できるものとして扱います
+pub fn is_connected(chunk: &LevelChunk) -> bool {
+    let Some(entry) = chunk.entry_points.first() else {
+        return false;
+    };
+    let mut opened = chunk.clone();
+    for y in chunk.min_y..chunk.max_y {
+        for x in chunk.min_x..chunk.max_x {
+            if chunk.get_tile(x, y) == Tile::CrackedWall {
+                opened.set_tile(x, y, Tile::StoneTile);
+            }
+        }
+    }
+    let distances = distance_map(&opened, entry.x as i32, entry.y as i32);
+    for y in chunk.min_y..chunk.max_y {
+        for x in chunk.min_x..chunk.max_x {
+            if opened.is_empty(x, y) && distances[chunk_index(&opened, x, y)].is_none() {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::check::{check_chunk, render_ascii};
+
+    fn dungeon(seed: u64) -> LevelChunk {
+        generate_dungeon(
+            seed,
+            GENERATED_LEVEL_WIDTH,
+            GENERATED_LEVEL_HEIGHT,
+            seed % 2 == 0,
+            2,
+        )
+    }
+
+    fn find_entity(chunk: &LevelChunk, entity: GameEntity) -> Option<(i32, i32)> {
+        chunk
+            .entities
+            .iter()
+            .find(|(e, _, _)| *e == entity)
+            .map(|(_, x, y)| (*x, *y))
+    }
+
+    #[test]
+    fn same_seed_generates_same_dungeon() {
+        for seed in [0, 1, 42, 12345] {
+            let a = dungeon(seed);
+            let b = dungeon(seed);
+            assert_eq!(render_ascii(&a), render_ascii(&b));
+            assert_eq!(a.entry_points, b.entry_points);
+            assert_eq!(format!("{:?}", a.entities), format!("{:?}", b.entities));
+        }
+    }
+
+    #[test]
+    fn different_seeds_generate_different_dungeons() {
+        assert_ne!(render_ascii(&dungeon(1)), render_ascii(&dungeon(2)));
+    }
+
+    #[test]
+    fn exit_is_reachable_from_entry() {
+        for seed in 0..200 {
+            let chunk = dungeon(seed);
+            assert!(is_connected(&chunk), "seed {}", seed);
+            assert_eq!(check_chunk(&chunk), vec![], "seed {}", seed);
+
+            let entry = chunk.entry_points[0];
+            let (xx, xy) = find_entity(&chunk, GameEntity::MagicCircle).unwrap();
+            assert_ne!((xx, xy), (entry.x as i32, entry.y as i32), "seed {}", seed);
+            let distances = distance_map(&chunk, entry.x as i32, entry.y as i32);
+            assert!(
+                distances[chunk_index(&chunk, xx, xy)].is_some(),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn place_at_least_one_branch_exit() {
+        let count = |chunk: &LevelChunk| {
+            chunk
+                .entities
+                .iter()
+                .filter(|(e, _, _)| matches!(e, GameEntity::MagicCircleExit(_)))
+                .count()
+        };
+        for seed in 0..100 {
+            let chunk = generate_dungeon(
+                seed,
+                GENERATED_LEVEL_WIDTH,
+                GENERATED_LEVEL_HEIGHT,
+                false,
+                3,
+            );
+            assert!(1 <= count(&chunk), "seed {}", seed);
+            assert_eq!(check_chunk(&chunk), vec![], "seed {}", seed);
+
+            let chunk = generate_dungeon(seed, MIN_DUNGEON_WIDTH, MIN_DUNGEON_HEIGHT, false, 1);
+            assert_eq!(count(&chunk), 1, "seed {}", seed);
+            assert_eq!(check_chunk(&chunk), vec![], "seed {}", seed);
+
+            let chunk = generate_dungeon(seed, MIN_DUNGEON_WIDTH, MIN_DUNGEON_HEIGHT, false, 0);
+            assert_eq!(count(&chunk), 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn generate_smallest_dungeon() {
+        for seed in 0..50 {
+            let chunk = generate_dungeon(seed, MIN_DUNGEON_WIDTH, MIN_DUNGEON_HEIGHT, true, 2);
+            assert!(is_connected(&chunk), "seed {}", seed);
+            assert_eq!(check_chunk(&chunk), vec![], "seed {}", seed);
+
+            // 出口の魔法陣は入口とは別の場所に置かれます
+            let entry = chunk.entry_points[0];
+            let exit = find_entity(&chunk, GameEntity::MagicCircle).unwrap();
+            assert_ne!(exit, (entry.x as i32, entry.y as i32), "seed {}", seed);
+            let distances = distance_map(&chunk, entry.x as i32, entry.y as i32);
+            assert!(
+                distances[chunk_index(&chunk, exit.0, exit.1)].is_some(),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn place_exit_at_dead_end_without_second_room() {
+        let mut chunk = LevelChunk::new(0, MIN_DUNGEON_WIDTH, 0, MIN_DUNGEON_HEIGHT);
+        let room = Room {
+            x: 2,
+            y: 2,
+            w: 6,
+            h: 5,
+        };
+        carve_room(&mut chunk, &room, Biome::SafeZone);
+        let entry = room.center();
+        let mut rng = StdRng::seed_from_u64(0);
+        let exit = carve_dead_end(&mut chunk, entry, &[entry], &mut rng);
+        assert_ne!(exit, entry);
+        assert!(chunk.is_empty(exit.0, exit.1));
+        assert!(
+            distance_map(&chunk, entry.0, entry.1)[chunk_index(&chunk, exit.0, exit.1)].is_some()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn reject_too_small_dungeon() {
+        generate_dungeon(0, MIN_DUNGEON_WIDTH - 1, MIN_DUNGEON_HEIGHT, false, 0);
+    }
+
+    #[test]
+    fn vault_is_connected() {
+        for seed in 0..20 {
+            let chunk = generate_vault(seed);
+            assert!(is_connected(&chunk), "seed {}", seed);
+            assert_eq!(check_chunk(&chunk), vec![], "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn cracked_wall_does_not_disconnect() {
+        let mut chunk = LevelChunk::new(0, 8, 0, 3);
+        for x in 1..7 {
+            chunk.set_tile(x, 1, Tile::StoneTile);
+        }
+        chunk.set_tile(4, 1, Tile::CrackedWall);
+        chunk.entry_points.push(Vec2::new(1.0, 1.0));
+        assert!(is_connected(&chunk));
+
+        chunk.set_tile(4, 1, Tile::Wall);
+        assert!(!is_connected(&chunk));
+    }
+}
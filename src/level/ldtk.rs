@@ -0,0 +1,620 @@
+use crate::entity::door::DoorLock;
+use crate::entity::trap::{TrapKind, TurretAim};
+use crate::entity::GameEntity;
+use crate::level::map::{Biome, LevelChunk, SpawnTable};
+use crate::level::tile::Tile;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// LDtk のプロジェクトファイル(.ldtk)のうち、レベルの読み込みに必要な部分だけを表します
+/// https://ldtk.io/json/
+///
+/// レベルは次の規則で LevelChunk に変換されます
+///
+/// - `Tiles` という名前の IntGrid レイヤーが地形になります
+///   値が 0 のマスは壁、1 はモンスターが出現する床、2 はモンスターが出現しない床、
+///   3 は水、4 は溶岩、5 は穴、6 は氷、7 はひび割れた壁です
+/// - Entities レイヤーのエンティティは、識別子の名前で GameEntity に変換されます
+///   `Chest` は `kind` フィールド(Chest / Crate / CrateOrBarrel)で種類を、
+///   `MagicCircle` は `destination` フィールド(NextLevel / Home / MultiplayArena / Exit)で行き先を指定します
+///   `Exit` の魔法陣は整数の `exit` フィールドで、level.meta.json のそのレベルの exits の番号を指定します
+///   `EntryPoint` はプレイヤーの出現位置になり、壊れた魔法陣が置かれます
+///   `Door` は `lock` フィールド(Unlocked / Key / Boss / Switch)で鍵の種類を、
+///   `Switch` の扉と `Switch` エンティティは整数の `switch` フィールドで対応するスイッチの番号を指定します
+///   `PressurePlate` は踏むと同じ番号の `Switch` の扉と `Turret` を作動させます
+///   `Spikes` は一定の周期で出入りするトゲの床です
+///   `Turret` は `aim` フィールド(Up / Down / Left / Right / Player)で弾を撃つ向きを指定します
+///   連結する番号のない `Turret` は一定の間隔で撃ち続けます
+///   スイッチ、感圧板、扉、砲台は `switch` の番号の代わりに文字列の `link` フィールドで連結することもできます
+///   同じ `link` の名前を持つエンティティは、そのレベルの中で同じ番号に割り当てられます
+///   `Stash` は周回をまたいでアイテムを預けておける保管箱です
+/// - レベルの `spawn_table` フィールドで、出現する敵の組み合わせを指定できます
+///
+/// level.ldtk の `example` レベルは、これらの規則をひととおり使った見本です
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct LdtkProject {
+    pub levels: Vec<LdtkLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LdtkLevel {
+    pub identifier: String,
+
+    /// 外部ファイルにレベルを保存する設定の場合は null になりますが、その形式には対応していません
+    #[serde(rename = "layerInstances")]
+    pub layer_instances: Option<Vec<LdtkLayer>>,
+
+    #[serde(rename = "fieldInstances", default)]
+    pub field_instances: Vec<LdtkField>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LdtkLayer {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+
+    #[serde(rename = "__type")]
+    pub layer_type: String,
+
+    #[serde(rename = "__cWid")]
+    pub width: i32,
+
+    #[serde(rename = "__cHei")]
+    pub height: i32,
+
+    #[serde(rename = "intGridCsv", default)]
+    pub int_grid_csv: Vec<i32>,
+
+    #[serde(rename = "entityInstances", default)]
+    pub entity_instances: Vec<LdtkEntity>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LdtkEntity {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+
+    /// マス単位の座標です
+    #[serde(rename = "__grid")]
+    pub grid: [i32; 2],
+
+    #[serde(rename = "fieldInstances", default)]
+    pub field_instances: Vec<LdtkField>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LdtkField {
+    #[serde(rename = "__identifier")]
+    pub identifier: String,
+
+    #[serde(rename = "__value")]
+    pub value: Value,
+}
+
+impl LdtkProject {
+    pub fn level(&self, identifier: &str) -> Option<&LdtkLevel> {
+        self.levels.iter().find(|l| l.identifier == identifier)
+    }
+}
+
+/// 文字列のフィールドの値を返します
+/// フィールドが存在しないか、値が null の場合は None を返します
+fn string_field<'a>(fields: &'a [LdtkField], identifier: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|f| f.identifier == identifier)
+        .and_then(|f| f.value.as_str())
+}
+
+/// スイッチや出口の番号を表す整数のフィールドの値を返します
+fn number_field(
+    fields: &[LdtkField],
+    identifier: &str,
+    level: &LdtkLevel,
+    x: i32,
+    y: i32,
+) -> Result<u8, String> {
+    fields
+        .iter()
+        .find(|f| f.identifier == identifier)
+        .and_then(|f| f.value.as_u64())
+        .and_then(|v| u8::try_from(v).ok())
+        .ok_or_else(|| {
+            format!(
+                "{}: {} number (0-255) is required at ({}, {})",
+                level.identifier, identifier, x, y
+            )
+        })
+}
+
+/// 名前で連結されたエンティティに割り当てる番号の始まりです
+/// `switch` フィールドで直接指定された番号と重ならないようにしています
+const LINK_NUMBER_START: usize = 128;
+
+/// スイッチや感圧板と、それに連結されたエンティティの番号を返します
+/// 文字列の `link` フィールドがあれば、名前ごとにレベルの中で一意な番号を割り当てます
+/// なければ整数の `switch` フィールドの値を返し、どちらもなければ None を返します
+/// 名前の連結と衝突しないよう、`switch` フィールドの値は LINK_NUMBER_START 未満でなければなりません
+fn link_field(
+    fields: &[LdtkField],
+    links: &mut Vec<String>,
+    level: &LdtkLevel,
+    x: i32,
+    y: i32,
+) -> Result<Option<u8>, String> {
+    if let Some(name) = string_field(fields, "link") {
+        let index = match links.iter().position(|l| l == name) {
+            Some(index) => index,
+            None => {
+                links.push(name.to_string());
+                links.len() - 1
+            }
+        };
+        return u8::try_from(LINK_NUMBER_START + index)
+            .map(Some)
+            .map_err(|_| format!("{}: too many links at ({}, {})", level.identifier, x, y));
+    }
+    if fields
+        .iter()
+        .any(|f| f.identifier == "switch" && !f.value.is_null())
+    {
+        let number = number_field(fields, "switch", level, x, y)?;
+        if LINK_NUMBER_START <= number as usize {
+            return Err(format!(
+                "{}: switch number must be less than {} at ({}, {})",
+                level.identifier, LINK_NUMBER_START, x, y
+            ));
+        }
+        return Ok(Some(number));
+    }
+    Ok(None)
+}
+
+/// 連結が必須のエンティティの番号を返します
+fn required_link_field(
+    fields: &[LdtkField],
+    links: &mut Vec<String>,
+    level: &LdtkLevel,
+    x: i32,
+    y: i32,
+) -> Result<u8, String> {
+    link_field(fields, links, level, x, y)?.ok_or_else(|| {
+        format!(
+            "{}: link name or switch number is required at ({}, {})",
+            level.identifier, x, y
+        )
+    })
+}
+
+/// LDtk のレベルを LevelChunk に変換します
+/// Aseprite のスライスから読み込んだ場合と同じく、チャンクの左上が (0, 0) になります
+pub fn ldtk_to_tilemap(level: &LdtkLevel) -> Result<LevelChunk, String> {
+    let layers = level.layer_instances.as_ref().ok_or_else(|| {
+        format!(
+            "{}: external level files are not supported",
+            level.identifier
+        )
+    })?;
+
+    let tiles = layers
+        .iter()
+        .find(|l| l.layer_type == "IntGrid" && l.identifier == "Tiles")
+        .ok_or_else(|| format!("{}: IntGrid layer 'Tiles' not found", level.identifier))?;
+
+    let mut chunk = LevelChunk::new(0, tiles.width, 0, tiles.height);
+
+    for y in 0..tiles.height {
+        for x in 0..tiles.width {
+            let value = tiles
+                .int_grid_csv
+                .get((y * tiles.width + x) as usize)
+                .copied()
+                .unwrap_or(0);
+            let (tile, biome) = match value {
+                0 => (Tile::Wall, Biome::SafeZone),
+                1 => (Tile::StoneTile, Biome::Dungeon),
+                2 => (Tile::StoneTile, Biome::SafeZone),
+                3 => (Tile::Water, Biome::Dungeon),
+                4 => (Tile::Lava, Biome::SafeZone),
+                5 => (Tile::Pit, Biome::SafeZone),
+                6 => (Tile::Ice, Biome::Dungeon),
+                7 => (Tile::CrackedWall, Biome::SafeZone),
+                _ => {
+                    return Err(format!(
+                        "{}: unknown IntGrid value {} at ({}, {})",
+                        level.identifier, value, x, y
+                    ))
+                }
+            };
+            chunk.set_tile(x, y, tile);
+            chunk.set_biome(x, y, biome);
+        }
+    }
+
+    let mut links: Vec<String> = Vec::new();
+
+    for layer in layers.iter().filter(|l| l.layer_type == "Entities") {
+        for entity in layer.entity_instances.iter() {
+            let [x, y] = entity.grid;
+            let fields = &entity.field_instances;
+            let game_entity = match entity.identifier.as_str() {
+                "EntryPoint" => {
+                    chunk.entry_points.push(Vec2::new(x as f32, y as f32));
+                    GameEntity::BrokenMagicCircle
+                }
+                "Chest" => match string_field(fields, "kind") {
+                    None | Some("Chest") => GameEntity::Chest,
+                    Some("Crate") => GameEntity::Crate,
+                    Some("CrateOrBarrel") => GameEntity::CrateOrBarrel,
+                    Some(kind) => {
+                        return Err(format!(
+                            "{}: unknown chest kind '{}' at ({}, {})",
+                            level.identifier, kind, x, y
+                        ))
+                    }
+                },
+                "MagicCircle" => match string_field(fields, "destination") {
+                    None | Some("NextLevel") => GameEntity::MagicCircle,
+                    Some("Home") => GameEntity::MagicCircleHome,
+                    Some("MultiplayArena") => GameEntity::MultiPlayArenaMagicCircle,
+                    Some("Exit") => {
+                        GameEntity::MagicCircleExit(number_field(fields, "exit", level, x, y)?)
+                    }
+                    Some(destination) => {
+                        return Err(format!(
+                            "{}: unknown magic circle destination '{}' at ({}, {})",
+                            level.identifier, destination, x, y
+                        ))
+                    }
+                },
+                "BookShelf" => GameEntity::BookShelf,
+                "StoneLantern" => GameEntity::StoneLantern,
+                "Spell" => GameEntity::Spell,
+                "Usage" => GameEntity::Usage,
+                "Routes" => GameEntity::Routes,
+                "HugeSlime" => GameEntity::HugeSlime,
+                "Rabbit" => GameEntity::Rabbit,
+                "Sandbug" => GameEntity::Sandbug,
+                "ShopDoor" => GameEntity::ShopDoor,
+                "Stash" => GameEntity::Stash,
+                "Door" => match string_field(fields, "lock") {
+                    None | Some("Unlocked") => GameEntity::Door(DoorLock::Unlocked),
+                    Some("Key") => GameEntity::Door(DoorLock::Key),
+                    Some("Boss") => GameEntity::Door(DoorLock::Boss),
+                    Some("Switch") => GameEntity::Door(DoorLock::Switch(required_link_field(
+                        fields, &mut links, level, x, y,
+                    )?)),
+                    Some(lock) => {
+                        return Err(format!(
+                            "{}: unknown door lock '{}' at ({}, {})",
+                            level.identifier, lock, x, y
+                        ))
+                    }
+                },
+                "Switch" => {
+                    GameEntity::DoorSwitch(required_link_field(fields, &mut links, level, x, y)?)
+                }
+                "PressurePlate" => GameEntity::Trap(TrapKind::PressurePlate(required_link_field(
+                    fields, &mut links, level, x, y,
+                )?)),
+                "Spikes" => GameEntity::Trap(TrapKind::Spikes),
+                "Turret" => {
+                    let aim = match string_field(fields, "aim") {
+                        Some("Up") => TurretAim::Up,
+                        None | Some("Down") => TurretAim::Down,
+                        Some("Left") => TurretAim::Left,
+                        Some("Right") => TurretAim::Right,
+                        Some("Player") => TurretAim::Player,
+                        Some(aim) => {
+                            return Err(format!(
+                                "{}: unknown turret aim '{}' at ({}, {})",
+                                level.identifier, aim, x, y
+                            ))
+                        }
+                    };
+                    let link = link_field(fields, &mut links, level, x, y)?;
+                    GameEntity::Trap(TrapKind::Turret(aim, link))
+                }
+                identifier => {
+                    return Err(format!(
+                        "{}: unknown entity '{}' at ({}, {})",
+                        level.identifier, identifier, x, y
+                    ))
+                }
+            };
+            chunk.entities.push((game_entity, x, y));
+        }
+    }
+
+    chunk.spawn_table = match string_field(&level.field_instances, "spawn_table") {
+        None | Some("Default") => SpawnTable::Default,
+        Some("Empty") => SpawnTable::Empty,
+        Some("Slimes") => SpawnTable::Slimes,
+        Some("Eyeballs") => SpawnTable::Eyeballs,
+        Some("Witches") => SpawnTable::Witches,
+        Some(table) => {
+            return Err(format!(
+                "{}: unknown spawn table '{}'",
+                level.identifier, table
+            ))
+        }
+    };
+
+    Ok(chunk)
+}
+
+#[derive(Default)]
+pub struct LdtkLoader;
+
+impl AssetLoader for LdtkLoader {
+    type Asset = LdtkProject;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtk"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::check::check_chunk;
+    use serde_json::json;
+
+    fn example() -> LevelChunk {
+        let project: LdtkProject =
+            serde_json::from_str(include_str!("../../assets/level.ldtk")).unwrap();
+        ldtk_to_tilemap(project.level("example").unwrap()).unwrap()
+    }
+
+    /// 3x3 のマスにエンティティを置いたレベルを作成します
+    fn level_with(tiles: Vec<i32>, entities: Vec<Value>, spawn_table: Value) -> LdtkLevel {
+        serde_json::from_value(json!({
+            "identifier": "test",
+            "fieldInstances": [{ "__identifier": "spawn_table", "__value": spawn_table }],
+            "layerInstances": [
+                {
+                    "__identifier": "Tiles",
+                    "__type": "IntGrid",
+                    "__cWid": 3,
+                    "__cHei": 3,
+                    "intGridCsv": tiles,
+                },
+                {
+                    "__identifier": "Entities",
+                    "__type": "Entities",
+                    "__cWid": 3,
+                    "__cHei": 3,
+                    "entityInstances": entities,
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn entity(identifier: &str, fields: Value) -> Value {
+        let fields: Vec<Value> = fields
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| json!({ "__identifier": k, "__value": v }))
+            .collect();
+        json!({ "__identifier": identifier, "__grid": [1, 1], "fieldInstances": fields })
+    }
+
+    fn convert_entity(identifier: &str, fields: Value) -> Result<GameEntity, String> {
+        let level = level_with(vec![1; 9], vec![entity(identifier, fields)], Value::Null);
+        ldtk_to_tilemap(&level).map(|chunk| chunk.entities[0].0)
+    }
+
+    #[test]
+    fn convert_tiles_and_biomes() {
+        let chunk = example();
+        assert_eq!((chunk.max_x, chunk.max_y), (10, 6));
+        for (x, y, tile, biome) in [
+            (0, 0, Tile::Wall, Biome::SafeZone),
+            (1, 1, Tile::StoneTile, Biome::SafeZone),
+            (4, 1, Tile::StoneTile, Biome::Dungeon),
+            (6, 1, Tile::Water, Biome::Dungeon),
+            (7, 1, Tile::Lava, Biome::SafeZone),
+            (8, 1, Tile::Ice, Biome::Dungeon),
+            (6, 4, Tile::Pit, Biome::SafeZone),
+            (4, 4, Tile::CrackedWall, Biome::SafeZone),
+        ] {
+            assert_eq!(chunk.get_tile(x, y), tile, "({}, {})", x, y);
+            assert_eq!(chunk.get_biome(x, y), biome, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn convert_entities_and_fields() {
+        let chunk = example();
+        assert_eq!(chunk.entry_points, vec![Vec2::new(1.0, 1.0)]);
+        assert_eq!(chunk.spawn_table, SpawnTable::Slimes);
+
+        let link = LINK_NUMBER_START as u8;
+        assert_eq!(
+            chunk.entities,
+            vec![
+                (GameEntity::BrokenMagicCircle, 1, 1),
+                (GameEntity::Crate, 2, 1),
+                (GameEntity::Chest, 3, 1),
+                (GameEntity::CrateOrBarrel, 1, 3),
+                (GameEntity::Stash, 2, 3),
+                (GameEntity::MagicCircle, 5, 2),
+                (GameEntity::MagicCircleHome, 6, 2),
+                (GameEntity::MultiPlayArenaMagicCircle, 7, 2),
+                (GameEntity::MagicCircleExit(1), 8, 2),
+                (GameEntity::Door(DoorLock::Switch(link)), 4, 3),
+                (GameEntity::DoorSwitch(link), 5, 3),
+                (GameEntity::Trap(TrapKind::PressurePlate(3)), 6, 3),
+                (
+                    GameEntity::Trap(TrapKind::Turret(TurretAim::Left, Some(link))),
+                    7,
+                    3
+                ),
+                (
+                    GameEntity::Trap(TrapKind::Turret(TurretAim::Down, None)),
+                    8,
+                    3
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn example_level_passes_check() {
+        assert_eq!(check_chunk(&example()), vec![]);
+    }
+
+    #[test]
+    fn assign_link_numbers_by_name() {
+        let level: LdtkLevel = serde_json::from_value(json!({
+            "identifier": "test",
+            "layerInstances": [
+                {
+                    "__identifier": "Tiles",
+                    "__type": "IntGrid",
+                    "__cWid": 3,
+                    "__cHei": 1,
+                    "intGridCsv": [1, 1, 1],
+                },
+                {
+                    "__identifier": "Entities",
+                    "__type": "Entities",
+                    "__cWid": 3,
+                    "__cHei": 1,
+                    "entityInstances": [
+                        entity("Switch", json!({ "link": "a" })),
+                        entity("Switch", json!({ "link": "b" })),
+                        entity("Switch", json!({ "link": "a" })),
+                    ],
+                },
+            ],
+        }))
+        .unwrap();
+        let chunk = ldtk_to_tilemap(&level).unwrap();
+        let numbers: Vec<GameEntity> = chunk.entities.iter().map(|(e, _, _)| *e).collect();
+        let a = LINK_NUMBER_START as u8;
+        assert_eq!(
+            numbers,
+            vec![
+                GameEntity::DoorSwitch(a),
+                GameEntity::DoorSwitch(a + 1),
+                GameEntity::DoorSwitch(a),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_field_values() {
+        assert_eq!(convert_entity("Chest", json!({})), Ok(GameEntity::Chest));
+        assert_eq!(
+            convert_entity("MagicCircle", json!({ "destination": null })),
+            Ok(GameEntity::MagicCircle)
+        );
+        assert_eq!(
+            convert_entity("Door", json!({})),
+            Ok(GameEntity::Door(DoorLock::Unlocked))
+        );
+        let level = level_with(vec![1; 9], vec![], Value::Null);
+        assert_eq!(
+            ldtk_to_tilemap(&level).unwrap().spawn_table,
+            SpawnTable::Default
+        );
+    }
+
+    #[test]
+    fn reject_unknown_values() {
+        for (identifier, fields) in [
+            ("Goblin", json!({})),
+            ("Chest", json!({ "kind": "Barrel" })),
+            ("MagicCircle", json!({ "destination": "Moon" })),
+            ("MagicCircle", json!({ "destination": "Exit" })),
+            ("MagicCircle", json!({ "destination": "Exit", "exit": 256 })),
+            ("Door", json!({ "lock": "Magic" })),
+            ("Door", json!({ "lock": "Switch" })),
+            ("Switch", json!({})),
+            ("PressurePlate", json!({ "switch": -1 })),
+            ("Turret", json!({ "aim": "Diagonal" })),
+        ] {
+            let result = convert_entity(identifier, fields.clone());
+            assert!(result.is_err(), "{} {}", identifier, fields);
+        }
+
+        let level = level_with(vec![1, 1, 1, 1, 8, 1, 1, 1, 1], vec![], Value::Null);
+        assert_eq!(
+            ldtk_to_tilemap(&level).err(),
+            Some("test: unknown IntGrid value 8 at (1, 1)".to_string())
+        );
+
+        let level = level_with(vec![1; 9], vec![], json!("Dragons"));
+        assert_eq!(
+            ldtk_to_tilemap(&level).err(),
+            Some("test: unknown spawn table 'Dragons'".to_string())
+        );
+    }
+
+    #[test]
+    fn reject_switch_numbers_reserved_for_links() {
+        let last = LINK_NUMBER_START - 1;
+        assert_eq!(
+            convert_entity("Switch", json!({ "switch": last })),
+            Ok(GameEntity::DoorSwitch(last as u8))
+        );
+        for identifier in ["Switch", "PressurePlate", "Turret"] {
+            assert_eq!(
+                convert_entity(identifier, json!({ "switch": LINK_NUMBER_START })).err(),
+                Some(format!(
+                    "test: switch number must be less than {} at (1, 1)",
+                    LINK_NUMBER_START
+                )),
+                "{}",
+                identifier
+            );
+        }
+        assert_eq!(
+            convert_entity("Door", json!({ "lock": "Switch", "switch": 200 })).err(),
+            Some(format!(
+                "test: switch number must be less than {} at (1, 1)",
+                LINK_NUMBER_START
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_layers() {
+        let level: LdtkLevel = serde_json::from_value(json!({
+            "identifier": "test",
+            "layerInstances": null,
+        }))
+        .unwrap();
+        assert!(ldtk_to_tilemap(&level).is_err());
+
+        let level: LdtkLevel = serde_json::from_value(json!({
+            "identifier": "test",
+            "layerInstances": [],
+        }))
+        .unwrap();
+        assert_eq!(
+            ldtk_to_tilemap(&level).err(),
+            Some("test: IntGrid layer 'Tiles' not found".to_string())
+        );
+    }
+}
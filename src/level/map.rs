@@ -1,9 +1,17 @@
-use crate::{entity::GameEntity, level::tile::Tile};
+use crate::{
+    entity::{
+        door::DoorLock,
+        trap::{TrapKind, TurretAim},
+        GameEntity,
+    },
+    level::{check::LevelIssue, tile::Tile},
+};
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use super::TILE_SIZE;
+use super::{GameLevel, TILE_SIZE};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Biome {
     /// モンスターがスポーンしないエリア
     SafeZone,
@@ -12,6 +20,43 @@ pub enum Biome {
     Dungeon,
 }
 
+/// レベルにランダムに出現する敵の組み合わせです
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum SpawnTable {
+    /// スライムとアイボール、深い階層では敵の魔法使いも出現します
+    #[default]
+    Default,
+
+    /// 敵は出現しません
+    Empty,
+
+    Slimes,
+
+    Eyeballs,
+
+    Witches,
+}
+
+impl SpawnTable {
+    /// 難易度による補正をする前の、スライム、アイボール、敵の魔法使いの出現数を返します
+    pub fn counts(&self, level: GameLevel) -> (u32, u32, u32) {
+        match self {
+            SpawnTable::Default => {
+                // 敵の魔法使いは図書館跡より深い階層から出現します
+                let witches = match level.depth() {
+                    Some(depth) if 2 <= depth => 2,
+                    _ => 0,
+                };
+                (10, 10, witches)
+            }
+            SpawnTable::Empty => (0, 0, 0),
+            SpawnTable::Slimes => (20, 0, 0),
+            SpawnTable::Eyeballs => (0, 20, 0),
+            SpawnTable::Witches => (0, 0, 5),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 struct LevelTileMapile {
     tile: Tile,
@@ -27,9 +72,31 @@ pub struct LevelChunk {
     pub max_y: i32,
     pub entities: Vec<(GameEntity, i32, i32)>,
     pub entry_points: Vec<Vec2>,
+    pub spawn_table: SpawnTable,
 }
 
 impl LevelChunk {
+    /// すべてのタイルが壁で埋められたチャンクを作成します
+    pub fn new(min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> Self {
+        let tiles = vec![
+            LevelTileMapile {
+                tile: Tile::Wall,
+                biome: Biome::SafeZone,
+            };
+            ((max_x - min_x) * (max_y - min_y)) as usize
+        ];
+        LevelChunk {
+            tiles,
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            entities: Vec::new(),
+            entry_points: Vec::new(),
+            spawn_table: SpawnTable::Default,
+        }
+    }
+
     pub fn get_tile(&self, x: i32, y: i32) -> Tile {
         if x < self.min_x || x >= self.max_x || y < self.min_y || y >= self.max_y {
             return Tile::Blank;
@@ -65,7 +132,6 @@ impl LevelChunk {
         return self.tiles[i].tile == tile;
     }
 
-    #[allow(dead_code)]
     pub fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
         if x < self.min_x || x >= self.max_x || y < self.min_y || y >= self.max_y {
             return;
@@ -75,29 +141,101 @@ impl LevelChunk {
         self.tiles[i].tile = tile;
     }
 
+    pub fn set_biome(&mut self, x: i32, y: i32, biome: Biome) {
+        if x < self.min_x || x >= self.max_x || y < self.min_y || y >= self.max_y {
+            return;
+        }
+        let w = self.max_x - self.min_x;
+        let i = ((y - self.min_y) * w + (x - self.min_x)) as usize;
+        self.tiles[i].biome = biome;
+    }
+
+    /// 指定した位置のタイルを歩いて通過できるかどうかを返します
     pub fn is_empty(&self, x: i32, y: i32) -> bool {
-        self.get_tile(x, y) == Tile::StoneTile
+        self.get_tile(x, y).is_walkable()
+    }
+
+    /// 指定した位置のタイルが壁として描画されるかどうかを返します
+    /// 範囲外は壁として扱います
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        if x < self.min_x || x >= self.max_x || y < self.min_y || y >= self.max_y {
+            return true;
+        }
+        self.get_tile(x, y).is_wall()
+    }
+
+    /// チャンクをタイル単位で平行移動します
+    /// タイルの座標はワールド全体で共通なので、複数のチャンクを重ならない位置に配置するために使います
+    pub fn translate(&mut self, dx: i32, dy: i32) {
+        self.min_x += dx;
+        self.max_x += dx;
+        self.min_y += dy;
+        self.max_y += dy;
+        for (_, x, y) in self.entities.iter_mut() {
+            *x += dx;
+            *y += dy;
+        }
+        for p in self.entry_points.iter_mut() {
+            p.x += dx as f32;
+            p.y += dy as f32;
+        }
+    }
+
+    /// ワールド全体で共通のタイルの座標を、チャンクの左上を原点とする座標に変換します
+    /// 同じレベルでも読み込まれるたびに異なる位置に配置されるので、レベルごとに記録する位置にはこの座標を使います
+    pub fn to_local(&self, x: i32, y: i32) -> (i32, i32) {
+        (x - self.min_x, y - self.min_y)
+    }
+
+    /// チャンクの左上の角のワールド座標(ピクセル)を返します
+    /// 他のプレイヤーと位置を送受信するときは、この点を原点とする座標に変換します
+    pub fn origin(&self) -> Vec2 {
+        Vec2::new(
+            self.min_x as f32 * TILE_SIZE,
+            self.min_y as f32 * -TILE_SIZE,
+        )
+    }
+
+    /// 指定した位置のタイルがチャンクの範囲内にあるかどうかを返します
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.min_x <= x && x < self.max_x && self.min_y <= y && y < self.max_y
+    }
+
+    /// 指定した位置からチャンクの範囲までの距離(タイル数)を返します
+    /// 範囲内であれば 0 です
+    pub fn distance(&self, x: i32, y: i32) -> i32 {
+        let dx = (self.min_x - x).max(x - (self.max_x - 1)).max(0);
+        let dy = (self.min_y - y).max(y - (self.max_y - 1)).max(0);
+        dx.max(dy)
+    }
+
+    /// 指定した位置のタイルが床として描画されるかどうかを返します
+    pub fn is_floor(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).is_floor()
     }
 }
 
+/// RGBA形式の画像の指定した範囲を、色に応じてタイルとエンティティに変換します
+/// 未知の色のピクセルは壁として扱い、その位置を問題の一覧として返します
 pub fn image_to_tilemap(
-    level_image: &Image,
+    data: &[u8],
+    texture_width: u32,
     min_x: i32,
     max_x: i32,
     min_y: i32,
     max_y: i32,
-) -> LevelChunk {
-    let texture_width = level_image.width();
+) -> (LevelChunk, Vec<LevelIssue>) {
+    let mut issues = Vec::new();
     let mut tiles: Vec<LevelTileMapile> = Vec::new();
     let mut entities = Vec::new();
     let mut entry_points = Vec::<Vec2>::new();
     for y in min_y..max_y {
         for x in min_x..max_x {
             let i = 4 * (y * texture_width as i32 + x) as usize;
-            let r = level_image.data[i + 0];
-            let g = level_image.data[i + 1];
-            let b = level_image.data[i + 2];
-            let a = level_image.data[i + 3];
+            let r = data[i + 0];
+            let g = data[i + 1];
+            let b = data[i + 2];
+            let a = data[i + 3];
 
             match (r, g, b, a) {
                 (203, 219, 252, 255) => {
@@ -224,6 +362,119 @@ pub fn image_to_tilemap(
                     });
                     entities.push((GameEntity::Sandbug, x, y));
                 }
+                (91, 110, 225, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::Water,
+                        biome: Biome::Dungeon,
+                    });
+                }
+                (172, 50, 50, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::Lava,
+                        biome: Biome::SafeZone,
+                    });
+                }
+                (0, 0, 0, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::Pit,
+                        biome: Biome::SafeZone,
+                    });
+                }
+                (95, 205, 228, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::Ice,
+                        biome: Biome::Dungeon,
+                    });
+                }
+                (105, 106, 106, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::CrackedWall,
+                        biome: Biome::SafeZone,
+                    });
+                }
+                (143, 86, 59, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Door(DoorLock::Unlocked), x, y));
+                }
+                (217, 160, 102, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Door(DoorLock::Key), x, y));
+                }
+                (138, 111, 48, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Door(DoorLock::Boss), x, y));
+                }
+                // スイッチとスイッチで開く扉は、青の値でスイッチの番号を指定します
+                (69, 40, id, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Door(DoorLock::Switch(id)), x, y));
+                }
+                (70, 40, id, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::DoorSwitch(id), x, y));
+                }
+                (71, 40, id, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::MagicCircleExit(id), x, y));
+                }
+                // 感圧板は青の値で連結する番号を指定します
+                // 番号は扉のスイッチと共通で、同じ番号の扉も開きます
+                (72, 40, id, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Trap(TrapKind::PressurePlate(id)), x, y));
+                }
+                (73, 40, _, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Trap(TrapKind::Spikes), x, y));
+                }
+                // 砲台は赤の値で向き(上下左右、プレイヤー)を指定します
+                // 緑が 40 なら青の値の番号の感圧板と連結され、41 なら一定の間隔で撃ち続けます
+                (r @ 80..=84, g @ (40 | 41), id, 255) => {
+                    let aim = match r {
+                        80 => TurretAim::Up,
+                        81 => TurretAim::Down,
+                        82 => TurretAim::Left,
+                        83 => TurretAim::Right,
+                        _ => TurretAim::Player,
+                    };
+                    let link = if g == 40 { Some(id) } else { None };
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Trap(TrapKind::Turret(aim, link)), x, y));
+                }
+                (74, 40, _, 255) => {
+                    tiles.push(LevelTileMapile {
+                        tile: Tile::StoneTile,
+                        biome: Biome::SafeZone,
+                    });
+                    entities.push((GameEntity::Stash, x, y));
+                }
                 (197, 255, 142, 255) => {
                     tiles.push(LevelTileMapile {
                         tile: Tile::StoneTile,
@@ -233,15 +484,19 @@ pub fn image_to_tilemap(
                 }
                 _ => {
                     tiles.push(LevelTileMapile {
-                        tile: Tile::Blank,
+                        tile: Tile::Wall,
                         biome: Biome::SafeZone,
                     });
-                    panic!("Unknown color: ({}, {}, {}, {})", r, g, b, a);
+                    issues.push(LevelIssue::UnknownColor {
+                        x,
+                        y,
+                        color: [r, g, b, a],
+                    });
                 }
             }
         }
     }
-    return LevelChunk {
+    let chunk = LevelChunk {
         tiles,
         min_x,
         max_x,
@@ -249,7 +504,9 @@ pub fn image_to_tilemap(
         max_y,
         entities,
         entry_points,
+        spawn_table: SpawnTable::Default,
     };
+    (chunk, issues)
 }
 
 pub fn image_to_spawn_tiles(tilemap: &LevelChunk) -> Vec<(i32, i32)> {
@@ -266,3 +523,18 @@ pub fn image_to_spawn_tiles(tilemap: &LevelChunk) -> Vec<(i32, i32)> {
     }
     tiles
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_local_positions_after_translate() {
+        let mut chunk = LevelChunk::new(0, 10, 0, 8);
+        assert_eq!(chunk.to_local(3, 4), (3, 4));
+        chunk.translate(320, 0);
+        assert_eq!(chunk.to_local(323, 4), (3, 4));
+        chunk.translate(-320 * 2, 16);
+        assert_eq!(chunk.to_local(-317, 20), (3, 4));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::asset::GameAssets;
+use crate::language::Dict;
+use crate::level::map::SpawnTable;
+use crate::level::GameLevel;
+use crate::states::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+/// レベルの地形をどこから読み込むかを表します
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LevelLayout {
+    /// levelN という名前のレベルを読み込みます
+    /// level.ldtk に同じ名前のレベルがあればそちらを、なければ level.aseprite のスライスを使います
+    Slice(i32),
+
+    /// シードから自動生成します
+    Generated(u64),
+}
+
+/// ひとつのレベルの名前や雰囲気、敵の出現などの設定です
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelMeta {
+    pub name: Dict<String>,
+
+    /// レベルで再生するBGMのパスです
+    /// レベルに入るたびにこの中からランダムに選ばれます
+    pub bgm: Vec<String>,
+
+    /// 光が当たっていない部分の明るさです
+    #[serde(default = "default_brightness")]
+    pub brightness: f32,
+
+    /// 光が当たっていない部分の色です
+    #[serde(default = "default_ambient_color")]
+    pub ambient_color: [f32; 3],
+
+    /// レベルにランダムに出現する敵の組み合わせです
+    /// level.ldtk のレベルの spawn_table フィールドが指定されていれば、そちらが優先されます
+    #[serde(default)]
+    pub spawn_table: SpawnTable,
+
+    /// 安全地帯では敵がランダムに出現しません
+    #[serde(default)]
+    pub safe_zone: bool,
+
+    /// 周回をまたいでアイテムを預けておける保管箱をこのレベルに置きます
+    /// レベルのデータに保管箱が配置されていなければ、出現位置の近くに置かれます
+    #[serde(default)]
+    pub stash: bool,
+
+    /// 魔法陣で次のレベルへ進んだときの行き先の深度です
+    /// 省略すると、ひとつ深い深度へ進みます
+    #[serde(default)]
+    pub next: Option<i32>,
+
+    /// 次のレベル以外へ向かう出口です
+    /// レベルのデータの Exit の魔法陣が、番号でこの出口を指定します
+    /// 自動生成されたレベルでは、それぞれの出口が一定の確率で配置されます
+    #[serde(default)]
+    pub exits: Vec<LevelExit>,
+}
+
+/// 本来の順路から分岐する出口の行き先です
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelExit {
+    /// 行き先の分岐のレベルの id です
+    /// 省略すると本来の順路のレベルへ向かいます
+    #[serde(default)]
+    pub branch: Option<String>,
+
+    /// 行き先のレベルが、いまのレベルよりいくつ深いかを表します
+    #[serde(default = "default_advance")]
+    pub advance: i32,
+}
+
+fn default_advance() -> i32 {
+    1
+}
+
+/// 分岐のレベルの地形をどこから読み込むかを表します
+#[derive(Deserialize, Debug, Clone)]
+pub enum BranchLayout {
+    /// level.ldtk のレベルか level.aseprite のスライスを名前で指定して読み込みます
+    Slice(String),
+
+    /// 部屋と通路で構成されたダンジョンを自動生成します
+    Dungeon {
+        #[serde(default)]
+        shop: bool,
+    },
+
+    /// 宝箱の並んだ小さな部屋を自動生成します
+    Vault,
+}
+
+/// 本来の順路から外れた分岐のレベルの設定です
+#[derive(Deserialize, Debug, Clone)]
+pub struct BranchMeta {
+    /// 出口の branch から参照される識別子です
+    pub id: String,
+
+    pub layout: BranchLayout,
+
+    #[serde(flatten)]
+    pub meta: LevelMeta,
+}
+
+fn default_brightness() -> f32 {
+    0.01
+}
+
+fn default_ambient_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+impl LevelMeta {
+    pub fn ambient_color(&self) -> Color {
+        let [r, g, b] = self.ambient_color;
+        Color::srgb(r, g, b)
+    }
+
+    /// BGMのプレイリストからランダムにひとつ選びます
+    pub fn choose_bgm(&self, asset_server: &AssetServer) -> Option<Handle<AudioSource>> {
+        self.bgm
+            .choose(&mut rand::thread_rng())
+            .map(|path| asset_server.load(path))
+    }
+}
+
+/// すべてのレベルの設定です
+/// assets/level.meta.json から読み込まれ、読み込みが完了するとリソースとしても登録されます
+/// レベルを追加するときは、levels の末尾に設定を追加し、
+/// level.aseprite か level.ldtk に同じ番号の levelN を作成します
+#[derive(Asset, Resource, TypePath, Deserialize, Debug, Clone)]
+pub struct LevelMetadata {
+    /// 手作りのレベルの設定です
+    /// 配列の位置が深度とレベルの番号(levelN の N)に対応します
+    pub levels: Vec<LevelMeta>,
+
+    /// 自動生成されたレベルの設定です
+    pub generated: LevelMeta,
+
+    /// マルチプレイヤーのアリーナの設定です
+    pub arena: LevelMeta,
+
+    /// 分岐のレベルの設定です
+    #[serde(default)]
+    pub branches: Vec<BranchMeta>,
+}
+
+impl LevelMetadata {
+    /// 手作りのレベルの数です
+    /// これを越えた深度はエンドレスモードになります
+    pub fn handcrafted_levels(&self) -> i32 {
+        self.levels.len() as i32
+    }
+
+    /// 深度に対応するレベルのレイアウトを返します
+    /// 手作りのレベルを越えた深度では、拠点を除いた手作りのレイアウトと自動生成のレイアウトから、
+    /// 深度ごとに決まったものを選びます
+    pub fn layout(&self, depth: i32) -> LevelLayout {
+        let levels = self.handcrafted_levels();
+        if depth < levels {
+            LevelLayout::Slice(depth)
+        } else {
+            let mut rng = StdRng::seed_from_u64(depth as u64);
+            if levels <= 1 || rng.gen_bool(0.5) {
+                LevelLayout::Generated(rng.gen())
+            } else {
+                LevelLayout::Slice(rng.gen_range(1..levels))
+            }
+        }
+    }
+
+    pub fn get(&self, level: GameLevel) -> &LevelMeta {
+        match level {
+            GameLevel::Level(depth) => match self.layout(depth) {
+                LevelLayout::Slice(layout) => &self.levels[layout as usize],
+                LevelLayout::Generated(_) => &self.generated,
+            },
+            GameLevel::Branch(_, branch) => &self.branches[branch as usize].meta,
+            GameLevel::MultiPlayArena => &self.arena,
+        }
+    }
+
+    /// レベルの出口の行き先を返します
+    /// 存在しない出口や分岐を指定した場合は None を返します
+    pub fn exit(&self, level: GameLevel, exit: u8) -> Option<GameLevel> {
+        let exit = self.get(level).exits.get(exit as usize)?;
+        let depth = level.depth().unwrap_or(0) + exit.advance;
+        match &exit.branch {
+            None => Some(GameLevel::Level(depth)),
+            Some(id) => self
+                .branches
+                .iter()
+                .position(|b| &b.id == id)
+                .map(|i| GameLevel::Branch(depth, i as u8)),
+        }
+    }
+
+    /// 魔法陣で次のレベルへ進んだときの行き先を返します
+    /// 分岐のレベルからは、本来の順路のひとつ深いレベルへ戻ります
+    pub fn next(&self, level: GameLevel) -> GameLevel {
+        let default = match level {
+            GameLevel::Level(depth) | GameLevel::Branch(depth, _) => depth + 1,
+            GameLevel::MultiPlayArena => 1,
+        };
+        GameLevel::Level(self.get(level).next.unwrap_or(default))
+    }
+}
+
+/// 読み込んだレベルの設定をリソースとして登録します
+fn setup_level_metadata(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    metadata_assets: Res<Assets<LevelMetadata>>,
+) {
+    let metadata = metadata_assets
+        .get(&assets.level_meta)
+        .expect("level metadata is not loaded")
+        .clone();
+    commands.insert_resource(metadata);
+}
+
+#[derive(Default)]
+pub struct LevelMetadataLoader;
+
+impl AssetLoader for LevelMetadataLoader {
+    type Asset = LevelMetadata;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["meta.json"]
+    }
+}
+
+pub struct LevelMetadataPlugin;
+
+impl Plugin for LevelMetadataPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelMetadata>();
+        app.init_asset_loader::<LevelMetadataLoader>();
+        app.add_systems(OnExit(GameState::Setup), setup_level_metadata);
+    }
+}
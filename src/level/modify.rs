@@ -0,0 +1,130 @@
+use crate::asset::GameAssets;
+use crate::controller::player::Player;
+use crate::controller::remote::{send_remote_message, RemoteMessage};
+use crate::entity::actor::Actor;
+use crate::level::spawn_world_tile;
+use crate::level::streaming::LevelWorld;
+use crate::level::tile::{Tile, WorldTile};
+use crate::level::wall::{get_collider_block, spawn_block_wall_collisions, WallCollider};
+use crate::level::CurrentLevel;
+use crate::states::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::plugin::PhysicsSet;
+use bevy_simple_websocket::{ClientMessage, ReadyState, WebSocketState};
+use std::collections::HashSet;
+
+/// ゲームの実行中にタイルを変更します
+/// 壁の破壊や橋の建設などに使います
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SetTileEvent {
+    pub x: i32,
+    pub y: i32,
+    pub tile: Tile,
+
+    /// 他のプレイヤーから通知された変更であれば true です
+    /// その場合は、変更を再び通知することはありません
+    pub remote: bool,
+}
+
+/// タイルの変更を LevelChunk に反映し、周囲のスプライトと衝突形状を作り直します
+/// 壁の見た目や天井は周囲8マスのタイルに依存するため、変更したタイルの周囲のスプライトも作り直します
+/// スプライトと衝突形状が生成されていない区画では、チャンクの変更だけを行い、区画の読み込み時に反映されます
+fn apply_tile_changes(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut reader: EventReader<SetTileEvent>,
+    mut current: ResMut<CurrentLevel>,
+    world: Res<LevelWorld>,
+    tile_query: Query<(Entity, &WorldTile)>,
+    collider_query: Query<(Entity, &WallCollider)>,
+    player_query: Query<&Actor, With<Player>>,
+    websocket: Res<WebSocketState>,
+    mut remote_writer: EventWriter<ClientMessage>,
+) {
+    if reader.is_empty() {
+        return;
+    }
+
+    let Some(level) = current.level else {
+        return;
+    };
+    let Some(chunk) = current.chunk.as_mut() else {
+        return;
+    };
+
+    let online = websocket.ready_state == ReadyState::OPEN;
+
+    let mut tiles: HashSet<(i32, i32)> = HashSet::new();
+    let mut blocks: HashSet<(i32, i32)> = HashSet::new();
+
+    for event in reader.read() {
+        let SetTileEvent { x, y, tile, remote } = *event;
+        if x < chunk.min_x || chunk.max_x <= x || y < chunk.min_y || chunk.max_y <= y {
+            continue;
+        }
+        if chunk.get_tile(x, y) == tile {
+            continue;
+        }
+
+        chunk.set_tile(x, y, tile);
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if world.is_loaded(get_collider_block(x + dx, y + dy)) {
+                    tiles.insert((x + dx, y + dy));
+                }
+            }
+        }
+        if world.is_loaded(get_collider_block(x, y)) {
+            blocks.insert(get_collider_block(x, y));
+        }
+
+        if !remote {
+            if let Ok(actor) = player_query.get_single() {
+                send_remote_message(
+                    &mut remote_writer,
+                    online,
+                    &RemoteMessage::SetTile {
+                        sender: actor.uuid,
+                        level,
+                        x: x - chunk.min_x,
+                        y: y - chunk.min_y,
+                        tile,
+                    },
+                );
+            }
+        }
+    }
+
+    for (entity, world_tile) in tile_query.iter() {
+        if tiles.contains(&(world_tile.x, world_tile.y)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for (x, y) in tiles.iter() {
+        spawn_world_tile(&mut commands, &assets, chunk, *x, *y);
+    }
+
+    for (entity, collider) in collider_query.iter() {
+        if blocks.contains(&(collider.block_x, collider.block_y)) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for (block_x, block_y) in blocks.iter() {
+        spawn_block_wall_collisions(&mut commands, chunk, *block_x, *block_y);
+    }
+}
+
+pub struct TileModifyPlugin;
+
+impl Plugin for TileModifyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SetTileEvent>();
+        app.add_systems(
+            FixedUpdate,
+            apply_tile_changes
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use crate::asset::GameAssets;
+use crate::camera::GameCamera;
+use crate::codex::locked_items;
+use crate::constant::*;
+use crate::controller::player::Player;
+use crate::controller::remote::RemotePlayer;
+use crate::controller::servant::{Servant, ServantOrder};
+use crate::difficulty::DifficultyCurve;
+use crate::entity::actor::Actor;
+use crate::entity::life::Life;
+use crate::hud::life_bar::LifeBarResource;
+use crate::level::ldtk::LdtkProject;
+use crate::level::map::LevelChunk;
+use crate::level::meta::LevelMetadata;
+use crate::level::tile::WorldTile;
+use crate::level::wall::{
+    get_collider_block, spawn_block_wall_collisions, WallCollider, COLLIDER_BLOCK_SIZE,
+};
+use crate::level::{spawn_level, spawn_world_tile, CurrentLevel, GameLevel};
+use crate::player_state::PlayerState;
+use crate::save::SaveSlots;
+use crate::states::GameState;
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::Aseprite;
+use bevy_rapier2d::prelude::*;
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+/// レベルごとのチャンクを配置する間隔(タイル数)です
+/// level.aseprite の大きさ(256)より大きく、衝突形状の区画の大きさの倍数でなければなりません
+pub const CHUNK_STRIDE: i32 = 320;
+
+/// 同時に保持しておくレベルのチャンクの最大数です
+/// これを超えると、もっとも長く離れていたレベルのチャンクと、そのレベルが所有するエンティティが破棄されます
+/// チャンクはこの数の配置場所を使い回すので、ワールド座標は CHUNK_STRIDE * MAX_RETAINED_LEVELS を超えません
+pub const MAX_RETAINED_LEVELS: usize = 4;
+
+/// プレイヤーからこの距離(タイル数)以内にある区画のタイルと衝突形状を生成します
+/// カメラをもっとも引いたときに画面に映る範囲より大きくしておきます
+const STREAMING_DISTANCE: i32 = 48;
+
+/// フェードを伴わない移動で、使い魔を出現位置の周囲に散らばらせる距離です
+const SERVANT_SCATTERING: f32 = 16.0;
+
+/// これまでに読み込んだレベルのチャンクと、その配置場所です
+/// プレイヤーのいるレベルのチャンクは CurrentLevel が持ち、それ以外のチャンクをここで保持します
+/// チャンクのタイルの変更や、チャンクにいる敵の状態は、プレイヤーが離れても保存されます
+///
+/// タイルのスプライトと壁の衝突形状は、プレイヤーのいるチャンクのうち、
+/// プレイヤーの周囲にある区画(衝突形状の区画と同じ大きさ)の分だけが生成されます
+#[derive(Resource, Default)]
+pub struct LevelWorld {
+    /// プレイヤーのいないレベルのチャンクです
+    /// プレイヤーが離れた順に並んでいます
+    chunks: Vec<(GameLevel, LevelChunk)>,
+
+    /// 配置場所ごとに、そこに置かれているレベルです
+    slots: [Option<GameLevel>; MAX_RETAINED_LEVELS],
+
+    /// タイルと衝突形状が生成されている区画です
+    loaded: HashSet<(i32, i32)>,
+
+    /// 破棄されたが、所有するエンティティがまだ削除されていないレベルです
+    evicted: Vec<GameLevel>,
+}
+
+impl LevelWorld {
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.slots = default();
+        self.loaded.clear();
+        self.evicted.clear();
+    }
+
+    /// レベルのチャンクを配置するワールド上の位置(タイル単位)を割り当てます
+    /// 空いている配置場所がなければ、もっとも長く離れていたレベルのチャンクを破棄してその場所を使います
+    pub fn allocate(&mut self, level: GameLevel) -> (i32, i32) {
+        let slot = match self.slots.iter().position(|s| s.is_none()) {
+            Some(slot) => slot,
+            None => {
+                let (evicted, _) = self.chunks.remove(0);
+                info!("evict chunk {:?}", evicted);
+                self.evicted.push(evicted);
+                self.slots
+                    .iter()
+                    .position(|s| *s == Some(evicted))
+                    .expect("evicted level has no slot")
+            }
+        };
+        self.slots[slot] = Some(level);
+        (slot as i32 * CHUNK_STRIDE, 0)
+    }
+
+    /// 指定した区画のタイルと衝突形状が生成されているかどうかを返します
+    pub fn is_loaded(&self, block: (i32, i32)) -> bool {
+        self.loaded.contains(&block)
+    }
+
+    fn take(&mut self, level: GameLevel) -> Option<LevelChunk> {
+        let index = self.chunks.iter().position(|(l, _)| *l == level)?;
+        Some(self.chunks.remove(index).1)
+    }
+
+    /// 指定した位置のタイルを含むチャンクのレベルを返します
+    fn level_at(&self, current: &CurrentLevel, x: i32, y: i32) -> Option<GameLevel> {
+        self.chunks
+            .iter()
+            .map(|(l, c)| (*l, c))
+            .chain(current.level.zip(current.chunk.as_ref()))
+            .find(|(_, c)| c.contains(x, y))
+            .map(|(l, _)| l)
+    }
+}
+
+/// エンティティを所有するレベルです
+/// レベルのチャンクが破棄されると、そのレベルが所有するエンティティも削除されます
+/// 生成されたときの位置にあるチャンクのレベルが自動的に設定されます
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkOwner(pub GameLevel);
+
+/// タイルと衝突形状が生成されていない区画にいるアクターです
+/// 物理演算が無効になり、非表示になり、呪文を詠唱しなくなります
+/// プレイヤーが区画に近づくと、そのままの状態で再開します
+#[derive(Component)]
+pub struct Dormant;
+
+/// フェードを伴わずに別のレベルへ移動します
+/// 移動先のレベルのチャンクがまだなければ生成し、プレイヤーとその使い魔を出現位置に移動します
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SeamlessWarpEvent {
+    pub level: GameLevel,
+}
+
+/// フェードを伴わずに移動できるレベルの組み合わせかどうかを返します
+/// マルチプレイヤーのアリーナは他のプレイヤーと同期するため、常にフェードを伴って移動します
+pub fn is_seamless(from: GameLevel, to: GameLevel) -> bool {
+    from != GameLevel::MultiPlayArena && to != GameLevel::MultiPlayArena
+}
+
+fn seamless_warp(
+    mut commands: Commands,
+    mut reader: EventReader<SeamlessWarpEvent>,
+    level_aseprites: Res<Assets<Aseprite>>,
+    images: Res<Assets<Image>>,
+    ldtk_projects: Res<Assets<LdtkProject>>,
+    assets: Res<GameAssets>,
+    life_bar_res: Res<LifeBarResource>,
+    curve: Res<DifficultyCurve>,
+    metadata: Res<LevelMetadata>,
+    mut current: ResMut<CurrentLevel>,
+    mut world: ResMut<LevelWorld>,
+    mut player_query: Query<(Entity, &Player, &Actor, &Life, &mut Transform)>,
+    mut servant_query: Query<
+        (Entity, &mut Servant, &mut Transform),
+        (Without<Player>, Without<Camera2d>),
+    >,
+    mut camera_query: Query<(&mut GameCamera, &mut Transform), (With<Camera2d>, Without<Player>)>,
+    mut slots: ResMut<SaveSlots>,
+) {
+    for SeamlessWarpEvent { level } in reader.read().copied() {
+        if current.level != Some(level) {
+            let chunk = match world.take(level) {
+                Some(chunk) => chunk,
+                None => {
+                    let origin = world.allocate(level);
+                    spawn_level(
+                        &mut commands,
+                        &level_aseprites,
+                        &images,
+                        &ldtk_projects,
+                        &assets,
+                        &life_bar_res,
+                        &metadata,
+                        level,
+                        origin,
+                        &curve.at(level).with_locked(locked_items(slots.current())),
+                    )
+                }
+            };
+            if let (Some(previous), Some(previous_chunk)) = (current.level, current.chunk.take()) {
+                world.chunks.push((previous, previous_chunk));
+            }
+            info!("entered chunk {:?}", level);
+            current.level = Some(level);
+            current.chunk = Some(chunk);
+        }
+
+        let Some(entry_point) = current
+            .chunk
+            .as_ref()
+            .and_then(|c| c.entry_points.choose(&mut rand::thread_rng()))
+            .copied()
+        else {
+            warn!("no entry point in {:?}", level);
+            continue;
+        };
+        let position = Vec2::new(
+            TILE_SIZE * entry_point.x + TILE_HALF,
+            -TILE_SIZE * entry_point.y - TILE_HALF,
+        );
+
+        if let Ok((player_entity, _, _, _, mut player)) = player_query.get_single_mut() {
+            player.translation.x = position.x;
+            player.translation.y = position.y;
+
+            // 使い魔はプレイヤーと一緒に移動し、移動先のレベルに所有されます
+            for (entity, mut servant, mut transform) in servant_query.iter_mut() {
+                if servant.master == player_entity {
+                    let offset =
+                        Vec2::new(rand::random::<f32>() - 0.5, rand::random::<f32>() - 0.5)
+                            * SERVANT_SCATTERING;
+                    transform.translation.x = position.x + offset.x;
+                    transform.translation.y = position.y + offset.y;
+                    servant.order = ServantOrder::Follow;
+                    commands.entity(entity).insert(ChunkOwner(level));
+                }
+            }
+        }
+        if let Ok((mut game_camera, mut camera)) = camera_query.get_single_mut() {
+            game_camera.x = position.x;
+            game_camera.y = position.y;
+            camera.translation.x = position.x;
+            camera.translation.y = position.y;
+        }
+
+        // 到達した最も深い深度を記録
+        if let Some(depth) = level.depth() {
+            if slots.current().best_depth < depth {
+                slots.current_mut().best_depth = depth;
+            }
+        }
+
+        current.next_level = level;
+        current.next_state = PlayerState::from(
+            player_query
+                .get_single()
+                .map(|(_, player, actor, life, _)| (player, actor, life)),
+            &slots.current().player_name,
+        );
+        current.visit(level);
+    }
+}
+
+/// 破棄されたレベルが所有するエンティティを削除します
+fn despawn_evicted_chunks(
+    mut commands: Commands,
+    mut world: ResMut<LevelWorld>,
+    query: Query<(Entity, &ChunkOwner)>,
+) {
+    if world.evicted.is_empty() {
+        return;
+    }
+    for (entity, owner) in query.iter() {
+        if world.evicted.contains(&owner.0) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    world.evicted.clear();
+}
+
+/// プレイヤーの位置に応じて、プレイヤーのいるチャンクの区画ごとにタイルと衝突形状を生成または削除し、
+/// 生成されていない区画にいるアクターを休止させます
+fn stream_chunks(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    current: Res<CurrentLevel>,
+    mut world: ResMut<LevelWorld>,
+    player_query: Query<&Transform, With<Player>>,
+    tile_query: Query<(Entity, &WorldTile)>,
+    collider_query: Query<(Entity, &WallCollider)>,
+    actor_query: Query<
+        (Entity, &Transform, Option<&Dormant>),
+        (With<Actor>, Without<Player>, Without<RemotePlayer>),
+    >,
+) {
+    let Ok(player) = player_query.get_single() else {
+        return;
+    };
+    let Some(chunk) = current.chunk.as_ref() else {
+        return;
+    };
+    let px = (player.translation.x / TILE_SIZE).floor() as i32;
+    let py = (-player.translation.y / TILE_SIZE).floor() as i32;
+
+    // プレイヤーの周囲の区画のうち、チャンクと重なるものを読み込みます
+    let (min_block_x, min_block_y) = get_collider_block(
+        chunk.min_x.max(px - STREAMING_DISTANCE),
+        chunk.min_y.max(py - STREAMING_DISTANCE),
+    );
+    let (max_block_x, max_block_y) = get_collider_block(
+        (chunk.max_x - 1).min(px + STREAMING_DISTANCE),
+        (chunk.max_y - 1).min(py + STREAMING_DISTANCE),
+    );
+    let mut near: HashSet<(i32, i32)> = HashSet::new();
+    for block_y in min_block_y..=max_block_y {
+        for block_x in min_block_x..=max_block_x {
+            near.insert((block_x, block_y));
+        }
+    }
+
+    for block in near.difference(&world.loaded) {
+        spawn_block(&mut commands, &assets, chunk, *block);
+    }
+
+    let unloading: HashSet<(i32, i32)> = world.loaded.difference(&near).copied().collect();
+    if !unloading.is_empty() {
+        for (entity, tile) in tile_query.iter() {
+            if unloading.contains(&get_collider_block(tile.x, tile.y)) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        for (entity, collider) in collider_query.iter() {
+            if unloading.contains(&(collider.block_x, collider.block_y)) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    world.loaded = near;
+
+    for (entity, transform, dormant) in actor_query.iter() {
+        let x = (transform.translation.x / TILE_SIZE).floor() as i32;
+        let y = (-transform.translation.y / TILE_SIZE).floor() as i32;
+        let active = world.is_loaded(get_collider_block(x, y));
+        match (active, dormant.is_some()) {
+            (true, true) => {
+                commands
+                    .entity(entity)
+                    .remove::<(Dormant, RigidBodyDisabled)>()
+                    .insert(Visibility::Inherited);
+            }
+            (false, false) => {
+                commands
+                    .entity(entity)
+                    .insert((Dormant, RigidBodyDisabled, Visibility::Hidden));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// ひとつの区画のタイルのスプライトと壁の衝突形状を生成します
+fn spawn_block(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    chunk: &LevelChunk,
+    (block_x, block_y): (i32, i32),
+) {
+    let min_x = (block_x * COLLIDER_BLOCK_SIZE).max(chunk.min_x);
+    let min_y = (block_y * COLLIDER_BLOCK_SIZE).max(chunk.min_y);
+    let max_x = ((block_x + 1) * COLLIDER_BLOCK_SIZE).min(chunk.max_x);
+    let max_y = ((block_y + 1) * COLLIDER_BLOCK_SIZE).min(chunk.max_y);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            spawn_world_tile(commands, assets, chunk, x, y);
+        }
+    }
+    spawn_block_wall_collisions(commands, chunk, block_x, block_y);
+}
+
+/// 新しく生成されたエンティティに、その位置にあるチャンクのレベルを所有者として設定します
+/// プレイヤー、リモートプレイヤー、UIはどのレベルにも所有されません
+fn assign_chunk_owner(
+    mut commands: Commands,
+    current: Res<CurrentLevel>,
+    world: Res<LevelWorld>,
+    query: Query<
+        (Entity, &Transform),
+        (
+            Added<StateScoped<GameState>>,
+            Without<ChunkOwner>,
+            Without<Player>,
+            Without<RemotePlayer>,
+            Without<Node>,
+            Without<Camera2d>,
+        ),
+    >,
+) {
+    for (entity, transform) in query.iter() {
+        let x = (transform.translation.x / TILE_SIZE).floor() as i32;
+        let y = (-transform.translation.y / TILE_SIZE).floor() as i32;
+        if let Some(level) = world.level_at(&current, x, y) {
+            commands.entity(entity).insert(ChunkOwner(level));
+        }
+    }
+}
+
+pub struct LevelStreamingPlugin;
+
+impl Plugin for LevelStreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelWorld>();
+        app.add_event::<SeamlessWarpEvent>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                seamless_warp,
+                despawn_evicted_chunks,
+                stream_chunks,
+                assign_chunk_owner,
+            )
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_slots_of_evicted_levels() {
+        let mut world = LevelWorld::default();
+        let mut origins = Vec::new();
+        for depth in 0..MAX_RETAINED_LEVELS as i32 {
+            let level = GameLevel::Level(depth);
+            origins.push(world.allocate(level));
+            world.chunks.push((level, LevelChunk::new(0, 1, 0, 1)));
+        }
+        assert!(world.evicted.is_empty());
+
+        // もっとも長く離れていたレベルを破棄し、その配置場所を使います
+        let origin = world.allocate(GameLevel::Level(100));
+        assert_eq!(origin, origins[0]);
+        assert_eq!(world.evicted, vec![GameLevel::Level(0)]);
+        assert!(world.take(GameLevel::Level(0)).is_none());
+
+        // ワールド座標は深度によらず一定の範囲に収まります
+        for (x, y) in origins.iter() {
+            assert!(*x < CHUNK_STRIDE * MAX_RETAINED_LEVELS as i32);
+            assert_eq!(*y, 0);
+        }
+    }
+
+    #[test]
+    fn clear_releases_all_slots() {
+        let mut world = LevelWorld::default();
+        world.allocate(GameLevel::Level(5));
+        world.loaded.insert((0, 0));
+        world.clear();
+        assert_eq!(world.allocate(GameLevel::Level(6)), (0, 0));
+        assert!(!world.is_loaded((0, 0)));
+    }
+}
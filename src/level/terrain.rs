@@ -0,0 +1,173 @@
+use crate::constant::*;
+use crate::controller::remote::RemotePlayer;
+use crate::entity::actor::Actor;
+use crate::entity::damege::SpawnDamageNumber;
+use crate::entity::life::Life;
+use crate::level::tile::Tile;
+use crate::level::CurrentLevel;
+use crate::se::{SEEvent, SE};
+use crate::set::GameSet;
+use crate::states::GameState;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+/// 燃焼状態が続くフレーム数
+/// 溶岩の上にいる間は、この値にリセットされ続けます
+const BURNING_DURATION: u32 = 120;
+
+/// 燃焼状態のダメージの間隔(フレーム数)
+const BURNING_INTERVAL: u32 = 20;
+
+const BURNING_DAMAGE: i32 = 2;
+
+const PIT_DAMAGE: i32 = 10;
+
+/// 水の上では減衰率がこの倍率になり、移動が遅くなります
+const WATER_DAMPING_SCALE: f32 = 2.0;
+
+/// 氷の上では減衰率がこの倍率になり、止まりにくくなります
+const ICE_DAMPING_SCALE: f32 = 0.1;
+
+/// 穴に落ちないアクターです
+#[derive(Component)]
+pub struct Flying;
+
+/// 燃焼状態のアクターです
+/// 一定の間隔でダメージを受け続け、水に入ると解除されます
+#[derive(Component)]
+pub struct Burning {
+    remaining: u32,
+    elapsed: u32,
+}
+
+/// 地形の効果を受けるアクターの状態です
+#[derive(Component)]
+pub struct Footing {
+    /// 地形による補正をする前の減衰率
+    base_linear_damping: f32,
+
+    /// 最後に立っていた安全なタイルの中心
+    /// 穴に落ちたときはここに戻されます
+    safe_position: Vec2,
+}
+
+/// 新しく生成されたアクターに地形の効果を受けるための状態を追加します
+/// リモートプレイヤーの位置や体力は送信元で処理されるため対象外です
+fn insert_footing(
+    mut commands: Commands,
+    query: Query<(Entity, &Damping, &Transform), (Added<Actor>, Without<RemotePlayer>)>,
+) {
+    for (entity, damping, transform) in query.iter() {
+        commands.entity(entity).insert(Footing {
+            base_linear_damping: damping.linear_damping,
+            safe_position: transform.translation.truncate(),
+        });
+    }
+}
+
+/// アクターの足元のタイルに応じた効果を適用します
+fn apply_terrain(
+    mut commands: Commands,
+    current: Res<CurrentLevel>,
+    mut query: Query<(
+        Entity,
+        &mut Footing,
+        &mut Damping,
+        &mut Life,
+        &mut Transform,
+        Option<&Flying>,
+        Option<&mut Burning>,
+    )>,
+    mut damage_writer: EventWriter<SpawnDamageNumber>,
+    mut se_writer: EventWriter<SEEvent>,
+) {
+    let Some(ref chunk) = current.chunk else {
+        return;
+    };
+
+    for (entity, mut footing, mut damping, mut life, mut transform, flying, burning) in
+        query.iter_mut()
+    {
+        let position = transform.translation.truncate();
+        let tile = chunk.get_tile_by_coords(position);
+
+        damping.linear_damping = footing.base_linear_damping
+            * match tile {
+                Tile::Water => WATER_DAMPING_SCALE,
+                Tile::Ice => ICE_DAMPING_SCALE,
+                _ => 1.0,
+            };
+
+        match tile {
+            Tile::StoneTile | Tile::Water | Tile::Ice => {
+                footing.safe_position = Vec2::new(
+                    (position.x / TILE_SIZE).floor() * TILE_SIZE + TILE_HALF,
+                    (position.y / TILE_SIZE).floor() * TILE_SIZE + TILE_HALF,
+                );
+                if tile == Tile::Water && burning.is_some() {
+                    commands.entity(entity).remove::<Burning>();
+                }
+            }
+            Tile::Lava => match burning {
+                Some(mut burning) => burning.remaining = BURNING_DURATION,
+                None => {
+                    commands.entity(entity).insert(Burning {
+                        remaining: BURNING_DURATION,
+                        elapsed: 0,
+                    });
+                }
+            },
+            Tile::Pit if flying.is_none() => {
+                transform.translation.x = footing.safe_position.x;
+                transform.translation.y = footing.safe_position.y;
+                life.life = (life.life - PIT_DAMAGE).max(0);
+                life.amplitude = 6.0;
+                damage_writer.send(SpawnDamageNumber {
+                    damage: PIT_DAMAGE,
+                    position,
+                });
+                se_writer.send(SEEvent::pos(SE::Damage, position));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 燃焼状態のアクターにダメージを与えます
+fn burn(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Burning, &mut Life, &Transform)>,
+    mut damage_writer: EventWriter<SpawnDamageNumber>,
+) {
+    for (entity, mut burning, mut life, transform) in query.iter_mut() {
+        burning.elapsed += 1;
+        if burning.elapsed % BURNING_INTERVAL == 0 {
+            life.life = (life.life - BURNING_DAMAGE).max(0);
+            life.amplitude = 2.0;
+            damage_writer.send(SpawnDamageNumber {
+                damage: BURNING_DAMAGE,
+                position: transform.translation.truncate(),
+            });
+        }
+
+        burning.remaining = burning.remaining.saturating_sub(1);
+        if burning.remaining == 0 {
+            commands.entity(entity).remove::<Burning>();
+        }
+    }
+}
+
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (insert_footing, apply_terrain, burn)
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .in_set(GameSet)
+                .before(PhysicsSet::SyncBackend),
+        );
+    }
+}
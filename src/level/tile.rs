@@ -1,11 +1,56 @@
 use bevy::prelude::Component;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Tile {
     Blank,
     Wall,
     StoneTile,
+
+    /// アクターの移動が遅くなり、燃焼状態が解除されます
+    Water,
+
+    /// 上にいるアクターが燃焼状態になり、継続的にダメージを受けます
+    Lava,
+
+    /// 飛行していないアクターは落下してダメージを受け、最後に立っていた安全な場所に戻されます
+    Pit,
+
+    /// 滑りやすく、アクターが止まりにくくなります
+    Ice,
+
+    /// ひび割れた壁です
+    /// 壁と同じく通過できませんが、弾丸が当たると壊れて床になり、隠し部屋の入口になります
+    CrackedWall,
 }
 
+impl Tile {
+    /// アクターが歩いて通過できるタイルかどうかを返します
+    pub fn is_walkable(&self) -> bool {
+        match self {
+            Tile::StoneTile | Tile::Water | Tile::Lava | Tile::Ice => true,
+            _ => false,
+        }
+    }
+
+    /// 壁として描画され、衝突形状を持つタイルかどうかを返します
+    pub fn is_wall(&self) -> bool {
+        match self {
+            Tile::Wall | Tile::CrackedWall => true,
+            _ => false,
+        }
+    }
+
+    /// 壁以外の、床として描画されるタイルかどうかを返します
+    pub fn is_floor(&self) -> bool {
+        self.is_walkable() || *self == Tile::Pit
+    }
+}
+
+/// 床や壁、天井のスプライトです
+/// タイルが変更されたときに作り直せるよう、どのタイルのスプライトかを記録しています
 #[derive(Component)]
-pub struct WorldTile;
+pub struct WorldTile {
+    pub x: i32,
+    pub y: i32,
+}
@@ -2,7 +2,7 @@ use crate::level::{
     map::LevelChunk, ENEMY_BULLET_GROUP, ENEMY_GROUP, ENTITY_GROUP, TILE_HALF, TILE_SIZE,
     WALL_GROUP, WITCH_BULLET_GROUP, WITCH_GROUP,
 };
-use crate::{level::Tile, states::GameState};
+use crate::states::GameState;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{
     CoefficientCombineRule, Collider, CollisionGroups, Friction, RigidBody,
@@ -11,12 +11,23 @@ use std::collections::HashMap;
 
 use super::RABBIT_GROUP;
 
+/// 壁の衝突形状は、この大きさの区画ごとに壁タイルをまとめて生成されます
+/// タイルが変更されたときは、そのタイルを含む区画の衝突形状だけを作り直します
+pub const COLLIDER_BLOCK_SIZE: i32 = 16;
+
 /// 壁タイルから衝突矩形を計算します
 /// チェストや本棚なども侵入不可能ですが、それらは個別に衝突形状を持つため、ここでは壁のみを扱います
 /// TODO: 本棚などのエンティティもここで一括で生成したほうが効率はいい？
 /// でもエンティティが個別に削除されることも多そうなので、その場合はエンティティは別のほうがいいかも
 /// https://github.com/Trouv/bevy_ecs_ldtk/blob/main/examples/platformer/walls.rs
-pub fn get_wall_collisions(chunk: &LevelChunk) -> Vec<Rect> {
+/// min_x, max_x, min_y, max_y で指定した範囲の外側の壁は無視されます
+pub fn get_wall_collisions(
+    chunk: &LevelChunk,
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+) -> Vec<Rect> {
     /// Represents a wide wall that is 1 tile tall
     /// Used to spawn wall collisions
     #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
@@ -28,15 +39,15 @@ pub fn get_wall_collisions(chunk: &LevelChunk) -> Vec<Rect> {
     // combine wall tiles into flat "plates" in each individual row
     let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
 
-    for y in chunk.min_y..chunk.max_y {
+    for y in min_y..max_y {
         let mut row_plates: Vec<Plate> = Vec::new();
         let mut plate_start = None;
 
         // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-        for x in chunk.min_x..(chunk.max_x + 1) {
+        for x in min_x..(max_x + 1) {
             match (
                 plate_start,
-                chunk.get_tile(x as i32, y as i32) == Tile::Wall,
+                x < max_x && chunk.get_tile(x as i32, y as i32).is_wall(),
             ) {
                 (Some(s), false) => {
                     row_plates.push(Plate {
@@ -71,7 +82,7 @@ pub fn get_wall_collisions(chunk: &LevelChunk) -> Vec<Rect> {
             }
         }
 
-        let y = chunk.min_y + plate_index as i32;
+        let y = min_y + plate_index as i32;
 
         for plate in &current_row {
             rect_builder
@@ -90,19 +101,44 @@ pub fn get_wall_collisions(chunk: &LevelChunk) -> Vec<Rect> {
     wall_rects
 }
 
+/// 壁の衝突形状です
+/// どの区画の壁から生成されたかを記録しています
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Component)]
-pub struct WallCollider;
+pub struct WallCollider {
+    pub block_x: i32,
+    pub block_y: i32,
+}
+
+/// 指定したタイルを含む区画の位置を返します
+/// 区画はワールド全体で共通の格子なので、別のレベルのチャンクの区画と重なることはありません
+pub fn get_collider_block(x: i32, y: i32) -> (i32, i32) {
+    (
+        x.div_euclid(COLLIDER_BLOCK_SIZE),
+        y.div_euclid(COLLIDER_BLOCK_SIZE),
+    )
+}
+
+/// ひとつの区画の衝突形状を生成します
+pub fn spawn_block_wall_collisions(
+    commands: &mut Commands,
+    chunk: &LevelChunk,
+    block_x: i32,
+    block_y: i32,
+) {
+    let min_x = (block_x * COLLIDER_BLOCK_SIZE).max(chunk.min_x);
+    let min_y = (block_y * COLLIDER_BLOCK_SIZE).max(chunk.min_y);
+    let max_x = ((block_x + 1) * COLLIDER_BLOCK_SIZE).min(chunk.max_x);
+    let max_y = ((block_y + 1) * COLLIDER_BLOCK_SIZE).min(chunk.max_y);
 
-pub fn spawn_wall_collisions(commands: &mut Commands, chunk: &LevelChunk) {
     // 衝突形状の生成
-    for rect in get_wall_collisions(&chunk) {
+    for rect in get_wall_collisions(&chunk, min_x, max_x, min_y, max_y) {
         let w = TILE_HALF * (rect.width() + 1.0);
         let h = TILE_HALF * (rect.height() + 1.0);
         let x = rect.min.x as f32 * TILE_SIZE + w;
         let y = rect.min.y as f32 * -TILE_SIZE - h;
         commands.spawn((
             Name::new("wall collider"),
-            WallCollider,
+            WallCollider { block_x, block_y },
             StateScoped(GameState::InGame),
             Transform::from_translation(Vec3::new(x, y, 0.0)),
             GlobalTransform::default(),
@@ -124,8 +160,6 @@ pub fn spawn_wall_collisions(commands: &mut Commands, chunk: &LevelChunk) {
             ),
         ));
     }
-
-    commands.insert_resource(chunk.clone());
 }
 
 pub struct WallPlugin;
@@ -0,0 +1,44 @@
+// ゲーム本体と、レベルの検査ツールなどの補助的なバイナリから共有されるモジュールです
+
+mod asset;
+mod audio;
+mod camera;
+mod cast;
+mod codex;
+mod config;
+mod constant;
+mod controller;
+mod curve;
+mod debug;
+mod difficulty;
+mod enemy;
+pub mod entity;
+mod equipment;
+mod footsteps;
+pub mod game;
+mod hud;
+mod input;
+mod inventory;
+mod inventory_item;
+mod language;
+pub mod level;
+mod page;
+mod physics;
+mod player_state;
+mod random;
+#[cfg(all(not(target_arch = "wasm32"), feature = "server"))]
+pub mod relay;
+mod save;
+mod se;
+mod set;
+mod speech_bubble;
+mod spell;
+mod states;
+mod stats;
+mod ui;
+mod wand;
+mod wand_props;
+
+// 中継サーバーの結合テストで、クライアントとしてメッセージを送受信するために使います
+pub use controller::protocol;
+pub use controller::remote::{send_remote_message, RemoteMessage};
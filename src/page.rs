@@ -1,3 +1,5 @@
+pub mod achievements;
+pub mod codex;
 pub mod ending;
 pub mod main_menu;
 pub mod name_input;
@@ -0,0 +1,184 @@
+use crate::asset::GameAssets;
+use crate::config::GameConfig;
+use crate::hud::overlay::OverlayEvent;
+use crate::language::Dict;
+use crate::save::SaveSlots;
+use crate::se::{SEEvent, SE};
+use crate::states::GameState;
+use crate::stats::{Achievements, Stats};
+use crate::ui::menu_button::menu_button;
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+
+#[derive(Resource)]
+struct ButtonShots {
+    back: SystemId,
+}
+
+impl FromWorld for ButtonShots {
+    fn from_world(world: &mut World) -> Self {
+        ButtonShots {
+            back: world.register_system(back_to_main_menu),
+        }
+    }
+}
+
+fn back_to_main_menu(
+    mut overlay_event_writer: EventWriter<OverlayEvent>,
+    mut writer: EventWriter<SEEvent>,
+) {
+    overlay_event_writer.send(OverlayEvent::Close(GameState::MainMenu));
+    writer.send(SEEvent::new(SE::Click));
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    shots: Res<ButtonShots>,
+    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
+    achievements: Res<Achievements>,
+) {
+    let stats = &slots.current().stats;
+    let unlocked = achievements
+        .0
+        .iter()
+        .filter(|a| stats.is_unlocked(a))
+        .count();
+
+    commands
+        .spawn((
+            Name::new("achievements"),
+            StateScoped(GameState::Achievements),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(40.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(
+                    Dict {
+                        ja: format!("実績 {} / {}", unlocked, achievements.0.len()),
+                        en: format!("Achievements {} / {}", unlocked, achievements.0.len()),
+                    }
+                    .get(config.language),
+                ),
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                TextFont {
+                    font_size: 40.0,
+                    font: assets.dotgothic.clone(),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(40.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            width: Val::Px(720.0),
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(6.0),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for achievement in achievements.0.iter() {
+                                let color = if stats.is_unlocked(achievement) {
+                                    Color::srgb(0.9, 0.9, 0.9)
+                                } else {
+                                    Color::srgba(0.9, 0.9, 0.9, 0.3)
+                                };
+                                parent.spawn((
+                                    Text::new(format!(
+                                        "{}  {}",
+                                        achievement.name.get(config.language),
+                                        achievement.description.get(config.language)
+                                    )),
+                                    TextColor(color),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        font: assets.dotgothic.clone(),
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+
+                    parent.spawn((
+                        Node {
+                            width: Val::Px(400.0),
+                            ..default()
+                        },
+                        Text::new(stats_text(stats).get(config.language)),
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        TextFont {
+                            font_size: 18.0,
+                            font: assets.dotgothic.clone(),
+                            ..default()
+                        },
+                    ));
+                });
+
+            menu_button(
+                parent,
+                &assets,
+                shots.back,
+                160.0,
+                60.0,
+                Dict {
+                    ja: "もどる",
+                    en: "Back",
+                },
+            );
+        });
+}
+
+/// 統計の一覧を表示するテキストです
+fn stats_text(stats: &Stats) -> Dict<String> {
+    let boss = stats
+        .best_boss_clear_time
+        .map_or("-".to_string(), |time| format!("{:.1}", time));
+    Dict {
+        ja: format!(
+            "倒した敵 {}\n与えたダメージ {}\n受けたダメージ {}\n詠唱した回数 {}\n拾ったゴールド {}\n使ったゴールド {}\n倒れた回数 {}\nボス最速撃破 {} 秒\n倒したプレイヤー {}",
+            stats.total_kills(),
+            stats.damage_dealt,
+            stats.damage_taken,
+            stats.total_casts(),
+            stats.gold_earned,
+            stats.gold_spent,
+            stats.total_deaths(),
+            boss,
+            stats.pvp_kills
+        ),
+        en: format!(
+            "Enemies defeated {}\nDamage dealt {}\nDamage taken {}\nSpells cast {}\nGolds earned {}\nGolds spent {}\nDeaths {}\nFastest boss clear {} s\nPlayers defeated {}",
+            stats.total_kills(),
+            stats.damage_dealt,
+            stats.damage_taken,
+            stats.total_casts(),
+            stats.gold_earned,
+            stats.gold_spent,
+            stats.total_deaths(),
+            boss,
+            stats.pvp_kills
+        ),
+    }
+}
+
+pub struct AchievementsPagePlugin;
+
+impl Plugin for AchievementsPagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonShots>();
+        app.add_systems(OnEnter(GameState::Achievements), setup);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::asset::GameAssets;
+use crate::codex::{all_items, unlock_of};
+use crate::config::GameConfig;
+use crate::hud::overlay::OverlayEvent;
+use crate::inventory_item::InventoryItemType;
+use crate::language::Dict;
+use crate::save::SaveSlots;
+use crate::se::{SEEvent, SE};
+use crate::states::GameState;
+use crate::ui::menu_button::menu_button;
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::*;
+
+const ITEM_BACKGROUND: Color = Color::hsla(0.0, 0.0, 1.0, 0.05);
+
+const ITEM_BACKGROUND_HOVERED: Color = Color::hsla(0.0, 0.0, 1.0, 0.2);
+
+#[derive(Resource)]
+struct ButtonShots {
+    back: SystemId,
+}
+
+impl FromWorld for ButtonShots {
+    fn from_world(world: &mut World) -> Self {
+        ButtonShots {
+            back: world.register_system(back_to_main_menu),
+        }
+    }
+}
+
+/// 図鑑の一覧に並ぶアイテムです
+#[derive(Component)]
+struct CodexItem(InventoryItemType);
+
+/// カーソルを合わせたアイテムの説明です
+#[derive(Component)]
+struct CodexDescription;
+
+fn back_to_main_menu(
+    mut overlay_event_writer: EventWriter<OverlayEvent>,
+    mut writer: EventWriter<SEEvent>,
+) {
+    overlay_event_writer.send(OverlayEvent::Close(GameState::MainMenu));
+    writer.send(SEEvent::new(SE::Click));
+}
+
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    shots: Res<ButtonShots>,
+    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
+) {
+    let codex = &slots.current().codex;
+    let items = all_items();
+    let seen = items.iter().filter(|i| codex.is_seen(**i)).count();
+
+    commands
+        .spawn((
+            Name::new("codex"),
+            StateScoped(GameState::Codex),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(40.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(
+                    Dict {
+                        ja: format!("図鑑 {} / {}", seen, items.len()),
+                        en: format!("Codex {} / {}", seen, items.len()),
+                    }
+                    .get(config.language),
+                ),
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                TextFont {
+                    font_size: 40.0,
+                    font: assets.dotgothic.clone(),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(40.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            width: Val::Px(640.0),
+                            flex_wrap: FlexWrap::Wrap,
+                            align_content: AlignContent::FlexStart,
+                            column_gap: Val::Px(8.0),
+                            row_gap: Val::Px(8.0),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for item in items.iter() {
+                                spawn_codex_item(parent, &assets, *item, codex.is_seen(*item));
+                            }
+                        });
+
+                    parent.spawn((
+                        CodexDescription,
+                        Node {
+                            width: Val::Px(480.0),
+                            ..default()
+                        },
+                        Text::new(""),
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        TextFont {
+                            font_size: 20.0,
+                            font: assets.dotgothic.clone(),
+                            ..default()
+                        },
+                    ));
+                });
+
+            menu_button(
+                parent,
+                &assets,
+                shots.back,
+                160.0,
+                60.0,
+                Dict {
+                    ja: "もどる",
+                    en: "Back",
+                },
+            );
+        });
+}
+
+/// 一覧のアイテムを生成します
+/// まだ見つけていないアイテムはアイコンの代わりに ? を表示します
+fn spawn_codex_item(
+    parent: &mut ChildBuilder,
+    assets: &Res<GameAssets>,
+    item: InventoryItemType,
+    seen: bool,
+) {
+    let width = item.get_icon_width();
+    let mut builder = parent.spawn((
+        CodexItem(item),
+        Button,
+        Node {
+            width: Val::Px(width),
+            height: Val::Px(32.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(ITEM_BACKGROUND),
+    ));
+
+    if seen {
+        builder.with_child((
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(32.0),
+                ..default()
+            },
+            AseUiSlice {
+                aseprite: assets.atlas.clone(),
+                name: item.get_icon().into(),
+            },
+        ));
+    } else {
+        builder.with_child((
+            Text::new("?"),
+            TextColor(Color::srgba(1.0, 1.0, 1.0, 0.5)),
+            TextFont {
+                font_size: 20.0,
+                font: assets.dotgothic.clone(),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// カーソルを合わせたアイテムの名前と説明を表示します
+/// 説明は手に入れたことのあるアイテムだけ、名前は見かけたことのあるアイテムだけ表示されます
+fn hover_codex_item(
+    mut item_query: Query<(&CodexItem, &Interaction, &mut BackgroundColor), Changed<Interaction>>,
+    mut description_query: Query<&mut Text, With<CodexDescription>>,
+    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
+) {
+    let slot = slots.current();
+    for (CodexItem(item), interaction, mut background) in item_query.iter_mut() {
+        if *interaction == Interaction::None {
+            background.0 = ITEM_BACKGROUND;
+            continue;
+        }
+        background.0 = ITEM_BACKGROUND_HOVERED;
+
+        let props = item.to_props();
+        let mut lines = Vec::new();
+        if slot.codex.is_seen(*item) {
+            lines.push(props.name.get(config.language));
+        } else {
+            lines.push("???".to_string());
+        }
+        if slot.codex.is_acquired(*item) {
+            lines.push(props.description.get(config.language));
+        } else if slot.codex.is_seen(*item) {
+            lines.push(
+                Dict {
+                    ja: "手に入れると説明が読めるようになります",
+                    en: "Pick it up to read the description",
+                }
+                .get(config.language),
+            );
+        } else {
+            lines.push(
+                Dict {
+                    ja: "まだ見つけていません",
+                    en: "Not found yet",
+                }
+                .get(config.language),
+            );
+        }
+        if let Some(unlock) = unlock_of(*item) {
+            if !unlock.is_satisfied(slot) {
+                lines.push(unlock.hint().get(config.language));
+            }
+        }
+
+        for mut text in description_query.iter_mut() {
+            text.0 = lines.join("\n\n");
+        }
+    }
+}
+
+pub struct CodexPagePlugin;
+
+impl Plugin for CodexPagePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonShots>();
+        app.add_systems(OnEnter(GameState::Codex), setup);
+        app.add_systems(Update, hover_codex_item.run_if(in_state(GameState::Codex)));
+    }
+}
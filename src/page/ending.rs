@@ -1,18 +1,35 @@
 use crate::{
     asset::GameAssets,
     audio::NextBGM,
+    config::GameConfig,
+    controller::player::Player,
     enemy::huge_slime::HugeSlime,
+    entity::{actor::Actor, life::Life},
     hud::overlay::OverlayEvent,
-    level::{CurrentLevel, GameLevel},
+    language::Dict,
+    level::{meta::LevelMetadata, CurrentLevel, GameLevel},
+    player_state::PlayerState,
+    save::SaveSlots,
+    se::{SEEvent, SE},
     states::GameState,
+    ui::menu_button::menu_button,
 };
+use bevy::ecs::system::SystemId;
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::AseUiAnimation;
 
 #[derive(Component)]
 pub struct EndingImage;
 
-fn setup(mut commands: Commands, assets: Res<GameAssets>, mut next_bgm: ResMut<NextBGM>) {
+fn setup(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut next_bgm: ResMut<NextBGM>,
+    current: Res<CurrentLevel>,
+    metadata: Res<LevelMetadata>,
+    config: Res<GameConfig>,
+    shots: Res<ButtonShots>,
+) {
     next_bgm.0 = Some(assets.ending_bgm.clone());
 
     commands.spawn((
@@ -29,33 +46,124 @@ fn setup(mut commands: Commands, assets: Res<GameAssets>, mut next_bgm: ResMut<N
             top: Val::Px(0.0),
             ..default()
         },
-        Interaction::default(),
+    ));
+
+    // エンディングのあとは、エンドレスモードとしてさらに深い階層へ進むか、周回を終えてタイトルへ戻るかを選べます
+    commands
+        .spawn((
+            Name::new("ending_menu"),
+            StateScoped(GameState::Ending),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(16.0),
+                bottom: Val::Px(48.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            menu_button(
+                parent,
+                &assets,
+                shots.descend,
+                280.0,
+                60.0,
+                Dict {
+                    ja: "さらに深く",
+                    en: "Descend Further",
+                },
+            );
+            menu_button(
+                parent,
+                &assets,
+                shots.main_menu,
+                280.0,
+                60.0,
+                Dict {
+                    ja: "タイトルへ戻る",
+                    en: "Back to Title",
+                },
+            );
+        });
+
+    // この周回でたどった道筋
+    let path = current
+        .path
+        .iter()
+        .map(|level| metadata.get(*level).name.get(config.language))
+        .collect::<Vec<String>>()
+        .join(" → ");
+    commands.spawn((
+        Name::new("ending_path"),
+        StateScoped(GameState::Ending),
+        Text::new(path),
+        TextColor(Color::srgba(1.0, 1.0, 1.0, 0.7)),
+        TextFont {
+            font: assets.dotgothic.clone(),
+            font_size: 14.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            right: Val::Px(16.0),
+            bottom: Val::Px(16.0),
+            ..default()
+        },
     ));
 }
 
-fn interaction(
-    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<EndingImage>)>,
-    mut writer: EventWriter<OverlayEvent>,
-) {
-    for interaction in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => {
-                writer.send(OverlayEvent::Close(GameState::MainMenu));
-            }
-            _ => {}
+#[derive(Resource)]
+struct ButtonShots {
+    descend: SystemId,
+    main_menu: SystemId,
+}
+
+impl FromWorld for ButtonShots {
+    fn from_world(world: &mut World) -> Self {
+        ButtonShots {
+            descend: world.register_system(descend),
+            main_menu: world.register_system(back_to_main_menu),
         }
     }
 }
 
+/// エンドレスモードとしてさらに深い階層へ進みます
+fn descend(mut writer: EventWriter<OverlayEvent>, mut se_writer: EventWriter<SEEvent>) {
+    writer.send(OverlayEvent::Close(GameState::Warp));
+    se_writer.send(SEEvent::new(SE::Click));
+}
+
+/// 周回を終えてタイトルへ戻ります
+/// 終えた周回は再開できません
+fn back_to_main_menu(
+    mut writer: EventWriter<OverlayEvent>,
+    mut se_writer: EventWriter<SEEvent>,
+    mut slots: ResMut<SaveSlots>,
+) {
+    slots.current_mut().run = None;
+    writer.send(OverlayEvent::Close(GameState::MainMenu));
+    se_writer.send(SEEvent::new(SE::Click));
+}
+
 fn start_ending(
     mut local: Local<u32>,
     boss_query: Query<&HugeSlime>,
     mut writer: EventWriter<OverlayEvent>,
-    current: Res<CurrentLevel>,
+    mut current: ResMut<CurrentLevel>,
+    player_query: Query<(&Player, &Actor, &Life)>,
+    slots: Res<SaveSlots>,
+    metadata: Res<LevelMetadata>,
 ) {
-    if current.level == Some(GameLevel::Level(3)) && boss_query.is_empty() {
+    // 最後の手作りのレベルのボスを倒すとエンディングになります
+    let last = GameLevel::Level(metadata.handcrafted_levels() - 1);
+    if current.level == Some(last) && boss_query.is_empty() {
         *local += 1;
         if *local == 120 {
+            current.next_level = metadata.next(last);
+            current.next_state =
+                PlayerState::from(player_query.get_single(), &slots.current().player_name);
             writer.send(OverlayEvent::Close(GameState::Ending));
         }
     }
@@ -66,7 +174,7 @@ pub struct EndingPlugin;
 impl Plugin for EndingPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, start_ending.run_if(in_state(GameState::InGame)));
-        app.add_systems(Update, interaction.run_if(in_state(GameState::Ending)));
+        app.init_resource::<ButtonShots>();
         app.add_systems(OnEnter(GameState::Ending), setup);
     }
 }
@@ -2,8 +2,9 @@ use crate::audio::NextBGM;
 use crate::config::GameConfig;
 use crate::constant::HUD_Z_INDEX;
 use crate::hud::overlay::OverlayEvent;
-use crate::language::Languages;
+use crate::language::{Dict, Languages};
 use crate::level::CurrentLevel;
+use crate::save::{SaveSlots, MAX_SAVE_SLOTS};
 use crate::se::{SEEvent, SE};
 use crate::ui::on_press::OnPress;
 use crate::{
@@ -30,6 +31,15 @@ struct CloudAnimation {
 #[derive(Event, PartialEq, Eq, Debug, Clone, Copy)]
 enum Events {
     Start,
+
+    /// 中断した周回を再開します
+    Continue,
+
+    /// 図鑑を開きます
+    Codex,
+
+    /// 実績を開きます
+    Achievements,
 }
 
 #[derive(Component)]
@@ -38,14 +48,46 @@ struct LanguageButton;
 #[derive(Component)]
 struct ClickToStart;
 
+#[derive(Component)]
+struct ContinueButton;
+
+#[derive(Component)]
+struct ContinueText;
+
+#[derive(Component)]
+struct CodexButton;
+
+#[derive(Component)]
+struct CodexText;
+
+#[derive(Component)]
+struct AchievementsButton;
+
+#[derive(Component)]
+struct AchievementsText;
+
+/// セーブスロットの一覧のボタンで行う操作です
+#[derive(Component, Clone, Copy)]
+enum SlotButton {
+    Select(usize),
+    Duplicate(usize),
+    Delete(usize),
+}
+
+/// セーブスロットの一覧のボタンのテキストです
+#[derive(Component)]
+struct SlotText(SlotButton);
+
 fn setup_main_menu(
     mut commands: Commands,
     assets: Res<GameAssets>,
     mut next_bgm: ResMut<NextBGM>,
     mut current: ResMut<CurrentLevel>,
+    slots: Res<SaveSlots>,
 ) {
     *next_bgm = NextBGM(Some(assets.boubaku.clone()));
     *current = CurrentLevel::default();
+    current.next_state.name = slots.current().player_name.clone();
 
     commands.spawn((
         Name::new("main_menu"),
@@ -213,6 +255,150 @@ fn setup_main_menu(
                 ..default()
             },
         ));
+
+    // 中断した周回があるときだけ、再開するボタンを表示します
+    commands
+        .spawn((
+            Name::new("continue_button"),
+            ContinueButton,
+            StateScoped(GameState::MainMenu),
+            GlobalZIndex(HUD_Z_INDEX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(40.0),
+                bottom: Val::Px(100.0),
+                padding: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(8.0), Val::Px(8.0)),
+                ..default()
+            },
+            Button,
+            BackgroundColor::from(Color::hsva(0.0, 0.0, 1.0, 0.3)),
+            Visibility::Hidden,
+        ))
+        .with_child((
+            ContinueText,
+            Text::new(""),
+            TextColor::from(Color::hsl(0.0, 0.0, 0.0)),
+            TextFont {
+                font_size: 16.0,
+                font: assets.dotgothic.clone(),
+                ..default()
+            },
+        ));
+
+    commands
+        .spawn((
+            Name::new("codex_button"),
+            CodexButton,
+            StateScoped(GameState::MainMenu),
+            GlobalZIndex(HUD_Z_INDEX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(40.0),
+                bottom: Val::Px(160.0),
+                padding: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(8.0), Val::Px(8.0)),
+                ..default()
+            },
+            Button,
+            BackgroundColor::from(Color::hsva(0.0, 0.0, 1.0, 0.3)),
+        ))
+        .with_child((
+            CodexText,
+            Text::new(""),
+            TextColor::from(Color::hsl(0.0, 0.0, 0.0)),
+            TextFont {
+                font_size: 16.0,
+                font: assets.dotgothic.clone(),
+                ..default()
+            },
+        ));
+
+    commands
+        .spawn((
+            Name::new("achievements_button"),
+            AchievementsButton,
+            StateScoped(GameState::MainMenu),
+            GlobalZIndex(HUD_Z_INDEX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(40.0),
+                bottom: Val::Px(220.0),
+                padding: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(8.0), Val::Px(8.0)),
+                ..default()
+            },
+            Button,
+            BackgroundColor::from(Color::hsva(0.0, 0.0, 1.0, 0.3)),
+        ))
+        .with_child((
+            AchievementsText,
+            Text::new(""),
+            TextColor::from(Color::hsl(0.0, 0.0, 0.0)),
+            TextFont {
+                font_size: 16.0,
+                font: assets.dotgothic.clone(),
+                ..default()
+            },
+        ));
+
+    commands
+        .spawn((
+            Name::new("save_slots"),
+            StateScoped(GameState::MainMenu),
+            GlobalZIndex(HUD_Z_INDEX),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(40.0),
+                top: Val::Px(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for index in 0..MAX_SAVE_SLOTS {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        spawn_slot_button(parent, &assets, SlotButton::Select(index), 360.0);
+                        spawn_slot_button(parent, &assets, SlotButton::Duplicate(index), 80.0);
+                        spawn_slot_button(parent, &assets, SlotButton::Delete(index), 80.0);
+                    });
+            }
+        });
+}
+
+/// セーブスロットの一覧のボタンを生成します
+fn spawn_slot_button(
+    parent: &mut ChildBuilder,
+    assets: &Res<GameAssets>,
+    action: SlotButton,
+    width: f32,
+) {
+    parent
+        .spawn((
+            action,
+            Node {
+                width: Val::Px(width),
+                padding: UiRect::new(Val::Px(12.0), Val::Px(12.0), Val::Px(6.0), Val::Px(6.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Button,
+            BackgroundColor::from(Color::hsva(0.0, 0.0, 1.0, 0.3)),
+        ))
+        .with_child((
+            SlotText(action),
+            Text::new(""),
+            TextColor::from(Color::hsl(0.0, 0.0, 0.0)),
+            TextFont {
+                font_size: 12.0,
+                font: assets.dotgothic.clone(),
+                ..default()
+            },
+        ));
 }
 
 fn spawn_cloud<T: Component>(
@@ -240,8 +426,10 @@ fn spawn_cloud<T: Component>(
     ));
 }
 
+/// このフレームでメニューのボタンが押されたかどうかです
+/// ボタンのクリックで同時にゲームが始まらないようにするために使います
 #[derive(Resource, Default)]
-struct LanguageChanged(bool);
+struct MenuButtonPressed(bool);
 
 fn toggle_language(
     mut query: Query<
@@ -249,9 +437,9 @@ fn toggle_language(
         (With<LanguageButton>, Changed<Interaction>),
     >,
     mut config: ResMut<GameConfig>,
-    mut changed: ResMut<LanguageChanged>,
+    mut pressed: ResMut<MenuButtonPressed>,
 ) {
-    changed.0 = false;
+    pressed.0 = false;
 
     for (mut background, interaction) in &mut query.iter_mut() {
         match interaction {
@@ -267,15 +455,110 @@ fn toggle_language(
                     Languages::En => Languages::Ja,
                     Languages::Ja => Languages::En,
                 };
-                changed.0 = true;
+                pressed.0 = true;
+            }
+        }
+    }
+}
+
+fn toggle_continue(
+    mut query: Query<
+        (&mut BackgroundColor, &Interaction),
+        (With<ContinueButton>, Changed<Interaction>),
+    >,
+    mut writer: EventWriter<Events>,
+    mut pressed: ResMut<MenuButtonPressed>,
+) {
+    for (mut background, interaction) in &mut query.iter_mut() {
+        match interaction {
+            Interaction::None => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.3);
+            }
+            Interaction::Hovered => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.8);
+            }
+            Interaction::Pressed => {
+                background.0 = Color::WHITE;
+                writer.send(Events::Continue);
+                pressed.0 = true;
+            }
+        }
+    }
+}
+
+fn toggle_codex(
+    mut query: Query<
+        (&mut BackgroundColor, &Interaction),
+        (With<CodexButton>, Changed<Interaction>),
+    >,
+    mut writer: EventWriter<Events>,
+    mut pressed: ResMut<MenuButtonPressed>,
+) {
+    for (mut background, interaction) in &mut query.iter_mut() {
+        match interaction {
+            Interaction::None => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.3);
+            }
+            Interaction::Hovered => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.8);
+            }
+            Interaction::Pressed => {
+                background.0 = Color::WHITE;
+                writer.send(Events::Codex);
+                pressed.0 = true;
+            }
+        }
+    }
+}
+
+fn toggle_achievements(
+    mut query: Query<
+        (&mut BackgroundColor, &Interaction),
+        (With<AchievementsButton>, Changed<Interaction>),
+    >,
+    mut writer: EventWriter<Events>,
+    mut pressed: ResMut<MenuButtonPressed>,
+) {
+    for (mut background, interaction) in &mut query.iter_mut() {
+        match interaction {
+            Interaction::None => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.3);
+            }
+            Interaction::Hovered => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.8);
+            }
+            Interaction::Pressed => {
+                background.0 = Color::WHITE;
+                writer.send(Events::Achievements);
+                pressed.0 = true;
             }
         }
     }
 }
 
 fn update_click_to_start_text(
-    mut query: Query<&mut Text, With<ClickToStart>>,
+    mut query: Query<
+        &mut Text,
+        (
+            With<ClickToStart>,
+            Without<ContinueText>,
+            Without<CodexText>,
+            Without<AchievementsText>,
+        ),
+    >,
+    mut continue_query: Query<
+        &mut Text,
+        (
+            With<ContinueText>,
+            Without<CodexText>,
+            Without<AchievementsText>,
+        ),
+    >,
+    mut codex_query: Query<&mut Text, (With<CodexText>, Without<AchievementsText>)>,
+    mut achievements_query: Query<&mut Text, With<AchievementsText>>,
+    added_query: Query<(), Added<ContinueText>>,
     config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
 ) {
     if config.is_changed() {
         for mut text in &mut query.iter_mut() {
@@ -286,14 +569,162 @@ fn update_click_to_start_text(
             .to_string();
         }
     }
+
+    if config.is_changed() || !added_query.is_empty() {
+        for mut text in &mut codex_query.iter_mut() {
+            text.0 = Dict {
+                ja: "図鑑",
+                en: "Codex",
+            }
+            .get(config.language);
+        }
+        for mut text in &mut achievements_query.iter_mut() {
+            text.0 = Dict {
+                ja: "実績",
+                en: "Achievements",
+            }
+            .get(config.language);
+        }
+    }
+
+    if config.is_changed() || slots.is_changed() || !added_query.is_empty() {
+        for mut text in &mut continue_query.iter_mut() {
+            let depth = slots
+                .current()
+                .run
+                .as_ref()
+                .and_then(|r| r.level.depth())
+                .unwrap_or(0);
+            text.0 = match config.language {
+                Languages::Ja => format!("つづきから (深度{})", depth),
+                Languages::En => format!("Continue (Depth {})", depth),
+            };
+        }
+    }
+}
+
+/// セーブスロットの一覧の表示を更新します
+fn update_slot_list(
+    mut slot_query: Query<(&mut Text, &SlotText)>,
+    mut continue_query: Query<&mut Visibility, With<ContinueButton>>,
+    added_query: Query<(), Added<SlotText>>,
+    config: Res<GameConfig>,
+    slots: Res<SaveSlots>,
+) {
+    if !config.is_changed() && !slots.is_changed() && added_query.is_empty() {
+        return;
+    }
+
+    for mut visibility in continue_query.iter_mut() {
+        *visibility = if slots.current().run.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for (mut text, SlotText(button)) in slot_query.iter_mut() {
+        text.0 = match button {
+            SlotButton::Select(index) => {
+                let slot = &slots.slots[*index];
+                let marker = if *index == slots.current { "▶ " } else { "" };
+                if slot.is_empty() {
+                    Dict {
+                        ja: format!("{}スロット{} (空き)", marker, index + 1),
+                        en: format!("{}Slot {} (Empty)", marker, index + 1),
+                    }
+                    .get(config.language)
+                } else {
+                    let level_name = slot
+                        .level_name
+                        .as_ref()
+                        .map(|n| n.get(config.language))
+                        .unwrap_or_default();
+                    Dict {
+                        ja: format!(
+                            "{}スロット{} {} {} 最深{}\nプレイ時間 {} {}",
+                            marker,
+                            index + 1,
+                            slot.player_name,
+                            level_name,
+                            slot.best_depth,
+                            slot.playtime_text(),
+                            slot.timestamp_text()
+                        ),
+                        en: format!(
+                            "{}Slot {} {} {} Best {}\nPlaytime {} {}",
+                            marker,
+                            index + 1,
+                            slot.player_name,
+                            level_name,
+                            slot.best_depth,
+                            slot.playtime_text(),
+                            slot.timestamp_text()
+                        ),
+                    }
+                    .get(config.language)
+                }
+            }
+            SlotButton::Duplicate(_) => Dict {
+                ja: "複製",
+                en: "Copy",
+            }
+            .get(config.language),
+            SlotButton::Delete(_) => Dict {
+                ja: "削除",
+                en: "Delete",
+            }
+            .get(config.language),
+        };
+    }
+}
+
+/// セーブスロットの選択、複製、削除を行います
+fn press_slot_buttons(
+    mut query: Query<(&SlotButton, &mut BackgroundColor, &Interaction), Changed<Interaction>>,
+    mut slots: ResMut<SaveSlots>,
+    mut current: ResMut<CurrentLevel>,
+    mut pressed: ResMut<MenuButtonPressed>,
+    mut writer: EventWriter<SEEvent>,
+) {
+    for (button, mut background, interaction) in query.iter_mut() {
+        match interaction {
+            Interaction::None => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.3);
+            }
+            Interaction::Hovered => {
+                background.0 = Color::hsva(0.0, 0.0, 1.0, 0.8);
+            }
+            Interaction::Pressed => {
+                background.0 = Color::WHITE;
+                match *button {
+                    SlotButton::Select(index) => slots.current = index,
+                    SlotButton::Duplicate(index) => {
+                        if let Some(copied) = slots.duplicate(index) {
+                            slots.current = copied;
+                        }
+                    }
+                    SlotButton::Delete(index) => slots.delete(index),
+                }
+                current.next_state.name = slots.current().player_name.clone();
+                pressed.0 = true;
+                writer.send(SEEvent::new(SE::Click));
+            }
+        }
+    }
 }
 
 fn start_game(
     buttons: Res<ButtonInput<MouseButton>>,
     mut writer: EventWriter<Events>,
-    changed: Res<LanguageChanged>,
+    pressed: Res<MenuButtonPressed>,
+    phase: Res<State<MainMenuPhase>>,
 ) {
-    if !changed.0 && buttons.any_just_pressed(vec![MouseButton::Left, MouseButton::Right]) {
+    // 図鑑や実績を開いてフェードアウトしている最中のクリックでゲームが始まらないようにします
+    if *phase.get() == MainMenuPhase::Active
+        && !pressed.0
+        && buttons.any_just_pressed(vec![MouseButton::Left, MouseButton::Right])
+    {
         writer.send(Events::Start);
     }
 }
@@ -305,10 +736,20 @@ fn read_events(
     mut reader: EventReader<Events>,
     mut next_bgm: ResMut<NextBGM>,
     mut overlay_event_writer: EventWriter<OverlayEvent>,
+    mut current: ResMut<CurrentLevel>,
+    slots: Res<SaveSlots>,
 ) {
     for event in reader.read() {
+        if *event == Events::Continue {
+            let Some(ref save) = slots.current().run else {
+                continue;
+            };
+            current.next_level = save.level;
+            current.next_state = save.player.clone();
+            current.path = save.path.clone();
+        }
         match event {
-            Events::Start => {
+            Events::Start | Events::Continue => {
                 for mut visibility in &mut query {
                     *visibility = Visibility::Hidden;
                 }
@@ -317,6 +758,16 @@ fn read_events(
                 overlay_event_writer.send(OverlayEvent::Close(GameState::InGame));
                 *next_bgm = NextBGM(None);
             }
+            Events::Codex => {
+                menu_next_state.set(MainMenuPhase::Paused);
+                writer.send(SEEvent::new(SE::Click));
+                overlay_event_writer.send(OverlayEvent::Close(GameState::Codex));
+            }
+            Events::Achievements => {
+                menu_next_state.set(MainMenuPhase::Paused);
+                writer.send(SEEvent::new(SE::Click));
+                overlay_event_writer.send(OverlayEvent::Close(GameState::Achievements));
+            }
         }
     }
 }
@@ -345,7 +796,7 @@ pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<LanguageChanged>();
+        app.init_resource::<MenuButtonPressed>();
         app.add_event::<Events>();
         app.add_systems(OnEnter(GameState::MainMenu), setup_main_menu);
         app.add_systems(
@@ -354,8 +805,17 @@ impl Plugin for MainMenuPlugin {
                 read_events,
                 witch_animation,
                 cloud_animation,
-                (toggle_language, start_game).chain(),
+                (
+                    toggle_language,
+                    toggle_continue,
+                    toggle_codex,
+                    toggle_achievements,
+                    press_slot_buttons,
+                    start_game,
+                )
+                    .chain(),
                 update_click_to_start_text,
+                update_slot_list,
             )
                 .run_if(in_state(GameState::MainMenu)),
         );
@@ -1,7 +1,9 @@
 use crate::audio::NextBGM;
-use crate::config::GameConfig;
+use crate::config::{GameConfig, ServerOverride};
 use crate::hud::overlay::OverlayEvent;
 use crate::language::Dict;
+use crate::level::CurrentLevel;
+use crate::save::SaveSlots;
 use crate::se::{SEEvent, SE};
 use crate::ui::menu_button::menu_button;
 use crate::{
@@ -11,13 +13,28 @@ use crate::{
 use bevy::ecs::system::SystemId;
 use bevy::prelude::*;
 use bevy_simple_text_input::{
-    TextInput, TextInputSettings, TextInputTextColor, TextInputTextFont, TextInputValue,
+    TextInput, TextInputInactive, TextInputSettings, TextInputTextColor, TextInputTextFont,
+    TextInputValue,
 };
+use bevy_simple_websocket::{ReadyState, WebSocketState};
 
 const BORDER_COLOR_ACTIVE: Color = Color::srgb(0.75, 0.52, 0.99);
+const BORDER_COLOR_INACTIVE: Color = Color::srgb(0.25, 0.25, 0.25);
 const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
 const BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
 
+/// このページの入力欄の種類です
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum InputField {
+    Name,
+    ServerUrl,
+    Room,
+}
+
+/// サーバーとの接続状態を表示するテキストです
+#[derive(Component)]
+struct ConnectionStatus;
+
 #[derive(Resource)]
 struct ButtonShots {
     start: SystemId,
@@ -33,8 +50,11 @@ impl FromWorld for ButtonShots {
 
 fn start_game(
     mut menu_next_state: ResMut<NextState<MainMenuPhase>>,
+    mut slots: ResMut<SaveSlots>,
+    mut current: ResMut<CurrentLevel>,
     mut config: ResMut<GameConfig>,
-    query: Query<&TextInputValue>,
+    mut server_override: ResMut<ServerOverride>,
+    query: Query<(&InputField, &TextInputValue)>,
     mut writer: EventWriter<SEEvent>,
     mut next_bgm: ResMut<NextBGM>,
     mut overlay_event_writer: EventWriter<OverlayEvent>,
@@ -43,8 +63,20 @@ fn start_game(
     overlay_event_writer.send(OverlayEvent::Close(GameState::InGame));
     *next_bgm = NextBGM(None);
 
-    let q = query.single();
-    config.player_name = q.0.clone();
+    for (field, value) in query.iter() {
+        match field {
+            InputField::Name => {
+                slots.current_mut().player_name = value.0.clone();
+                current.next_state.name = value.0.clone();
+            }
+            InputField::ServerUrl => {
+                config.set_server_url(&mut server_override, &value.0);
+            }
+            InputField::Room => {
+                config.set_room(&mut server_override, &value.0);
+            }
+        }
+    }
 
     writer.send(SEEvent::new(SE::Click));
 }
@@ -54,6 +86,8 @@ fn setup(
     assets: Res<GameAssets>,
     shots: Res<ButtonShots>,
     config: Res<GameConfig>,
+    server_override: Res<ServerOverride>,
+    slots: Res<SaveSlots>,
 ) {
     commands
         .spawn((
@@ -103,28 +137,14 @@ fn setup(
                             ..default()
                         })
                         .with_children(|parent| {
-                            parent.spawn((
-                                Node {
-                                    width: Val::Px(200.0),
-                                    border: UiRect::all(Val::Px(5.0)),
-                                    padding: UiRect::all(Val::Px(5.0)),
-                                    ..default()
-                                },
-                                BorderColor::from(BORDER_COLOR_ACTIVE),
-                                BackgroundColor::from(BACKGROUND_COLOR),
-                                TextInput,
-                                TextInputTextFont(TextFont {
-                                    font: assets.dotgothic.clone(),
-                                    font_size: 40.,
-                                    ..default()
-                                }),
-                                TextInputTextColor(TEXT_COLOR.into()),
-                                TextInputSettings {
-                                    retain_on_submit: true,
-                                    ..default()
-                                },
-                                TextInputValue(config.player_name.clone()),
-                            ));
+                            spawn_input_field(
+                                parent,
+                                &assets,
+                                InputField::Name,
+                                200.0,
+                                40.0,
+                                slots.current().player_name.clone(),
+                            );
 
                             menu_button(
                                 parent,
@@ -138,15 +158,173 @@ fn setup(
                                 },
                             );
                         });
+
+                    // マルチプレイの接続先です
+                    parent
+                        .spawn(Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.0),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            spawn_label(
+                                parent,
+                                &assets,
+                                Dict {
+                                    ja: "サーバー",
+                                    en: "Server",
+                                }
+                                .get(config.language),
+                            );
+                            spawn_input_field(
+                                parent,
+                                &assets,
+                                InputField::ServerUrl,
+                                560.0,
+                                16.0,
+                                config.server_url(&server_override).to_string(),
+                            );
+                            spawn_label(
+                                parent,
+                                &assets,
+                                Dict {
+                                    ja: "ルーム",
+                                    en: "Room",
+                                }
+                                .get(config.language),
+                            );
+                            spawn_input_field(
+                                parent,
+                                &assets,
+                                InputField::Room,
+                                160.0,
+                                16.0,
+                                config.room(&server_override).to_string(),
+                            );
+                        });
+
+                    parent.spawn((
+                        ConnectionStatus,
+                        Text::new(""),
+                        TextColor(TEXT_COLOR),
+                        TextFont {
+                            font_size: 16.0,
+                            font: assets.dotgothic.clone(),
+                            ..default()
+                        },
+                    ));
                 });
         });
 }
 
+fn spawn_label(parent: &mut ChildBuilder, assets: &Res<GameAssets>, text: String) {
+    parent.spawn((
+        Text::new(text),
+        TextColor(TEXT_COLOR),
+        TextFont {
+            font_size: 16.0,
+            font: assets.dotgothic.clone(),
+            ..default()
+        },
+    ));
+}
+
+/// 入力欄を生成します
+/// 最初は名前の入力欄だけが入力を受け付け、ほかの入力欄はクリックすると入力を受け付けるようになります
+fn spawn_input_field(
+    parent: &mut ChildBuilder,
+    assets: &Res<GameAssets>,
+    field: InputField,
+    width: f32,
+    font_size: f32,
+    value: String,
+) {
+    let active = field == InputField::Name;
+    parent.spawn((
+        field,
+        Node {
+            width: Val::Px(width),
+            border: UiRect::all(Val::Px(5.0)),
+            padding: UiRect::all(Val::Px(5.0)),
+            ..default()
+        },
+        Interaction::None,
+        BorderColor::from(if active {
+            BORDER_COLOR_ACTIVE
+        } else {
+            BORDER_COLOR_INACTIVE
+        }),
+        BackgroundColor::from(BACKGROUND_COLOR),
+        TextInput,
+        TextInputInactive(!active),
+        TextInputTextFont(TextFont {
+            font: assets.dotgothic.clone(),
+            font_size,
+            ..default()
+        }),
+        TextInputTextColor(TEXT_COLOR.into()),
+        TextInputSettings {
+            retain_on_submit: true,
+            ..default()
+        },
+        TextInputValue(value),
+    ));
+}
+
+/// クリックされた入力欄だけが入力を受け付けるようにします
+fn focus(
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<InputField>)>,
+    mut input_query: Query<(Entity, &mut TextInputInactive, &mut BorderColor), With<InputField>>,
+) {
+    for (pressed, interaction) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        for (entity, mut inactive, mut border) in input_query.iter_mut() {
+            inactive.0 = entity != pressed;
+            *border = BorderColor::from(if inactive.0 {
+                BORDER_COLOR_INACTIVE
+            } else {
+                BORDER_COLOR_ACTIVE
+            });
+        }
+    }
+}
+
+fn update_connection_status(
+    mut query: Query<&mut Text, With<ConnectionStatus>>,
+    config: Res<GameConfig>,
+    websocket: Res<WebSocketState>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    let status = if websocket.ready_state == ReadyState::OPEN {
+        Dict {
+            ja: "接続状態: 接続中",
+            en: "Status: Connected",
+        }
+    } else {
+        Dict {
+            ja: "接続状態: 未接続 (アリーナに入ると接続します)",
+            en: "Status: Disconnected (connects when entering the arena)",
+        }
+    };
+    let status = status.get(config.language);
+    if text.0 != status {
+        text.0 = status;
+    }
+}
+
 pub struct NameInputPagePlugin;
 
 impl Plugin for NameInputPagePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::NameInput), setup);
+        app.add_systems(
+            Update,
+            (focus, update_connection_status).run_if(in_state(GameState::NameInput)),
+        );
         app.init_resource::<ButtonShots>();
     }
 }
@@ -7,7 +7,7 @@ use bevy::prelude::*;
 
 fn on_enter_warp(mut overlay_writer: EventWriter<OverlayEvent>, next_level: Res<CurrentLevel>) {
     overlay_writer.send(OverlayEvent::Close(match next_level.next_level {
-        GameLevel::Level(_) => GameState::InGame,
+        GameLevel::Level(_) | GameLevel::Branch(..) => GameState::InGame,
         GameLevel::MultiPlayArena => GameState::NameInput,
     }));
 }
@@ -1,7 +1,7 @@
 use bevy::ecs::query::QuerySingleError;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::GameConfig,
     constant::{MAX_ITEMS_IN_EQUIPMENT, MAX_WANDS},
     controller::player::{Equipment, Player},
     entity::{actor::Actor, life::Life},
@@ -12,7 +12,7 @@ use crate::{
     wand::{Wand, WandSpell, WandType},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerState {
     pub name: String,
     pub life: i32,
@@ -38,16 +38,17 @@ impl PlayerState {
 
     pub fn from(
         props: Result<(&Player, &Actor, &Life), QuerySingleError>,
-        config: &GameConfig,
+        player_name: &str,
     ) -> Self {
         if let Ok((player, actor, life)) = props {
             PlayerState::new(player, actor, life)
         } else {
-            PlayerState::from_config(config)
+            PlayerState::initial(player_name)
         }
     }
 
-    pub fn from_config(config: &GameConfig) -> Self {
+    /// 新しい周回を始めたときのプレイヤーの状態です
+    pub fn initial(player_name: &str) -> Self {
         let mut inventory = Inventory::new();
         inventory.insert_free(InventoryItemType::Spell(SpellType::MagicBolt));
         inventory.insert_free(InventoryItemType::Spell(SpellType::MagicBolt));
@@ -159,7 +160,7 @@ impl PlayerState {
         ];
 
         PlayerState {
-            name: config.player_name.clone(),
+            name: player_name.to_string(),
             life: 60,
             max_life: 60,
             inventory,
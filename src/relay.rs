@@ -0,0 +1,143 @@
+// マルチプレイの開発やテストのためにローカルで動かす中継サーバーです
+// 接続したURLのパスをルームとして扱い、受信したメッセージを同じルームにいる他のクライアントへそのまま転送します
+// メッセージの中身は解釈しないので、RemoteMessage の形式が変わってもサーバーを変更する必要はありません
+// 遅延やパケットロスを擬似的に加えて、通信状態の悪い環境を再現することもできます
+// ルームへの登録が完了すると、クライアントに JOINED_PING の Ping を送ります
+
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RelayConfig {
+    /// メッセージを転送するまでに加える遅延です
+    pub latency: Duration,
+
+    /// 転送するメッセージを受信者ごとに破棄する確率です
+    /// 0.0 ですべて転送し、1.0 ですべて破棄します
+    pub loss: f32,
+}
+
+/// ルームへの登録が完了したときにクライアントへ送る Ping の内容です
+/// Ping はブラウザや WebSocket のライブラリが自動で応答するので、ゲームのクライアントには影響しません
+pub const JOINED_PING: &[u8] = b"joined";
+
+/// 転送するメッセージと、それを送信してよい時刻です
+type Delayed = (Instant, Message);
+
+/// ルームの名前から、そのルームにいるクライアントの送信待ちの列への対応です
+type Rooms = Arc<Mutex<HashMap<String, HashMap<u64, UnboundedSender<Delayed>>>>>;
+
+/// 指定したリスナーで接続を待ち受け、中継を開始します
+/// リスナーでエラーが起きない限り終了しません
+pub async fn run_relay(listener: TcpListener, config: RelayConfig) -> std::io::Result<()> {
+    let rooms: Rooms = Arc::new(Mutex::new(HashMap::new()));
+    let mut next_id = 0;
+    loop {
+        let (stream, address) = listener.accept().await?;
+        next_id += 1;
+        tokio::spawn(handle_connection(
+            stream,
+            address,
+            next_id,
+            rooms.clone(),
+            config,
+        ));
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    address: SocketAddr,
+    id: u64,
+    rooms: Rooms,
+    config: RelayConfig,
+) {
+    let mut room = String::new();
+    let callback = |request: &Request, response: Response| {
+        room = request.uri().path().to_string();
+        Ok(response)
+    };
+    let websocket = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(websocket) => websocket,
+        Err(e) => {
+            println!("handshake failed: {}: {}", address, e);
+            return;
+        }
+    };
+    println!("client {} joined room {} from {}", id, room, address);
+
+    let (mut sink, mut source) = websocket.split();
+
+    // 他のクライアントからの転送は、チャンネルを経由してこのタスクから送信します
+    // 遅延はすべてのメッセージで同じなので、受信した順に送信時刻まで待つことで順序を保ったまま遅らせることができます
+    let (sender, mut receiver) = unbounded_channel::<Delayed>();
+    let _ = sender.send((Instant::now(), Message::Ping(JOINED_PING.to_vec())));
+    rooms
+        .lock()
+        .unwrap()
+        .entry(room.clone())
+        .or_default()
+        .insert(id, sender);
+    let forward = tokio::spawn(async move {
+        while let Some((deadline, message)) = receiver.recv().await {
+            tokio::time::sleep_until(deadline).await;
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = source.next().await {
+        match message {
+            Message::Binary(_) | Message::Text(_) => {
+                broadcast(&rooms, &room, id, message, config);
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    let mut rooms = rooms.lock().unwrap();
+    if let Some(members) = rooms.get_mut(&room) {
+        members.remove(&id);
+        if members.is_empty() {
+            rooms.remove(&room);
+        }
+    }
+    forward.abort();
+    println!("client {} left room {}", id, room);
+}
+
+/// 送信者以外の同じルームのクライアントにメッセージを転送します
+fn broadcast(rooms: &Rooms, room: &str, sender: u64, message: Message, config: RelayConfig) {
+    let members: Vec<UnboundedSender<Delayed>> = rooms
+        .lock()
+        .unwrap()
+        .get(room)
+        .map(|members| {
+            members
+                .iter()
+                .filter(|(id, _)| **id != sender)
+                .map(|(_, member)| member.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let deadline = Instant::now() + config.latency;
+    let mut rng = rand::thread_rng();
+    for member in members {
+        if 0.0 < config.loss && rng.gen::<f32>() < config.loss {
+            continue;
+        }
+        let _ = member.send((deadline, message.clone()));
+    }
+}
@@ -0,0 +1,548 @@
+use crate::codex::Codex;
+use crate::config::CONFIG_KEY;
+use crate::controller::player::{Equipment, Player};
+use crate::entity::actor::Actor;
+use crate::entity::life::Life;
+use crate::inventory::{Inventory, InventoryItem};
+use crate::inventory_item::InventoryItemType;
+use crate::language::Dict;
+use crate::level::meta::LevelMetadata;
+use crate::level::{CurrentLevel, GameLevel};
+use crate::player_state::PlayerState;
+use crate::spell::SpellType;
+use crate::states::GameState;
+use crate::stats::Stats;
+use crate::wand::{Wand, WandSpell};
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use bevy_pkv::PkvStore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// セーブデータの形式のバージョンです
+/// 形式を変更したときはこの値を増やし、MIGRATIONS にひとつ前の形式から変換する関数を追加します
+const SAVE_VERSION: u32 = 2;
+
+/// MIGRATIONS[i] はバージョン i + 1 のセーブデータをバージョン i + 2 の形式に変換します
+/// 呪文や装備の名前を変更したときは、ここで古い名前を新しい名前に置き換えます
+/// 削除された呪文や装備は、変換のあとでアイテムごと取り除かれるので、ここで扱う必要はありません
+const MIGRATIONS: [fn(&mut Value); (SAVE_VERSION - 1) as usize] = [migrate_run_to_slot];
+
+/// セーブスロットの数です
+pub const MAX_SAVE_SLOTS: usize = 4;
+
+/// 選択中のスロットの番号を保存するキーです
+const CURRENT_SLOT_KEY: &str = "slot";
+
+/// スロットに分かれる前に、ひとつだけ保存されていた周回のキーです
+const LEGACY_RUN_KEY: &str = "run";
+
+fn slot_key(index: usize) -> String {
+    format!("slot{}", index)
+}
+
+/// 中断した周回の状態です
+/// 呪文や装備の種類は名前の文字列で保存されます
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveData {
+    /// 再開したときに入るレベルです
+    pub level: GameLevel,
+
+    /// この周回でこれまでに訪れたレベルです
+    pub path: Vec<GameLevel>,
+
+    pub player: PlayerState,
+}
+
+/// ひとつのセーブスロットです
+/// プレイヤー名、中断した周回、周回をまたいで引き継がれる記録をそれぞれ独立して持ちます
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveSlot {
+    pub version: u32,
+
+    #[serde(default)]
+    pub player_name: String,
+
+    /// 最後に保存した日時(UNIX時間の秒)です
+    /// 一度も保存していないスロットでは None になります
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+
+    /// 累計のプレイ時間(秒)です
+    #[serde(default)]
+    pub playtime: f64,
+
+    /// 最後に保存したときにいたレベルの名前です
+    #[serde(default)]
+    pub level_name: Option<Dict<String>>,
+
+    /// エンドレスモードで到達した最も深い深度です
+    #[serde(default)]
+    pub best_depth: i32,
+
+    /// 周回をまたいで引き継がれる図鑑の記録です
+    #[serde(default)]
+    pub codex: Codex,
+
+    /// 周回をまたいで累計される統計と、解除した実績です
+    #[serde(default)]
+    pub stats: Stats,
+
+    /// 保管箱に預けたアイテムです
+    /// 周回が終わっても失われません
+    #[serde(default = "Inventory::new")]
+    pub stash: Inventory,
+
+    /// 中断した周回です
+    /// プレイヤーが倒れて周回が終わったときは None になります
+    #[serde(default)]
+    pub run: Option<SaveData>,
+}
+
+impl Default for SaveSlot {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            player_name: "".to_string(),
+            timestamp: None,
+            playtime: 0.0,
+            level_name: None,
+            best_depth: 0,
+            codex: Codex::default(),
+            stats: Stats::default(),
+            stash: Inventory::new(),
+            run: None,
+        }
+    }
+}
+
+impl SaveSlot {
+    pub fn is_empty(&self) -> bool {
+        self.timestamp.is_none()
+            && self.run.is_none()
+            && self.player_name.is_empty()
+            && self.best_depth == 0
+            && self.codex.seen.is_empty()
+            && self.codex.acquired.is_empty()
+            && self.stats.achievements.is_empty()
+            && self.stash.0.iter().all(|item| item.is_none())
+    }
+
+    /// 古いバージョンのセーブデータを現在の形式に変換してから読み込みます
+    /// 読み込めない呪文や装備、杖はアイテムごと取り除かれます
+    /// 現在より新しいバージョンのセーブデータや、壊れたセーブデータの場合は None を返します
+    pub fn from_json(json: &str) -> Option<Self> {
+        let mut value: Value = serde_json::from_str(json).ok()?;
+        let version = value.get("version")?.as_u64()? as u32;
+        if version == 0 || SAVE_VERSION < version {
+            warn!("Unsupported save data version: {}", version);
+            return None;
+        }
+        for migration in MIGRATIONS.iter().skip(version as usize - 1) {
+            migration(&mut value);
+        }
+        value["version"] = Value::from(SAVE_VERSION);
+
+        if let Some(player) = value.get_mut("run").and_then(|r| r.get_mut("player")) {
+            remove_unknown_items(player);
+        }
+        if let Some(stash) = value.get_mut("stash") {
+            remove_unknown::<InventoryItem>(stash);
+        }
+        if let Some(codex) = value.get_mut("codex") {
+            for key in ["seen", "acquired"] {
+                if let Some(Value::Array(items)) = codex.get_mut(key) {
+                    items.retain(|item| {
+                        serde_json::from_value::<InventoryItemType>(item.clone()).is_ok()
+                    });
+                }
+            }
+        }
+        if let Some(Value::Array(casts)) = value.get_mut("stats").and_then(|s| s.get_mut("casts")) {
+            casts.retain(|cast| serde_json::from_value::<(SpellType, u32)>(cast.clone()).is_ok());
+        }
+
+        match serde_json::from_value(value) {
+            Ok(slot) => Some(slot),
+            Err(err) => {
+                warn!("Failed to load save data: {}", err);
+                None
+            }
+        }
+    }
+
+    /// 累計のプレイ時間を 時:分 の形式で返します
+    pub fn playtime_text(&self) -> String {
+        let minutes = (self.playtime / 60.0) as u64;
+        format!("{}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    /// 最後に保存した日時を 年/月/日 時:分 (UTC) の形式で返します
+    pub fn timestamp_text(&self) -> String {
+        let Some(timestamp) = self.timestamp else {
+            return "-".to_string();
+        };
+        let days = (timestamp / 86400) as i64;
+        let seconds = timestamp % 86400;
+
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!(
+            "{}/{:02}/{:02} {:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60
+        )
+    }
+}
+
+/// バージョン1では中断した周回だけがひとつ保存されていたので、それをスロットの run に移します
+/// 保存した日時は記録されていなかったので、不明として扱います
+fn migrate_run_to_slot(value: &mut Value) {
+    let mut run = value.take();
+    if let Some(run) = run.as_object_mut() {
+        run.remove("version");
+    }
+    *value = serde_json::json!({
+        "version": 2,
+        "timestamp": null,
+        "run": run,
+    });
+}
+
+/// プレイヤーの状態のうち、読み込めないアイテムを取り除きます
+fn remove_unknown_items(player: &mut Value) {
+    if let Some(inventory) = player.get_mut("inventory") {
+        remove_unknown::<InventoryItem>(inventory);
+    }
+    if let Some(equipments) = player.get_mut("equipments") {
+        remove_unknown::<Equipment>(equipments);
+    }
+    if let Some(wands) = player.get_mut("wands") {
+        if let Value::Array(wands) = wands {
+            for wand in wands.iter_mut() {
+                if let Some(slots) = wand.get_mut("slots") {
+                    remove_unknown::<WandSpell>(slots);
+                }
+            }
+        }
+        remove_unknown::<Wand>(wands);
+    }
+}
+
+/// 配列の要素のうち、指定した型として読み込めないものを空きに置き換えます
+fn remove_unknown<T: DeserializeOwned>(items: &mut Value) {
+    let Value::Array(items) = items else {
+        return;
+    };
+    for item in items.iter_mut() {
+        if !item.is_null() && serde_json::from_value::<T>(item.clone()).is_err() {
+            warn!("Removed unknown item from save data: {}", item);
+            *item = Value::Null;
+        }
+    }
+}
+
+/// すべてのセーブスロットと、選択中のスロットです
+/// 永続化が有効な場合、変更されるたびに bevy_pkv で保存されます
+/// bevy_pkv はデスクトップではファイルに、WASM ではブラウザのローカルストレージに保存します
+#[derive(Resource, Default)]
+pub struct SaveSlots {
+    pub slots: [SaveSlot; MAX_SAVE_SLOTS],
+    pub current: usize,
+}
+
+impl SaveSlots {
+    pub fn current(&self) -> &SaveSlot {
+        &self.slots[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut SaveSlot {
+        &mut self.slots[self.current]
+    }
+
+    /// スロットを空き状態に戻します
+    pub fn delete(&mut self, index: usize) {
+        self.slots[index] = SaveSlot::default();
+    }
+
+    /// スロットを最初の空きスロットに複製し、その番号を返します
+    /// 空きスロットがない場合は何もせずに None を返します
+    pub fn duplicate(&mut self, index: usize) -> Option<usize> {
+        let empty = self.slots.iter().position(|s| s.is_empty())?;
+        self.slots[empty] = self.slots[index].clone();
+        Some(empty)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[allow(dead_code)]
+fn startup(pkv: Res<PkvStore>, mut slots: ResMut<SaveSlots>) {
+    let mut found = false;
+    for index in 0..MAX_SAVE_SLOTS {
+        if let Ok(v) = pkv.get::<String>(&slot_key(index)) {
+            if let Some(slot) = SaveSlot::from_json(v.as_str()) {
+                slots.slots[index] = slot;
+                found = true;
+            }
+        }
+    }
+    if let Ok(current) = pkv.get::<String>(CURRENT_SLOT_KEY) {
+        if let Ok(current) = current.parse::<usize>() {
+            slots.current = current.min(MAX_SAVE_SLOTS - 1);
+        }
+    }
+
+    // スロットに分かれる前のセーブデータと設定から、最初のスロットを作ります
+    // GameConfigPlugin の on_change は Update で設定を現在の形式で書き直し、プレイヤー名と最深記録を取り除きます
+    // この処理は Startup で行うので、書き直される前の元の設定を読み込めます
+    // GameConfigPlugin の startup は設定を書き換えないので、同じ Startup の中での順序は問いません
+    if !found {
+        let slot = &mut slots.slots[0];
+        if let Ok(v) = pkv.get::<String>(LEGACY_RUN_KEY) {
+            if let Some(legacy) = SaveSlot::from_json(v.as_str()) {
+                *slot = legacy;
+            }
+        }
+        if let Ok(v) = pkv.get::<String>(CONFIG_KEY) {
+            if let Ok(config) = serde_json::from_str::<Value>(v.as_str()) {
+                if let Some(name) = config.get("player_name").and_then(|v| v.as_str()) {
+                    slot.player_name = name.to_string();
+                }
+                if let Some(depth) = config.get("best_depth").and_then(|v| v.as_i64()) {
+                    slot.best_depth = depth as i32;
+                }
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn on_change(mut pkv: ResMut<PkvStore>, slots: Res<SaveSlots>) {
+    if slots.is_changed() {
+        for (index, slot) in slots.slots.iter().enumerate() {
+            if let Ok(serialized) = serde_json::to_string(slot) {
+                if let Err(err) = pkv.set::<String>(&slot_key(index), &serialized) {
+                    warn!("Failed to save slot {}: {}", index, err);
+                }
+            } else {
+                warn!("Failed to serialize slot {}", index);
+            }
+        }
+        if let Err(err) = pkv.set::<String>(CURRENT_SLOT_KEY, &slots.current.to_string()) {
+            warn!("Failed to save current slot: {}", err);
+        }
+    }
+}
+
+/// レベルの移動を待たずに、その時点の周回の状態を保存します
+/// 保管箱の中身のように周回の外に保存されるものを変更したときは、
+/// 中断した周回と食い違わないよう、このイベントで周回も保存し直します
+#[derive(Event)]
+pub struct SaveRunEvent;
+
+/// プレイヤーが新しいレベルに入ったときに、その時点の状態を選択中のスロットに自動で保存します
+/// 魔法陣でフェードして移動した場合も、フェードせずに移動した場合も、レベルに入った時点で保存されます
+/// 新しい周回を始めた場合も、最初のレベルに入った時点で以前の周回が上書きされます
+/// マルチプレイヤーのアリーナでは保存しません
+fn autosave(
+    mut reader: EventReader<SaveRunEvent>,
+    current: Res<CurrentLevel>,
+    metadata: Res<LevelMetadata>,
+    mut slots: ResMut<SaveSlots>,
+    player_query: Query<(Ref<Player>, &Actor, &Life)>,
+) {
+    let level = current.next_level;
+    if level == GameLevel::MultiPlayArena {
+        return;
+    }
+    let Ok((player, actor, life)) = player_query.get_single() else {
+        return;
+    };
+    let saved_level = slots.current().run.as_ref().map(|r| r.level);
+    let requested = 0 < reader.read().count();
+    if !requested && !player.is_added() && saved_level == Some(level) {
+        return;
+    }
+    if 0 < life.life {
+        let slot = slots.current_mut();
+        slot.timestamp = Some(now());
+        slot.level_name = Some(metadata.get(level).name.clone());
+        slot.run = Some(SaveData {
+            level,
+            path: current.path.clone(),
+            player: PlayerState::new(&player, actor, life),
+        });
+    }
+}
+
+/// 選択中のスロットのプレイ時間を数えます
+/// 毎フレームの保存を避けるため、変更検出を通さずに加算し、次に保存するときに一緒に書き込みます
+fn count_playtime(time: Res<Time>, mut slots: ResMut<SaveSlots>) {
+    slots.bypass_change_detection().current_mut().playtime += time.delta_secs_f64();
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveSlots>();
+        app.add_event::<SaveRunEvent>();
+        #[cfg(any(not(debug_assertions), target_arch = "wasm32", feature = "save"))]
+        app.add_systems(Startup, startup);
+        #[cfg(any(not(debug_assertions), target_arch = "wasm32", feature = "save"))]
+        app.add_systems(Update, on_change);
+        app.add_systems(
+            Update,
+            (autosave, count_playtime).run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equipment::EquipmentType;
+    use crate::wand::WandType;
+    use serde_json::json;
+
+    /// スロットに分かれる前の、中断した周回だけを保存していたバージョン1のセーブデータです
+    fn version_1_run() -> Value {
+        let mut value = serde_json::to_value(SaveData {
+            level: GameLevel::Level(3),
+            path: vec![GameLevel::Level(0), GameLevel::Level(3)],
+            player: PlayerState::initial("alice"),
+        })
+        .unwrap();
+        value["version"] = json!(1);
+        value
+    }
+
+    #[test]
+    fn load_version_1_run() {
+        let slot = SaveSlot::from_json(&version_1_run().to_string()).unwrap();
+        assert_eq!(slot.version, SAVE_VERSION);
+        assert!(slot.timestamp.is_none());
+        assert_eq!(slot.timestamp_text(), "-");
+        let run = slot.run.unwrap();
+        assert_eq!(run.level, GameLevel::Level(3));
+        assert_eq!(run.path, vec![GameLevel::Level(0), GameLevel::Level(3)]);
+        assert_eq!(run.player.name, "alice");
+        assert_eq!(
+            run.player.inventory,
+            PlayerState::initial("alice").inventory
+        );
+    }
+
+    #[test]
+    fn remove_unknown_items_instead_of_failing() {
+        let mut value = version_1_run();
+        let player = &mut value["player"];
+        player["inventory"][0] = json!({ "item_type": { "Spell": "Removed" }, "price": 10 });
+        player["inventory"][1] = json!({ "item_type": { "Equipment": "Removed" }, "price": 10 });
+        player["inventory"][2] = json!({ "item_type": { "Wand": "Removed" }, "price": 10 });
+        player["equipments"][0] = json!({ "equipment_type": "Removed", "price": 0 });
+        player["equipments"][1] = json!({ "equipment_type": "SpikeBoots", "price": 0 });
+        player["wands"][0]["slots"][1] = json!({ "spell_type": "Removed", "price": 0 });
+        player["wands"][1] = player["wands"][0].clone();
+        player["wands"][1]["wand_type"] = json!("Removed");
+
+        let player = SaveSlot::from_json(&value.to_string())
+            .unwrap()
+            .run
+            .unwrap()
+            .player;
+        assert!(player.inventory.0[0].is_none());
+        assert!(player.inventory.0[1].is_none());
+        assert!(player.inventory.0[2].is_none());
+        assert_eq!(
+            player.inventory.0[3],
+            PlayerState::initial("alice").inventory.0[3]
+        );
+        assert!(player.equipments[0].is_none());
+        assert!(matches!(
+            player.equipments[1],
+            Some(Equipment {
+                equipment_type: EquipmentType::SpikeBoots,
+                ..
+            })
+        ));
+        let wand = player.wands[0].as_ref().unwrap();
+        assert!(matches!(wand.wand_type, WandType::CypressWand));
+        assert!(matches!(
+            wand.slots[0],
+            Some(WandSpell {
+                spell_type: SpellType::MagicBolt,
+                ..
+            })
+        ));
+        assert!(wand.slots[1].is_none());
+        assert!(player.wands[1].is_none());
+    }
+
+    #[test]
+    fn remove_unknown_records() {
+        let json = json!({
+            "version": SAVE_VERSION,
+            "stash": [{ "item_type": { "Spell": "Removed" }, "price": 0 }, { "item_type": { "Spell": "MagicBolt" }, "price": 0 }],
+            "codex": { "seen": [{ "Spell": "Removed" }, { "Spell": "Heal" }], "acquired": [{ "Wand": "Removed" }] },
+            "stats": { "casts": [["Removed", 3], ["MagicBolt", 5]] },
+        });
+        let slot = SaveSlot::from_json(&json.to_string()).unwrap();
+        assert!(slot.stash.0[0].is_none());
+        assert!(slot.stash.0[1].is_some());
+        assert_eq!(
+            slot.codex.seen,
+            vec![InventoryItemType::Spell(SpellType::Heal)]
+        );
+        assert!(slot.codex.acquired.is_empty());
+        assert_eq!(slot.stats.casts, vec![(SpellType::MagicBolt, 5)]);
+    }
+
+    #[test]
+    fn format_timestamp() {
+        let text = |timestamp| {
+            SaveSlot {
+                timestamp,
+                ..default()
+            }
+            .timestamp_text()
+        };
+        assert_eq!(text(None), "-");
+        assert_eq!(text(Some(0)), "1970/01/01 00:00");
+        assert_eq!(text(Some(951782400)), "2000/02/29 00:00");
+        assert_eq!(text(Some(1700000000)), "2023/11/14 22:13");
+        assert_eq!(text(Some(1704067199)), "2023/12/31 23:59");
+    }
+
+    #[test]
+    fn reject_newer_version() {
+        let json = json!({ "version": SAVE_VERSION + 1 });
+        assert!(SaveSlot::from_json(&json.to_string()).is_none());
+    }
+
+    #[test]
+    fn reject_broken_data() {
+        assert!(SaveSlot::from_json("not json").is_none());
+        assert!(SaveSlot::from_json(r#"{"player_name":"alice"}"#).is_none());
+        assert!(SaveSlot::from_json(r#"{"version":0}"#).is_none());
+    }
+}
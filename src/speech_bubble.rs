@@ -18,6 +18,10 @@ const SPEECH_BUBBLE_HEIGHT: f32 = 64.0;
 pub struct SpeechBubble {
     count: usize,
     text: String,
+
+    /// 吹き出しを表示するワールド座標です
+    /// None の場合はウサギの頭上に表示します
+    anchor: Option<Vec2>,
 }
 
 #[derive(Component)]
@@ -26,6 +30,10 @@ pub struct SpeechBubbleText;
 #[derive(Event)]
 pub enum SpeechEvent {
     Speech(Dict<String>),
+
+    /// 指定したワールド座標に吹き出しを表示します
+    SpeechAt(Dict<String>, Vec2),
+
     Close,
 }
 
@@ -36,6 +44,7 @@ pub fn spawn_speech_bubble(parent: &mut Commands, assets: &Res<GameAssets>) {
             SpeechBubble {
                 count: 0,
                 text: "".to_string(),
+                anchor: None,
             },
             AseUiSlice {
                 aseprite: assets.atlas.clone(),
@@ -68,19 +77,22 @@ pub fn spawn_speech_bubble(parent: &mut Commands, assets: &Res<GameAssets>) {
 }
 
 fn update_speech_bubble(
-    mut speech_query: Query<&mut Node, With<SpeechBubble>>,
+    mut speech_query: Query<(&mut Node, &SpeechBubble)>,
     rabbit_query: Query<&GlobalTransform, With<Rabbit>>,
     camera_query: Query<(&Camera, &GlobalTransform)>,
 ) {
-    if let Ok(mut speech) = speech_query.get_single_mut() {
-        if let Ok(rabbit) = rabbit_query.get_single() {
+    if let Ok((mut node, speech)) = speech_query.get_single_mut() {
+        let position = match speech.anchor {
+            Some(anchor) => Some(anchor.extend(0.0)),
+            None => rabbit_query.get_single().ok().map(|r| r.translation()),
+        };
+        if let Some(position) = position {
             let (camera, camera_transform) = camera_query.single();
-            if let Ok(p) = camera.world_to_viewport(
-                camera_transform,
-                rabbit.translation() + Vec3::new(0.0, 20.0, 0.0),
-            ) {
-                speech.left = Val::Px(p.x - SPEECH_BUBBLE_WIDTH * 0.5 * SCALE);
-                speech.top = Val::Px(p.y - 128.0 * 0.5 * SCALE);
+            if let Ok(p) =
+                camera.world_to_viewport(camera_transform, position + Vec3::new(0.0, 20.0, 0.0))
+            {
+                node.left = Val::Px(p.x - SPEECH_BUBBLE_WIDTH * 0.5 * SCALE);
+                node.top = Val::Px(p.y - 128.0 * 0.5 * SCALE);
             }
         }
     }
@@ -99,6 +111,13 @@ fn read_speech_events(
                 *visibility = Visibility::Inherited;
                 speech.count = 0;
                 speech.text = s.get(config.language).to_string();
+                speech.anchor = None;
+            }
+            SpeechEvent::SpeechAt(s, position) => {
+                *visibility = Visibility::Inherited;
+                speech.count = 0;
+                speech.text = s.get(config.language).to_string();
+                speech.anchor = Some(*position);
             }
             SpeechEvent::Close => {
                 *visibility = Visibility::Hidden;
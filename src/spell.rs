@@ -1,7 +1,8 @@
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
 use crate::{entity::servant_seed::ServantType, language::{Dict, Languages}};
 
-#[derive(Reflect, Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, strum::EnumIter)]
+#[derive(Reflect, Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, strum::EnumIter, Serialize, Deserialize)]
 pub enum SpellType {
     MagicBolt,
     PurpleBolt,
@@ -18,6 +19,7 @@ pub enum SpellType {
     SummonFriendEyeball,
     SummonEnemyEyeball,
     Dash,
+    Bridge,
 }
 
 
@@ -57,7 +59,14 @@ pub enum SpellCast {
     Homing,
     HeavyShot,
     Summon { friend: bool, servant_type: ServantType } ,
-    Dash
+    Dash,
+
+    /// 杖の向きに沿って、水や溶岩、穴を石の床に変えます
+    /// 壁に突き当たるとそこで止まります
+    Bridge {
+        /// 橋の最大の長さ(タイル数)
+        length: i32,
+    },
 }
 
 /// 呪文の基礎情報
@@ -293,6 +302,18 @@ impl SpellType {
                 price: 500,
                 cast: SpellCast::Dash,
             },
+            SpellType::Bridge => SpellProps {
+                name: Dict {
+                    ja: "石橋",
+                    en: "Stone Bridge",
+                },
+                description: Dict { ja: "杖の向きに石の床を伸ばし、水や溶岩、穴の上に橋を架けます。",
+                en: "Extends a stone floor in the aimed direction, bridging water, lava and pits." },
+                cast_delay: 60,
+                icon: "magic_star0",
+                price: 300,
+                cast: SpellCast::Bridge { length: 6 },
+            },
         }
     }
 }   
@@ -332,6 +353,11 @@ const HEAL_TEXT: Dict<&'static str> = Dict {
     ja: "回復",
     en: "Heal",
 };
+
+const LENGTH: Dict<&'static str> = Dict {
+    ja: "長さ",
+    en: "Length",
+};
  
 
 
@@ -374,6 +400,7 @@ pub fn get_spell_appendix(cast: SpellCast, language: Languages) -> String {
         SpellCast::HeavyShot => format!("威力: +5"),
         SpellCast::Summon {..} => format!(""),
         SpellCast::Dash {..} => format!(""),
+        SpellCast::Bridge { length } => format!("{}:{}", LENGTH.get(language), length),
     }
 }
 
@@ -31,6 +31,12 @@ pub enum GameState {
 
     // エンディング画面
     Ending,
+
+    /// 図鑑画面
+    Codex,
+
+    /// 実績画面
+    Achievements,
     //
     // 画面を追加したら OverlayPlugin や update_pointer_image_by_angle にも変更が必要
 }
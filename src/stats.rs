@@ -0,0 +1,414 @@
+use crate::asset::GameAssets;
+use crate::controller::player::Player;
+use crate::enemy::huge_slime::Boss;
+use crate::entity::actor::Actor;
+use crate::language::Dict;
+use crate::level::GameLevel;
+use crate::save::SaveSlots;
+use crate::spell::SpellType;
+use crate::states::GameState;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 周回をまたいで累計されるプレイヤーの記録です
+/// セーブスロットごとに保存されます
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Stats {
+    /// 倒した敵の数を、敵の種類ごとに数えたものです
+    #[serde(default)]
+    pub kills: Vec<(String, u32)>,
+
+    /// プレイヤーの弾丸が与えたダメージの合計です
+    #[serde(default)]
+    pub damage_dealt: u32,
+
+    /// プレイヤーが受けたダメージの合計です
+    #[serde(default)]
+    pub damage_taken: u32,
+
+    /// プレイヤーが詠唱した回数を、呪文の種類ごとに数えたものです
+    #[serde(default)]
+    pub casts: Vec<(SpellType, u32)>,
+
+    #[serde(default)]
+    pub gold_earned: u32,
+
+    #[serde(default)]
+    pub gold_spent: u32,
+
+    /// プレイヤーが倒れた回数を、倒れたレベルごとに数えたものです
+    #[serde(default)]
+    pub deaths: Vec<(GameLevel, u32)>,
+
+    /// ボスが現れてから倒すまでにかかった最短の時間(秒)です
+    #[serde(default)]
+    pub best_boss_clear_time: Option<f32>,
+
+    /// アリーナでほかのプレイヤーを倒した回数です
+    #[serde(default)]
+    pub pvp_kills: u32,
+
+    /// 解除した実績の識別子です
+    #[serde(default)]
+    pub achievements: Vec<String>,
+}
+
+impl Stats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn kills_of(&self, name: &str) -> u32 {
+        self.kills
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn total_casts(&self) -> u32 {
+        self.casts.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.deaths.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.achievements.iter().any(|id| *id == achievement.id)
+    }
+}
+
+/// 種類ごとの回数に加算します
+fn count<K: PartialEq>(counts: &mut Vec<(K, u32)>, key: K, amount: u32) {
+    match counts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, count)) => *count += amount,
+        None => counts.push((key, amount)),
+    }
+}
+
+/// 実績を解除する条件です
+#[derive(Clone, Debug, Deserialize)]
+pub enum Condition {
+    /// 種類を問わず、敵を指定した数だけ倒します
+    Kills(u32),
+
+    /// 指定した名前の敵を指定した数だけ倒します
+    KillsOf(String, u32),
+
+    DamageDealt(u32),
+
+    DamageTaken(u32),
+
+    Casts(u32),
+
+    GoldEarned(u32),
+
+    GoldSpent(u32),
+
+    Deaths(u32),
+
+    /// ボスを指定した秒数以内に倒します
+    BossClearWithin(f32),
+
+    PvpKills(u32),
+}
+
+impl Condition {
+    pub fn is_satisfied(&self, stats: &Stats) -> bool {
+        match self {
+            Condition::Kills(n) => *n <= stats.total_kills(),
+            Condition::KillsOf(name, n) => *n <= stats.kills_of(name),
+            Condition::DamageDealt(n) => *n <= stats.damage_dealt,
+            Condition::DamageTaken(n) => *n <= stats.damage_taken,
+            Condition::Casts(n) => *n <= stats.total_casts(),
+            Condition::GoldEarned(n) => *n <= stats.gold_earned,
+            Condition::GoldSpent(n) => *n <= stats.gold_spent,
+            Condition::Deaths(n) => *n <= stats.total_deaths(),
+            Condition::BossClearWithin(seconds) => stats
+                .best_boss_clear_time
+                .map_or(false, |time| time <= *seconds),
+            Condition::PvpKills(n) => *n <= stats.pvp_kills,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    /// セーブデータに保存される識別子です
+    /// 一度公開した実績の識別子は変更しないでください
+    pub id: String,
+    pub name: Dict<String>,
+    pub description: Dict<String>,
+    pub condition: Condition,
+}
+
+/// 実績の一覧です
+/// 実績ページにはこの順に並びます
+/// assets/game.achievements.json から読み込まれ、読み込みが完了するとリソースとしても登録されます
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug)]
+pub struct Achievements(pub Vec<Achievement>);
+
+#[derive(Event, Clone, Debug)]
+pub enum StatsEvent {
+    /// 敵が倒されました
+    /// 敵の種類は Name の文字列で区別します
+    Kill(String),
+
+    /// 弾丸がアクターに命中しました
+    /// プレイヤーが関係しないものは記録されません
+    Damage {
+        attacker: Option<Uuid>,
+        victim: Uuid,
+        damage: i32,
+    },
+
+    /// アクターが呪文を詠唱しました
+    /// プレイヤー以外の詠唱は記録されません
+    Cast {
+        caster: Uuid,
+        spell: SpellType,
+    },
+
+    EarnGold(u32),
+
+    SpendGold(u32),
+
+    /// プレイヤーが倒れました
+    Die(GameLevel),
+
+    DefeatBoss,
+
+    /// アリーナでプレイヤーの弾丸がほかのプレイヤーにとどめを刺しました
+    PvpKill,
+}
+
+/// 実績が解除されたことを通知します
+/// 値は Achievements での番号です
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AchievementEvent(pub usize);
+
+/// ボスが現れた時刻(秒)です
+#[derive(Resource, Default)]
+struct BossTimer(Option<f32>);
+
+/// 記録を選択中のスロットに書き込みます
+/// 倒した数やダメージは頻繁に変わるので、変更検出を通さずに加算し、次に保存するときに一緒に書き込みます
+/// プレイヤーが倒れたときやボスを倒したとき、実績が解除されたときは、すぐに保存します
+fn record_stats(
+    mut reader: EventReader<StatsEvent>,
+    mut writer: EventWriter<AchievementEvent>,
+    mut slots: ResMut<SaveSlots>,
+    mut timer: ResMut<BossTimer>,
+    player_query: Query<&Actor, With<Player>>,
+    boss_query: Query<(), Added<Boss>>,
+    time: Res<Time>,
+    achievements: Res<Achievements>,
+    mut player: Local<Option<Uuid>>,
+) {
+    if !boss_query.is_empty() {
+        timer.0 = Some(time.elapsed_secs());
+    }
+
+    // とどめの一撃を受けたときには、プレイヤーはすでに消滅しているので、最後に見たプレイヤーを使います
+    if let Ok(actor) = player_query.get_single() {
+        *player = Some(actor.uuid);
+    }
+    let player = *player;
+
+    let mut save = false;
+    for event in reader.read() {
+        let stats = &mut slots.bypass_change_detection().current_mut().stats;
+        match event {
+            StatsEvent::Kill(name) => {
+                count(&mut stats.kills, name.clone(), 1);
+            }
+            StatsEvent::Damage {
+                attacker,
+                victim,
+                damage,
+            } => {
+                let damage = (*damage).max(0) as u32;
+                if player.is_some() && *attacker == player {
+                    stats.damage_dealt += damage;
+                }
+                if Some(*victim) == player {
+                    stats.damage_taken += damage;
+                }
+            }
+            StatsEvent::Cast { caster, spell } => {
+                if Some(*caster) == player {
+                    count(&mut stats.casts, *spell, 1);
+                }
+            }
+            StatsEvent::EarnGold(golds) => {
+                stats.gold_earned += golds;
+            }
+            StatsEvent::SpendGold(golds) => {
+                stats.gold_spent += golds;
+            }
+            StatsEvent::Die(level) => {
+                count(&mut stats.deaths, *level, 1);
+                save = true;
+            }
+            StatsEvent::DefeatBoss => {
+                if let Some(start) = timer.0.take() {
+                    let elapsed = time.elapsed_secs() - start;
+                    if stats
+                        .best_boss_clear_time
+                        .map_or(true, |best| elapsed < best)
+                    {
+                        stats.best_boss_clear_time = Some(elapsed);
+                    }
+                }
+                save = true;
+            }
+            StatsEvent::PvpKill => {
+                stats.pvp_kills += 1;
+                save = true;
+            }
+        }
+    }
+
+    let stats = &slots.current().stats;
+    let unlocked: Vec<usize> = achievements
+        .0
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| !stats.is_unlocked(a) && a.condition.is_satisfied(stats))
+        .map(|(index, _)| index)
+        .collect();
+    for index in unlocked.iter() {
+        slots
+            .current_mut()
+            .stats
+            .achievements
+            .push(achievements.0[*index].id.clone());
+        writer.send(AchievementEvent(*index));
+    }
+
+    if save {
+        slots.set_changed();
+    }
+}
+
+fn reset_boss_timer(mut timer: ResMut<BossTimer>) {
+    timer.0 = None;
+}
+
+/// 読み込んだ実績の一覧をリソースとして登録します
+fn setup_achievements(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    achievements_assets: Res<Assets<Achievements>>,
+) {
+    let achievements = achievements_assets
+        .get(&assets.achievements)
+        .expect("achievements are not loaded")
+        .clone();
+    commands.insert_resource(achievements);
+}
+
+#[derive(Default)]
+pub struct AchievementsLoader;
+
+impl AssetLoader for AchievementsLoader {
+    type Asset = Achievements;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["achievements.json"]
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Achievements>();
+        app.init_asset_loader::<AchievementsLoader>();
+        app.init_resource::<BossTimer>();
+        app.add_event::<StatsEvent>();
+        app.add_event::<AchievementEvent>();
+        app.add_systems(OnExit(GameState::Setup), setup_achievements);
+        app.add_systems(OnEnter(GameState::InGame), reset_boss_timer);
+        app.add_systems(Update, record_stats.run_if(in_state(GameState::InGame)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn count_by_kind() {
+        let mut counts: Vec<(&str, u32)> = Vec::new();
+        count(&mut counts, "slime", 1);
+        count(&mut counts, "eyeball", 2);
+        count(&mut counts, "slime", 3);
+        assert_eq!(counts, vec![("slime", 4), ("eyeball", 2)]);
+    }
+
+    #[test]
+    fn satisfy_conditions_at_threshold() {
+        let mut stats = Stats::default();
+        count(&mut stats.kills, "slime".to_string(), 9);
+        count(&mut stats.kills, "eyeball".to_string(), 1);
+        count(&mut stats.casts, SpellType::MagicBolt, 5);
+        count(&mut stats.deaths, GameLevel::Level(1), 2);
+        stats.damage_dealt = 100;
+        stats.gold_spent = 50;
+
+        assert!(Condition::Kills(10).is_satisfied(&stats));
+        assert!(!Condition::Kills(11).is_satisfied(&stats));
+        assert!(Condition::KillsOf("slime".to_string(), 9).is_satisfied(&stats));
+        assert!(!Condition::KillsOf("slime".to_string(), 10).is_satisfied(&stats));
+        assert!(!Condition::KillsOf("enemy_witch".to_string(), 1).is_satisfied(&stats));
+        assert!(Condition::DamageDealt(100).is_satisfied(&stats));
+        assert!(!Condition::DamageTaken(1).is_satisfied(&stats));
+        assert!(Condition::Casts(5).is_satisfied(&stats));
+        assert!(!Condition::GoldEarned(1).is_satisfied(&stats));
+        assert!(Condition::GoldSpent(50).is_satisfied(&stats));
+        assert!(Condition::Deaths(2).is_satisfied(&stats));
+        assert!(!Condition::PvpKills(1).is_satisfied(&stats));
+    }
+
+    #[test]
+    fn require_boss_clear_time() {
+        let mut stats = Stats::default();
+        assert!(!Condition::BossClearWithin(60.0).is_satisfied(&stats));
+        stats.best_boss_clear_time = Some(60.0);
+        assert!(Condition::BossClearWithin(60.0).is_satisfied(&stats));
+        assert!(!Condition::BossClearWithin(59.0).is_satisfied(&stats));
+    }
+
+    #[test]
+    fn load_achievements() {
+        let achievements: Achievements =
+            serde_json::from_str(include_str!("../assets/game.achievements.json")).unwrap();
+        assert!(!achievements.0.is_empty());
+        let ids: HashSet<&str> = achievements.0.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids.len(), achievements.0.len(), "duplicate achievement id");
+
+        let mut stats = Stats::default();
+        assert!(!achievements.0.iter().any(|a| stats.is_unlocked(a)));
+        stats.achievements.push("first_blood".to_string());
+        assert!(stats.is_unlocked(&achievements.0[0]));
+    }
+}
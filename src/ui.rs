@@ -14,7 +14,9 @@ pub mod on_press;
 pub mod pause_menu;
 pub mod player_list;
 pub mod range;
+pub mod servant_list;
 pub mod spell_in_wand;
+pub mod stash;
 pub mod wand_editor;
 pub mod wand_list;
 pub mod wand_sprite;
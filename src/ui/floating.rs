@@ -5,9 +5,10 @@ use crate::{
     controller::player::{Equipment, Player},
     entity::{actor::Actor, dropped_item::spawn_dropped_item},
     hud::DropArea,
-    inventory::InventoryItem,
+    inventory::{Inventory, InventoryItem},
     inventory_item::InventoryItemType,
     level::{tile::Tile, CurrentLevel},
+    save::{SaveRunEvent, SaveSlots},
     se::{SEEvent, SE},
     states::{GameMenuState, GameState},
     wand::{Wand, WandSpell},
@@ -20,12 +21,16 @@ pub enum FloatingContent {
     WandSpell(usize, usize),
     Wand(usize),
     Equipment(usize),
+
+    /// 保管箱のスロットです
+    Stash(usize),
 }
 
 impl FloatingContent {
-    pub fn get_item(&self, actor: &Actor) -> Option<InventoryItem> {
+    pub fn get_item(&self, actor: &Actor, stash: &Inventory) -> Option<InventoryItem> {
         match self {
             FloatingContent::Inventory(index) => actor.inventory.get(*index),
+            FloatingContent::Stash(index) => stash.get(*index),
             FloatingContent::WandSpell(wand_index, spell_index) => actor.wands[*wand_index]
                 .clone()
                 .and_then(|ref wand| match wand.slots[*spell_index] {
@@ -77,10 +82,12 @@ pub fn spawn_inventory_floating(mut builder: &mut ChildBuilder, assets: &Res<Gam
 fn update_item_frame(
     query: Query<&Actor, With<Player>>,
     mut frame_query: Query<(&Floating, &mut ItemPanel)>,
+    slots: Res<SaveSlots>,
 ) {
     if let Ok(actor) = query.get_single() {
         let (floating, mut panel) = frame_query.single_mut();
-        panel.0 = floating.content.and_then(|f| f.get_item(actor));
+        let stash = &slots.current().stash;
+        panel.0 = floating.content.and_then(|f| f.get_item(actor, stash));
     }
 }
 
@@ -138,6 +145,8 @@ fn drop(
     camera_query: Query<(&Camera, &GlobalTransform), (With<Camera2d>, Without<Player>)>,
     map: Res<CurrentLevel>,
     mut se: EventWriter<SEEvent>,
+    mut slots: ResMut<SaveSlots>,
+    mut save_writer: EventWriter<SaveRunEvent>,
 ) {
     let mut floating = floating_query.single_mut();
     if mouse.just_released(MouseButton::Left) {
@@ -150,6 +159,9 @@ fn drop(
         floating.target = None;
 
         if let Ok(mut actor) = player_query.get_single_mut() {
+            // 変更がないときにスロットを保存しないよう、保管箱の中身を複製して操作します
+            let mut stash = slots.current().stash;
+
             if let Some(content) = content_optional {
                 let drop = drop_query.single();
 
@@ -169,9 +181,13 @@ fn drop(
                                         if chunk.get_tile_by_coords(pointer_in_world)
                                             == Tile::StoneTile
                                         {
-                                            if let Some(item) = content.get_inventory_item(&actor) {
+                                            if let Some(item) =
+                                                content.get_inventory_item(&actor, &stash)
+                                            {
                                                 let spells = content.get_wand_spells(&actor);
-                                                content.set_item(None, &spells, &mut actor, false);
+                                                content.set_item(
+                                                    None, &spells, &mut actor, &mut stash, false,
+                                                );
 
                                                 spawn_dropped_item(
                                                     &mut commands,
@@ -191,37 +207,66 @@ fn drop(
                     }
                 } else if let Some(target) = target_optional {
                     // 移動元のアイテムを取得
-                    let item_optional_from = content.get_inventory_item(&actor);
+                    let item_optional_from = content.get_inventory_item(&actor, &stash);
                     // 移動先のアイテムを取得
-                    let item_optional_to = target.get_inventory_item(&actor);
+                    let item_optional_to = target.get_inventory_item(&actor, &stash);
                     // 移動元が杖の場合は杖に含まれている魔法を取得
                     let spells_from = content.get_wand_spells(&actor);
 
                     let spells_to = target.get_wand_spells(&actor);
 
                     // 移動先に書きこみ
-                    let ok_target =
-                        target.set_item(item_optional_from, &spells_from, &mut actor, true);
+                    let ok_target = target.set_item(
+                        item_optional_from,
+                        &spells_from,
+                        &mut actor,
+                        &mut stash,
+                        true,
+                    );
                     // 移動元に書きこみ
-                    let ok_content =
-                        content.set_item(item_optional_to, &spells_to, &mut actor, true);
+                    let ok_content = content.set_item(
+                        item_optional_to,
+                        &spells_to,
+                        &mut actor,
+                        &mut stash,
+                        true,
+                    );
 
                     if ok_target && ok_content {
                         // 移動先に書きこみ
-                        target.set_item(item_optional_from, &spells_from, &mut actor, false);
+                        target.set_item(
+                            item_optional_from,
+                            &spells_from,
+                            &mut actor,
+                            &mut stash,
+                            false,
+                        );
                         // 移動元に書きこみ
-                        content.set_item(item_optional_to, &spells_to, &mut actor, false);
+                        content.set_item(
+                            item_optional_to,
+                            &spells_to,
+                            &mut actor,
+                            &mut stash,
+                            false,
+                        );
                     }
                 }
             }
+
+            // 保管箱の中身を変えたときは、預けたアイテムが中断した周回にも残らないよう周回も保存します
+            if stash != slots.current().stash {
+                slots.current_mut().stash = stash;
+                save_writer.send(SaveRunEvent);
+            }
         }
     }
 }
 
 impl FloatingContent {
-    pub fn get_inventory_item(&self, actor: &Actor) -> Option<InventoryItem> {
+    pub fn get_inventory_item(&self, actor: &Actor, stash: &Inventory) -> Option<InventoryItem> {
         match self {
             FloatingContent::Inventory(i) => actor.inventory.get(*i),
+            FloatingContent::Stash(i) => stash.get(*i),
             FloatingContent::WandSpell(w, i) => actor.get_spell(*w, *i).map(|w| InventoryItem {
                 item_type: InventoryItemType::Spell(w.spell_type),
                 price: w.price,
@@ -261,9 +306,26 @@ impl FloatingContent {
         item: Option<InventoryItem>,
         slots: &[Option<WandSpell>; MAX_SPELLS_IN_WAND],
         actor: &mut Actor,
+        stash: &mut Inventory,
         dry_run: bool,
     ) -> bool {
         match (self, item) {
+            // 未清算の商品を預けて支払いを逃れることはできません
+            (FloatingContent::Stash(_), Some(InventoryItem { price, .. })) if 0 < price => false,
+            (FloatingContent::Stash(i), _) => {
+                if !dry_run {
+                    stash.set(*i, item);
+                    for spell in slots.iter() {
+                        if let Some(spell) = spell {
+                            actor.inventory.insert(InventoryItem {
+                                item_type: InventoryItemType::Spell(spell.spell_type),
+                                price: spell.price,
+                            });
+                        }
+                    }
+                }
+                true
+            }
             (FloatingContent::Inventory(i), _) => {
                 if !dry_run {
                     actor.inventory.set(*i, item);
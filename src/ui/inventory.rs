@@ -2,6 +2,7 @@ use crate::asset::GameAssets;
 use crate::constant::MAX_ITEMS_IN_INVENTORY;
 use crate::controller::player::Player;
 use crate::entity::actor::Actor;
+use crate::save::SaveSlots;
 use crate::states::GameState;
 use crate::ui::floating::{Floating, FloatingContent};
 use crate::ui::item_panel::{spawn_item_panel, ItemPanel};
@@ -14,12 +15,27 @@ pub struct InventoryGrid {
     pub hover: bool,
 }
 
+/// グリッドのスロットです
+/// プレイヤーのインベントリと保管箱のどちらのスロットかを FloatingContent で表します
 #[derive(Component)]
-struct InventoryItemSlot(usize);
+struct InventoryItemSlot(FloatingContent);
 
 pub const INVENTORY_IMAGE_HEIGHT: f32 = 168.0;
 
 pub fn spawn_inventory(builder: &mut ChildBuilder, assets: &Res<GameAssets>) {
+    spawn_item_grid(builder, assets, FloatingContent::Inventory);
+}
+
+/// 保管箱の中身を、インベントリと同じ形のグリッドで表示します
+pub fn spawn_stash_grid(builder: &mut ChildBuilder, assets: &Res<GameAssets>) {
+    spawn_item_grid(builder, assets, FloatingContent::Stash);
+}
+
+fn spawn_item_grid(
+    builder: &mut ChildBuilder,
+    assets: &Res<GameAssets>,
+    content: fn(usize) -> FloatingContent,
+) {
     builder
         .spawn((Node {
             width: Val::Px(151.0 * 2.0),
@@ -63,7 +79,7 @@ pub fn spawn_inventory(builder: &mut ChildBuilder, assets: &Res<GameAssets>) {
                         spawn_item_panel(
                             &mut builder,
                             &assets,
-                            InventoryItemSlot(i),
+                            InventoryItemSlot(content(i)),
                             (i % 8) as f32 * 32.0,
                             (i / 8) as f32 * 32.0,
                             None,
@@ -78,17 +94,15 @@ fn update_inventory_slot(
     player_query: Query<&Actor, With<Player>>,
     mut slot_query: Query<(&InventoryItemSlot, &mut ItemPanel)>,
     floating_query: Query<&Floating>,
+    slots: Res<SaveSlots>,
 ) {
     if let Ok(actor) = player_query.get_single() {
         let floating = floating_query.single();
         for (slot, mut panel) in slot_query.iter_mut() {
-            match floating.content {
-                Some(FloatingContent::Inventory(i)) if i == slot.0 => {
-                    panel.0 = None;
-                }
-                _ => {
-                    panel.0 = actor.inventory.get(slot.0);
-                }
+            if floating.content == Some(slot.0) {
+                panel.0 = None;
+            } else {
+                panel.0 = slot.0.get_item(actor, &slots.current().stash);
             }
         }
     }
@@ -102,11 +116,11 @@ fn interaction(
     let mut popup = popup_query.single_mut();
     let mut floating = floating_query.single_mut();
     for (slot, interaction) in &mut interaction_query {
-        let content = FloatingContent::Inventory(slot.0);
+        let content = slot.0;
         match *interaction {
             Interaction::Pressed => match floating.content {
                 None => {
-                    floating.content = Some(FloatingContent::Inventory(slot.0));
+                    floating.content = Some(content);
                 }
                 _ => {}
             },
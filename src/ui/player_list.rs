@@ -1,8 +1,10 @@
 use crate::{
     asset::GameAssets,
-    controller::{player::Player, remote::RemotePlayer},
+    controller::{
+        player::Player,
+        remote::{IncompatiblePeers, RemotePlayer},
+    },
     entity::actor::Actor,
-    level::{CurrentLevel, GameLevel},
     states::GameState,
 };
 use bevy::prelude::*;
@@ -23,6 +25,9 @@ struct PlayersLabel;
 #[derive(Component)]
 struct RemotePlayerListItem;
 
+#[derive(Component)]
+struct IncompatibleLabel;
+
 fn spawn_player_list(mut commands: Commands, assets: Res<GameAssets>) {
     commands
         .spawn((
@@ -70,21 +75,32 @@ fn spawn_player_list(mut commands: Commands, assets: Res<GameAssets>) {
                     ..default()
                 },
             ));
+
+            parent.spawn((
+                IncompatibleLabel,
+                Text::new(""),
+                TextColor(Color::hsl(0.0, 1.0, 0.6)),
+                TextFont {
+                    font: assets.dotgothic.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
         });
 }
 
+/// 接続している間はアリーナに限らずダンジョンでもプレイヤーリストを表示します
 fn update_player_list_visibility(
     mut player_list_query: Query<&mut Node, With<PlayerListRoot>>,
-    current: Res<CurrentLevel>,
+    state: Res<WebSocketState>,
 ) {
     let mut player_list_root = player_list_query.single_mut();
 
-    player_list_root.display =
-        if current.level != None && current.level == Some(GameLevel::MultiPlayArena) {
-            Display::Flex
-        } else {
-            Display::None
-        };
+    player_list_root.display = if state.ready_state == ReadyState::OPEN {
+        Display::Flex
+    } else {
+        Display::None
+    };
 }
 
 /// プレイヤーリストを更新
@@ -189,6 +205,34 @@ fn update_ready_state_label(
     }
 }
 
+/// プロトコルのバージョンが異なるために無視しているクライアントを表示
+fn update_incompatible_label(
+    mut label_query: Query<&mut Text, With<IncompatibleLabel>>,
+    incompatible: Res<IncompatiblePeers>,
+) {
+    if !incompatible.is_changed() {
+        return;
+    }
+    if let Ok(mut label) = label_query.get_single_mut() {
+        let mut lines: Vec<String> = incompatible
+            .peers
+            .iter()
+            .map(|(sender, version)| match sender {
+                Some(sender) => format!(
+                    "Incompatible client {} (protocol v{})",
+                    &sender.to_string()[..8],
+                    version
+                ),
+                None => format!("Incompatible client (protocol v{})", version),
+            })
+            .collect();
+        if incompatible.unknown {
+            lines.push("Incompatible client (unknown protocol)".to_string());
+        }
+        label.0 = lines.join("\n");
+    }
+}
+
 pub struct PlayerListPlugin;
 
 impl Plugin for PlayerListPlugin {
@@ -201,6 +245,7 @@ impl Plugin for PlayerListPlugin {
                 update_player_list_visibility,
                 update_ready_state_label,
                 update_players,
+                update_incompatible_label,
             )
                 .run_if(in_state(GameState::InGame)),
         );
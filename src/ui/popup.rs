@@ -1,6 +1,7 @@
 use crate::constant::WAND_EDITOR_Z_INDEX;
 use crate::controller::player::Player;
 use crate::entity::actor::Actor;
+use crate::save::SaveSlots;
 use crate::ui::floating::Floating;
 use crate::ui::floating::FloatingContent;
 use crate::ui::wand_editor::MENU_THEME_COLOR;
@@ -111,6 +112,7 @@ fn update_spell_icon(
     popup_query: Query<&PopUp>,
     floating_query: Query<&Floating>,
     actor_query: Query<&Actor, With<Player>>,
+    slots: Res<SaveSlots>,
 ) {
     let floating = floating_query.single();
     if floating.content.is_some() {
@@ -121,7 +123,7 @@ fn update_spell_icon(
         let mut slice = query.single_mut();
         let popup = popup_query.single();
         if let Some(first) = popup.set.iter().next() {
-            match first.get_item(actor) {
+            match first.get_item(actor, &slots.current().stash) {
                 Some(item) => {
                     let props = item.item_type.to_props();
                     slice.name = props.icon.into();
@@ -138,6 +140,7 @@ fn update_spell_name(
     config: Res<GameConfig>,
     floating_query: Query<&Floating>,
     actor_query: Query<&Actor, With<Player>>,
+    slots: Res<SaveSlots>,
 ) {
     let floating = floating_query.single();
     if floating.content.is_some() {
@@ -148,7 +151,7 @@ fn update_spell_name(
         let mut text = query.single_mut();
         let popup = popup_query.single();
         let first = popup.set.iter().next();
-        if let Some(first) = first.and_then(|f| f.get_item(actor)) {
+        if let Some(first) = first.and_then(|f| f.get_item(actor, &slots.current().stash)) {
             text.0 = first.item_type.to_props().name.get(config.language);
         }
     }
@@ -160,6 +163,7 @@ fn update_spell_description(
     floating_query: Query<&Floating>,
     actor_query: Query<&Actor, With<Player>>,
     popup_query: Query<&PopUp>,
+    slots: Res<SaveSlots>,
 ) {
     let floating = floating_query.single();
     let popup = popup_query.single();
@@ -169,7 +173,7 @@ fn update_spell_description(
     let mut text = query.single_mut();
     if let Ok(actor) = actor_query.get_single() {
         let first = popup.set.iter().next();
-        if let Some(first) = first.and_then(|f| f.get_item(actor)) {
+        if let Some(first) = first.and_then(|f| f.get_item(actor, &slots.current().stash)) {
             text.0 = first.item_type.to_props().description.get(config.language);
             if 0 < first.price {
                 text.0 += &format!("\n未清算:{}ゴールド", first.price);
@@ -182,6 +186,7 @@ fn update_visible(
     mut popup_query: Query<(&mut PopUp, &mut Node)>,
     floating_query: Query<&Floating>,
     actor_query: Query<&Actor, With<Player>>,
+    slots: Res<SaveSlots>,
 ) {
     let (mut popup, mut popup_node) = popup_query.single_mut();
     let floating = floating_query.single();
@@ -189,7 +194,7 @@ fn update_visible(
     if let Ok(actor) = actor_query.get_single() {
         if popup.set.is_empty() {
         } else if let Some(first) = popup.set.iter().next() {
-            if let Some(_) = first.get_item(actor) {
+            if let Some(_) = first.get_item(actor, &slots.current().stash) {
                 visible = if floating.content == None {
                     true
                 } else {
@@ -0,0 +1,133 @@
+use crate::{
+    asset::GameAssets,
+    config::GameConfig,
+    controller::{
+        player::Player,
+        servant::{Servant, ServantOrder},
+    },
+    entity::life::Life,
+    language::Dict,
+    states::GameState,
+};
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct ServantList;
+
+#[derive(Component)]
+struct ServantListItem;
+
+/// プレイヤーの使い魔の一覧を表示します
+pub fn spawn_servant_list(parent: &mut ChildBuilder) {
+    parent.spawn((
+        ServantList,
+        Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            ..default()
+        },
+    ));
+}
+
+/// 使い魔の一覧を更新
+fn update_servant_list(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    config: Res<GameConfig>,
+    player_query: Query<Entity, With<Player>>,
+    servant_query: Query<(&Servant, &Life, &Name)>,
+    list_query: Query<Entity, With<ServantList>>,
+    mut item_query: Query<(Entity, &mut Text), With<ServantListItem>>,
+) {
+    let Ok(parent) = list_query.get_single() else {
+        return;
+    };
+
+    let mut servants: Vec<(&Servant, &Life, &Name)> = match player_query.get_single() {
+        Ok(player) => servant_query
+            .iter()
+            .filter(|(servant, life, _)| servant.master == player && 0 < life.life)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    servants.sort_by_key(|(servant, _, _)| servant.serial);
+
+    // 必要な個数だけListItemを生成
+    let diff = servants.len() as i32 - item_query.iter().len() as i32;
+    if 0 < diff {
+        for _ in 0..diff {
+            commands.entity(parent).with_children(|parent| {
+                parent.spawn((
+                    ServantListItem,
+                    Text::new(""),
+                    TextColor(Color::hsla(0.0, 0.0, 1.0, 0.7)),
+                    TextFont {
+                        font: assets.dotgothic.clone(),
+                        font_size: 12.0,
+                        ..default()
+                    },
+                ));
+            });
+        }
+    } else if diff < 0 {
+        for (item_entity, _) in item_query.iter().skip(servants.len()) {
+            commands.entity(item_entity).despawn_recursive();
+        }
+    }
+
+    for ((servant, life, name), (_, mut text)) in servants.iter().zip(item_query.iter_mut()) {
+        text.0 = format!(
+            "{} {}/{} [{}]",
+            servant_name(name.as_str()).get(config.language),
+            life.life,
+            life.max_life,
+            order_name(servant.order).get(config.language)
+        );
+    }
+}
+
+fn servant_name(name: &str) -> Dict<&'static str> {
+    match name {
+        "slime" => Dict {
+            ja: "スライム",
+            en: "Slime",
+        },
+        "eyeball" => Dict {
+            ja: "アイボール",
+            en: "Eyeball",
+        },
+        _ => Dict {
+            ja: "使い魔",
+            en: "Servant",
+        },
+    }
+}
+
+fn order_name(order: ServantOrder) -> Dict<&'static str> {
+    match order {
+        ServantOrder::Follow => Dict {
+            ja: "追従",
+            en: "Follow",
+        },
+        ServantOrder::Hold(_) => Dict {
+            ja: "待機",
+            en: "Hold",
+        },
+        ServantOrder::Attack(_) => Dict {
+            ja: "攻撃",
+            en: "Attack",
+        },
+    }
+}
+
+pub struct ServantListPlugin;
+
+impl Plugin for ServantListPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_servant_list.run_if(in_state(GameState::InGame)),
+        );
+    }
+}
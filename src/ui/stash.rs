@@ -0,0 +1,71 @@
+use super::inventory::{spawn_stash_grid, INVENTORY_IMAGE_HEIGHT};
+use crate::{
+    asset::GameAssets,
+    constant::WAND_EDITOR_Z_INDEX,
+    controller::player::Player,
+    entity::stash::{Stash, STASH_RANGE},
+    states::{GameMenuState, GameState},
+};
+use bevy::prelude::*;
+
+#[derive(Component)]
+struct StashPanel;
+
+/// 保管箱の中身を表示するパネルを生成します
+/// 保管箱の近くでインベントリを開いたときだけ表示され、インベントリとの間でアイテムをドラッグして移動できます
+pub fn spawn_stash_panel(builder: &mut ChildBuilder, assets: &Res<GameAssets>) {
+    builder
+        .spawn((
+            StashPanel,
+            GlobalZIndex(WAND_EDITOR_Z_INDEX),
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(20.0),
+                top: Val::Px(100.0),
+                width: Val::Px(151.0 * 2.0),
+                height: Val::Px(INVENTORY_IMAGE_HEIGHT * 2.0),
+                display: Display::None,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            spawn_stash_grid(parent, assets);
+        });
+}
+
+fn update_stash_panel_visibility(
+    mut panel_query: Query<&mut Node, With<StashPanel>>,
+    player_query: Query<&Transform, With<Player>>,
+    stash_query: Query<&Transform, With<Stash>>,
+    state: Res<State<GameMenuState>>,
+) {
+    let nearby = match player_query.get_single() {
+        Ok(player) => stash_query.iter().any(|stash| {
+            player
+                .translation
+                .truncate()
+                .distance(stash.translation.truncate())
+                < STASH_RANGE
+        }),
+        Err(_) => false,
+    };
+    let visible = nearby && *state.get() == GameMenuState::WandEditOpen;
+    for mut node in panel_query.iter_mut() {
+        node.display = if visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+pub struct StashPanelPlugin;
+
+impl Plugin for StashPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_stash_panel_visibility.run_if(in_state(GameState::InGame)),
+        );
+    }
+}
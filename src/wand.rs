@@ -1,19 +1,32 @@
 use crate::{constant::MAX_SPELLS_IN_WAND, spell::SpellType};
 use bevy::reflect::Reflect;
+use serde::{Deserialize, Serialize};
 
-#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Reflect,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum::EnumIter,
+    Serialize,
+    Deserialize,
+)]
 pub enum WandType {
     CypressWand,
     KeyWand,
 }
 
-#[derive(Reflect, Clone, Copy, Debug)]
+#[derive(Reflect, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct WandSpell {
     pub spell_type: SpellType,
     pub price: u32,
 }
 
-#[derive(Reflect, Clone, Debug)]
+#[derive(Reflect, Clone, Debug, Serialize, Deserialize)]
 pub struct Wand {
     pub wand_type: WandType,
     pub price: u32,
@@ -0,0 +1,272 @@
+// ローカルの中継サーバーを起動し、ヘッドレスのクライアント同士で RemoteMessage をやり取りできることを確かめます
+#![cfg(all(not(target_arch = "wasm32"), feature = "server"))]
+
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy_simple_websocket::{
+    ClientMessage, ReadyState, ServerMessage, WebSocketPlugin, WebSocketState,
+};
+use futures_util::{SinkExt, StreamExt};
+use magiaforge::protocol::{decode, encode};
+use magiaforge::relay::{run_relay, RelayConfig, JOINED_PING};
+use magiaforge::{send_remote_message, RemoteMessage};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn start_relay(config: RelayConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(run_relay(listener, config));
+    address
+}
+
+/// 接続し、サーバーがルームへの登録を完了したことを知らせる Ping を受信するまで待ちます
+async fn connect(address: SocketAddr, room: &str) -> Client {
+    let (mut client, _) = connect_async(format!("ws://{}/{}", address, room))
+        .await
+        .unwrap();
+    loop {
+        match tokio::time::timeout(Duration::from_secs(5), client.next()).await {
+            Ok(Some(Ok(Message::Ping(payload)))) if payload == JOINED_PING => return client,
+            Ok(Some(Ok(_))) => continue,
+            other => panic!("failed to join {}: {:?}", room, other),
+        }
+    }
+}
+
+async fn send(client: &mut Client, message: &RemoteMessage) {
+    client.send(Message::Binary(encode(message))).await.unwrap();
+}
+
+/// 指定した時間内に RemoteMessage を受信できなければ None を返します
+async fn receive(client: &mut Client, timeout: Duration) -> Option<RemoteMessage> {
+    loop {
+        match tokio::time::timeout(timeout, client.next()).await {
+            Ok(Some(Ok(Message::Binary(bin)))) => {
+                let (_, message) = decode(&bin).unwrap();
+                return Some(message);
+            }
+            Ok(Some(Ok(_))) => continue,
+            _ => return None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn relay_between_clients_in_same_room() {
+    let address = start_relay(RelayConfig::default()).await;
+    let mut alice = connect(address, "arena").await;
+    let mut bob = connect(address, "arena").await;
+    let mut carol = connect(address, "another").await;
+
+    let alice_uuid = Uuid::new_v4();
+    let bob_uuid = Uuid::new_v4();
+
+    send(
+        &mut alice,
+        &RemoteMessage::Hit {
+            sender: alice_uuid,
+            uuid: bob_uuid,
+            damage: 5,
+        },
+    )
+    .await;
+    match receive(&mut bob, Duration::from_secs(5)).await {
+        Some(RemoteMessage::Hit {
+            sender,
+            uuid,
+            damage,
+        }) => {
+            assert_eq!(sender, alice_uuid);
+            assert_eq!(uuid, bob_uuid);
+            assert_eq!(damage, 5);
+        }
+        other => panic!("unexpected message: {:?}", other),
+    }
+
+    send(
+        &mut bob,
+        &RemoteMessage::Die {
+            sender: bob_uuid,
+            uuid: bob_uuid,
+            killer: Some(alice_uuid),
+        },
+    )
+    .await;
+    match receive(&mut alice, Duration::from_secs(5)).await {
+        Some(RemoteMessage::Die { uuid, killer, .. }) => {
+            assert_eq!(uuid, bob_uuid);
+            assert_eq!(killer, Some(alice_uuid));
+        }
+        other => panic!("unexpected message: {:?}", other),
+    }
+
+    // 送信者自身や、別のルームのクライアントには転送されません
+    let timeout = Duration::from_millis(200);
+    assert!(receive(&mut alice, timeout).await.is_none());
+    assert!(receive(&mut bob, timeout).await.is_none());
+    assert!(receive(&mut carol, timeout).await.is_none());
+}
+
+#[tokio::test]
+async fn relay_with_latency() {
+    let latency = Duration::from_millis(300);
+    let address = start_relay(RelayConfig { latency, loss: 0.0 }).await;
+    let mut alice = connect(address, "arena").await;
+    let mut bob = connect(address, "arena").await;
+
+    let uuid = Uuid::new_v4();
+    let start = Instant::now();
+    send(
+        &mut alice,
+        &RemoteMessage::MonsterDie { sender: uuid, uuid },
+    )
+    .await;
+    assert!(receive(&mut bob, Duration::from_secs(5)).await.is_some());
+    assert!(latency <= start.elapsed());
+}
+
+#[tokio::test]
+async fn keep_order_with_latency() {
+    let latency = Duration::from_millis(100);
+    let address = start_relay(RelayConfig { latency, loss: 0.0 }).await;
+    let mut alice = connect(address, "arena").await;
+    let mut bob = connect(address, "arena").await;
+
+    let sender = Uuid::new_v4();
+    let uuids: Vec<Uuid> = (0..20).map(|_| Uuid::new_v4()).collect();
+    for uuid in uuids.iter() {
+        send(
+            &mut alice,
+            &RemoteMessage::MonsterDie {
+                sender,
+                uuid: *uuid,
+            },
+        )
+        .await;
+    }
+    for expected in uuids.iter() {
+        match receive(&mut bob, Duration::from_secs(5)).await {
+            Some(RemoteMessage::MonsterDie { uuid, .. }) => assert_eq!(uuid, *expected),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn relay_with_full_loss() {
+    let address = start_relay(RelayConfig {
+        latency: Duration::ZERO,
+        loss: 1.0,
+    })
+    .await;
+    let mut alice = connect(address, "arena").await;
+    let mut bob = connect(address, "arena").await;
+
+    let uuid = Uuid::new_v4();
+    send(
+        &mut alice,
+        &RemoteMessage::MonsterDie { sender: uuid, uuid },
+    )
+    .await;
+    assert!(receive(&mut bob, Duration::from_millis(300))
+        .await
+        .is_none());
+}
+
+/// ゲームのクライアントと同じく WebSocketPlugin で接続し、受信したメッセージを読み込んで記録するヘッドレスのアプリです
+#[derive(Resource, Default)]
+struct Received(Vec<RemoteMessage>);
+
+fn client_app(address: SocketAddr, room: &str) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, WebSocketPlugin));
+    app.init_resource::<Received>();
+    app.add_systems(Update, receive_messages);
+    app.world_mut()
+        .send_event(ClientMessage::Open(format!("ws://{}/{}", address, room)));
+    app
+}
+
+fn receive_messages(mut reader: EventReader<ServerMessage>, mut received: ResMut<Received>) {
+    for message in reader.read() {
+        if let ServerMessage::Binary(bin) = message {
+            if let Ok((_, message)) = decode(bin) {
+                received.0.push(message);
+            }
+        }
+    }
+}
+
+/// 条件を満たすまでアプリを更新します
+async fn update_until(app: &mut App, condition: impl Fn(&World) -> bool) {
+    let start = Instant::now();
+    while !condition(app.world()) {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+        app.update();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn relay_to_game_client() {
+    let address = start_relay(RelayConfig::default()).await;
+    let mut bob = connect(address, "arena").await;
+    let mut alice = client_app(address, "arena");
+    update_until(&mut alice, |world| {
+        world.resource::<WebSocketState>().ready_state == ReadyState::OPEN
+    })
+    .await;
+
+    // サーバーは接続したクライアントをルームに登録してからメッセージを読み込むので、
+    // bob にメッセージが届いた時点で alice もルームに登録されています
+    let alice_uuid = Uuid::new_v4();
+    let bob_uuid = Uuid::new_v4();
+    alice
+        .world_mut()
+        .run_system_once(move |mut writer: EventWriter<ClientMessage>| {
+            send_remote_message(
+                &mut writer,
+                true,
+                &RemoteMessage::Join {
+                    sender: alice_uuid,
+                    name: "alice".to_string(),
+                },
+            );
+        })
+        .unwrap();
+    alice.update();
+    match receive(&mut bob, Duration::from_secs(5)).await {
+        Some(RemoteMessage::Join { sender, name }) => {
+            assert_eq!(sender, alice_uuid);
+            assert_eq!(name, "alice");
+        }
+        other => panic!("unexpected message: {:?}", other),
+    }
+
+    send(
+        &mut bob,
+        &RemoteMessage::Hello {
+            sender: bob_uuid,
+            name: "bob".to_string(),
+        },
+    )
+    .await;
+    update_until(&mut alice, |world| {
+        !world.resource::<Received>().0.is_empty()
+    })
+    .await;
+    match &alice.world().resource::<Received>().0[..] {
+        [RemoteMessage::Hello { sender, name }] => {
+            assert_eq!(*sender, bob_uuid);
+            assert_eq!(name, "bob");
+        }
+        other => panic!("unexpected messages: {:?}", other),
+    }
+}